@@ -0,0 +1,99 @@
+//! the throughput of the request parsing and of a server on a local port, run with `cargo bench`.
+use std::time::{Duration, Instant};
+use tokio::{io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader}, net::TcpStream};
+use fake_rest::{server::request::{Request, RequestLimits}, ConfigFormat, ConfigSource, FakeRestServer};
+
+/// a request with the headers of a browser.
+const REQUEST: &[u8] = b"GET /users/42?fields=name,email HTTP/1.1\r\n\
+Host: 127.0.0.1\r\n\
+User-Agent: Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0\r\n\
+Accept: text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8\r\n\
+Accept-Language: en-US,en;q=0.5\r\n\
+Accept-Encoding: identity\r\n\
+Cookie: session=0123456789abcdef; theme=dark\r\n\
+Cache-Control: no-cache\r\n\
+Connection: keep-alive\r\n\r\n";
+
+const PARSED_REQUESTS: usize = 200_000;
+const CONNECTIONS: usize = 16;
+const REQUESTS_PER_CONNECTION: usize = 2_000;
+
+#[tokio::main]
+async fn main() {
+    parse().await;
+    serve().await;
+}
+
+/// parse the request from memory, without the cost of the socket.
+async fn parse() {
+    let started = Instant::now();
+    for _ in 0..PARSED_REQUESTS {
+        let mut reader = REQUEST;
+        Request::new(&mut reader, RequestLimits::default()).await.unwrap();
+    }
+    report("parse", PARSED_REQUESTS, started.elapsed());
+}
+
+/// keep-alive requests on a few connections to a running server.
+async fn serve() {
+    let log = std::env::temp_dir().join("fake-rest-bench.log");
+    let config = format!(r#"
+        [config]
+        host = "127.0.0.1"
+        port = 0
+        log = {{ file = "{}" }}
+
+        [[data]]
+        method = "GET"
+        path = "/users/{{id}}"
+        result_type = "direct"
+        result = '{{"id": 42, "name": "Jane"}}'
+    "#, log.display());
+    let server = FakeRestServer::start(ConfigSource::Text(config, ConfigFormat::Toml)).await.unwrap();
+    let addr = server.addr();
+
+    let started = Instant::now();
+    let clients: Vec<_> = (0..CONNECTIONS).map(|_| tokio::spawn(async move {
+        let mut socket = BufReader::new(TcpStream::connect(addr).await.unwrap());
+        for _ in 0..REQUESTS_PER_CONNECTION {
+            socket.get_mut().write_all(REQUEST).await.unwrap();
+            read_response(&mut socket).await;
+        }
+    })).collect();
+    for client in clients {
+        client.await.unwrap();
+    }
+    report("serve", CONNECTIONS * REQUESTS_PER_CONNECTION, started.elapsed());
+
+    server.stop().await;
+    let _ = std::fs::remove_file(log);
+}
+
+async fn read_response(socket: &mut BufReader<TcpStream>) {
+    let mut length = 0;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        socket.read_line(&mut line).await.unwrap();
+        if line == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+    }
+    let mut body = vec![0; length];
+    socket.read_exact(&mut body).await.unwrap();
+}
+
+fn report(name: &str, requests: usize, elapsed: Duration) {
+    println!(
+        "{:<6} {:>8} requests in {:>8.2?}, {:>10.0} requests/s",
+        name,
+        requests,
+        elapsed,
+        requests as f64 / elapsed.as_secs_f64()
+    );
+}
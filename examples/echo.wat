@@ -0,0 +1,26 @@
+;; a `wasm` plugin that answers with the request it gets, like
+;; `{"status":200,"headers":{"X-Plugin":"echo"},"body":{"method":"GET",...}}`.
+;; the plugins are usually compiled from rust or another language to `.wasm`, the text format is read too.
+(module
+  (memory (export "memory") 16)
+  ;; the start of the response, 51 bytes
+  (data (i32.const 0) "{\"status\":200,\"headers\":{\"X-Plugin\":\"echo\"},\"body\":")
+  (global $next (mut i32) (i32.const 1024))
+
+  ;; a bump allocator, every request gets a new instance
+  (func $alloc (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (local.get $ptr))
+
+  (func (export "handle") (param $ptr i32) (param $len i32) (result i64)
+    (local $out i32)
+    (local.set $out (call $alloc (i32.add (local.get $len) (i32.const 52))))
+    (memory.copy (local.get $out) (i32.const 0) (i32.const 51))
+    (memory.copy (i32.add (local.get $out) (i32.const 51)) (local.get $ptr) (local.get $len))
+    (i32.store8 (i32.add (i32.add (local.get $out) (i32.const 51)) (local.get $len)) (i32.const 125))
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $out)) (i64.const 32))
+      (i64.extend_i32_u (i32.add (local.get $len) (i32.const 52)))))
+)
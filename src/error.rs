@@ -3,27 +3,41 @@ use std::fmt::Display;
 pub type FakeRestResult = Result<(), Error>;
 pub type RequestParseResult = Result<crate::server::request::Request, Error>;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone)]
 pub enum Error {
     ConfigParsingError(String),
     ConfigFileOpenError(String),
-    ConfigRequiredQueriesError(String),
-    ConfigRequiredHeadersError(String),
+    ConfigValidationError(String),
     ParsingError(String),
     UTF8Error(String),
     IoError(String),
+    TemplateError(String),
+    ProxyError(String),
+    TlsError(String),
+    /// the client closed the connection before sending a new request
+    ConnectionClosed,
+    /// the client didn't send the whole request in the `read_timeout`
+    RequestTimeout,
+    /// a part of the request is bigger than its limit, with the status to answer it and the reason
+    RequestTooLarge(usize, String),
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::ConfigParsingError(e) => write!(f, "failed to parse a header! {}", e),
-            Error::ConfigRequiredQueriesError(e) => write!(f, "{}", e),
-            Error::ConfigRequiredHeadersError(e) => write!(f, "{}", e),
             Error::ParsingError(e) => write!(f, "{}", e),
             Error::UTF8Error(e) => write!(f, "{}", e),
             Error::IoError(e) => write!(f, "{}", e),
+            Error::TemplateError(e) => write!(f, "failed to render the template! {}", e),
+            Error::ProxyError(e) => write!(f, "failed to proxy the request! {}", e),
+            Error::TlsError(e) => write!(f, "tls error! {}", e),
+            Error::ConnectionClosed => write!(f, "the connection is closed by the client"),
+            Error::RequestTimeout => write!(f, "the client didn't send the whole request in time"),
+            Error::RequestTooLarge(_, e) => write!(f, "{}", e),
             Error::ConfigFileOpenError(e) => write!(f, "failed to open config file! {}", e),
+            Error::ConfigValidationError(e) => write!(f, "invalid config! {}", e),
         }
     }
 }
@@ -44,4 +58,16 @@ impl From<toml::de::Error> for Error {
     fn from(e: toml::de::Error) -> Self {
         Error::ConfigParsingError(e.to_string())
     }
-}
\ No newline at end of file
+}
+
+impl From<handlebars::RenderError> for Error {
+    fn from(e: handlebars::RenderError) -> Self {
+        Error::TemplateError(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::ProxyError(e.to_string())
+    }
+}
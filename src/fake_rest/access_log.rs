@@ -0,0 +1,85 @@
+use std::{sync::Arc, time::{Duration, SystemTime}};
+use serde::Deserialize;
+use crate::server::request::Request;
+use super::log_file::{self, Log, LogFile, Rotation};
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogFormat {
+    /// `host ident user [time] "request" status size`
+    Common,
+    /// the common format with the `"referer" "user-agent"` of the request
+    #[default]
+    Combined,
+}
+
+/// a line for every request in the Common or Combined Log Format, on the stdout or appended to a file.
+#[derive(Debug, Deserialize, Clone)]
+pub struct AccessLog {
+    #[serde(default)]
+    pub format: AccessLogFormat,
+    /// the file to append the lines to, default is the `log` of the server or the stdout
+    pub file: Option<String>,
+    #[serde(flatten)]
+    pub rotation: Rotation,
+    /// add the microseconds it took to answer the request at the end of the line, like `%D` of apache
+    #[serde(default)]
+    pub latency: bool,
+    /// the file is opened on the first request, it's shared between the clones of the config
+    #[serde(skip)]
+    writer: Arc<LogFile>,
+}
+
+impl AccessLog {
+
+    /// log a request that is answered with `status` and a body of `size` bytes.
+    pub async fn write(&self, request: &Request, status: usize, size: usize, elapsed: Duration, log: Option<&Log>) {
+        let line = self.format_line(request, status, size, elapsed);
+        match &self.file {
+            Some(path) => self.writer.append(path, &self.rotation, &line).await,
+            None => log_file::output(log, &line).await,
+        }
+    }
+
+    fn format_line(&self, request: &Request, status: usize, size: usize, elapsed: Duration) -> String {
+        let host = request.peer_ip.map(|ip| ip.to_string()).unwrap_or_else(|| "-".to_string());
+        // the size of an empty body is `-` in the common log format
+        let size = if size == 0 { "-".to_string() } else { size.to_string() };
+        let mut line = format!(
+            "{} - - [{}] \"{} {} {}\" {} {}",
+            host,
+            log_time(SystemTime::now()),
+            request.method,
+            escape(&request.raw_uri),
+            request.version,
+            status,
+            size
+        );
+        if self.format == AccessLogFormat::Combined {
+            let referer = request.header("Referer").map(|value| escape(value)).unwrap_or_else(|| "-".to_string());
+            let user_agent = request.header("User-Agent").map(|value| escape(value)).unwrap_or_else(|| "-".to_string());
+            line.push_str(&format!(" \"{}\" \"{}\"", referer, user_agent));
+        }
+        if self.latency {
+            line.push_str(&format!(" {}", elapsed.as_micros()));
+        }
+        line
+    }
+
+}
+
+/// the time like `10/Oct/2000:13:55:36 +0000`, it's made from the http date that is always in GMT.
+fn log_time(time: SystemTime) -> String {
+    // `Tue, 10 Oct 2000 13:55:36 GMT`
+    let http_date = httpdate::fmt_http_date(time);
+    let parts: Vec<&str> = http_date.split(' ').collect();
+    match parts.as_slice() {
+        [_, day, month, year, clock, _] => format!("{}/{}/{}:{} +0000", day, month, year, clock),
+        _ => http_date,
+    }
+}
+
+/// the quotes and the control characters of the values can't break the line.
+fn escape(value: &str) -> String {
+    value.escape_default().to_string()
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+use rand::Rng;
+use rand_distr::{Distribution as _, LogNormal, Normal};
+use serde::Deserialize;
+use crate::error::Error;
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Distribution {
+    #[default]
+    Uniform,
+    Normal,
+    LogNormal,
+}
+
+/// a random delay for the route.
+///
+/// `uniform` picks a value between `min_ms` and `max_ms`. `normal` and `lognormal` use
+/// `mean_ms` and `stddev_ms`, the result is clamped between `min_ms` and `max_ms` if they're set.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Delay {
+    #[serde(default)]
+    pub distribution: Distribution,
+    pub min_ms: Option<u64>,
+    pub max_ms: Option<u64>,
+    pub mean_ms: Option<f64>,
+    pub stddev_ms: Option<f64>,
+}
+
+impl Delay {
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if let (Some(min), Some(max)) = (self.min_ms, self.max_ms) {
+            if min > max {
+                return Err(Error::ConfigValidationError(
+                    format!("delay min_ms ({}) is bigger than max_ms ({})", min, max)
+                ));
+            }
+        }
+
+        match self.distribution {
+            Distribution::Uniform => {
+                if self.max_ms.is_none() {
+                    return Err(Error::ConfigValidationError(
+                        "uniform delay needs `max_ms`".to_string()
+                    ));
+                }
+            },
+            Distribution::Normal | Distribution::LogNormal => {
+                if self.mean_ms.is_none() || self.stddev_ms.is_none() {
+                    return Err(Error::ConfigValidationError(
+                        "normal and lognormal delays need `mean_ms` and `stddev_ms`".to_string()
+                    ));
+                }
+                if self.stddev_ms.unwrap_or(0.0) < 0.0 {
+                    return Err(Error::ConfigValidationError(
+                        "delay stddev_ms can't be negative".to_string()
+                    ));
+                }
+                if self.distribution == Distribution::LogNormal && self.mean_ms.unwrap_or(0.0) <= 0.0 {
+                    return Err(Error::ConfigValidationError(
+                        "lognormal delay needs a positive `mean_ms`".to_string()
+                    ));
+                }
+            },
+        }
+        Ok(())
+    }
+
+    pub fn sample(&self) -> Duration {
+        let mut rng = rand::rng();
+        let min = self.min_ms.unwrap_or(0) as f64;
+        let max = self.max_ms.map(|max| max as f64).unwrap_or(f64::MAX);
+        let mean = self.mean_ms.unwrap_or(0.0);
+        let stddev = self.stddev_ms.unwrap_or(0.0);
+
+        let millis = match self.distribution {
+            Distribution::Uniform => rng.random_range(min..=max),
+            Distribution::Normal => match Normal::new(mean, stddev) {
+                Ok(normal) => normal.sample(&mut rng),
+                Err(_) => mean,
+            },
+            Distribution::LogNormal => {
+                // convert the mean and stddev of the delay to the parameters of the underlying normal
+                let sigma = (1.0 + (stddev * stddev) / (mean * mean)).ln().sqrt();
+                let mu = mean.ln() - sigma * sigma / 2.0;
+                match LogNormal::new(mu, sigma) {
+                    Ok(log_normal) => log_normal.sample(&mut rng),
+                    Err(_) => mean,
+                }
+            },
+        };
+
+        Duration::from_millis(millis.clamp(min, max) as u64)
+    }
+
+}
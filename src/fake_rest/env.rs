@@ -0,0 +1,43 @@
+use std::sync::OnceLock;
+use regex::{Captures, Regex};
+
+/// replace the `${VAR}` and `${VAR:-default}` placeholders of the config with the environment variables,
+/// `$${VAR}` is kept as `${VAR}` and the `#` comment lines are left as they are.
+/// it returns the names of the missing variables without a default.
+pub fn interpolate(content: &str) -> Result<String, Vec<String>> {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    let placeholder = PLACEHOLDER
+        .get_or_init(|| Regex::new(r"\$(\$?)\{([A-Za-z_][A-Za-z0-9_]*)(:-([^}]*))?\}").unwrap());
+
+    let mut missing: Vec<String> = Vec::new();
+    let mut interpolated = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        if line.trim_start().starts_with('#') {
+            interpolated.push_str(line);
+            continue;
+        }
+        let line = placeholder.replace_all(line, |captures: &Captures| {
+            if !captures[1].is_empty() {
+                return captures[0][1..].to_string();
+            }
+            let name = &captures[2];
+            match (std::env::var(name), captures.get(4)) {
+                (Ok(value), _) => value,
+                (Err(_), Some(default)) => default.as_str().to_string(),
+                (Err(_), None) => {
+                    if !missing.iter().any(|item| item == name) {
+                        missing.push(name.to_string());
+                    }
+                    String::new()
+                },
+            }
+        });
+        interpolated.push_str(&line);
+    }
+
+    if missing.is_empty() {
+        Ok(interpolated)
+    }else {
+        Err(missing)
+    }
+}
@@ -0,0 +1,89 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::{Arc, Mutex}, time::SystemTime};
+use serde::Deserialize;
+
+/// the cached files take this many bytes at most by default.
+const DEFAULT_MAX_SIZE: usize = 64 * 1024 * 1024;
+
+/// keep the files of the `file` and `dl` results in memory, a file is read again when its
+/// modification time or size changes.
+#[derive(Debug, Deserialize, Clone)]
+pub struct FileCache {
+    /// the most bytes of all of the cached files, default is 64 MiB, the least recently used ones are dropped first
+    pub max_size: Option<usize>,
+    /// the files are shared between the clones of the config
+    #[serde(skip)]
+    files: Arc<Mutex<CachedFiles>>,
+}
+
+#[derive(Debug, Default)]
+struct CachedFiles {
+    files: HashMap<PathBuf, CachedFile>,
+    /// the bytes of all of the files
+    size: usize,
+    /// it goes up on every read, the file with the smallest `last_used` is the least recently used one
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct CachedFile {
+    content: Arc<Vec<u8>>,
+    modified: Option<SystemTime>,
+    last_used: u64,
+}
+
+impl FileCache {
+
+    /// the files bigger than `max_size` are not cached.
+    pub fn fits(&self, size: u64) -> bool {
+        size <= self.max_size.unwrap_or(DEFAULT_MAX_SIZE) as u64
+    }
+
+    /// the content of the file and its modification time, from the memory when the file is not changed.
+    pub async fn read(&self, path: &Path) -> std::io::Result<(Arc<Vec<u8>>, Option<SystemTime>)> {
+        let metadata = tokio::fs::metadata(path).await?;
+        let modified = metadata.modified().ok();
+        if let Some(content) = self.get(path, modified, metadata.len() as usize) {
+            return Ok((content, modified));
+        }
+
+        let content = Arc::new(tokio::fs::read(path).await?);
+        self.insert(path, content.clone(), modified);
+        Ok((content, modified))
+    }
+
+    fn get(&self, path: &Path, modified: Option<SystemTime>, size: usize) -> Option<Arc<Vec<u8>>> {
+        let mut cached = self.files.lock().unwrap();
+        cached.clock += 1;
+        let clock = cached.clock;
+        let file = cached.files.get_mut(path)?;
+        // the files without a modification time can't be checked
+        if modified.is_none() || file.modified != modified || file.content.len() != size {
+            return None;
+        }
+        file.last_used = clock;
+        Some(file.content.clone())
+    }
+
+    fn insert(&self, path: &Path, content: Arc<Vec<u8>>, modified: Option<SystemTime>) {
+        let max_size = self.max_size.unwrap_or(DEFAULT_MAX_SIZE);
+        let mut cached = self.files.lock().unwrap();
+        if let Some(old) = cached.files.remove(path) {
+            cached.size -= old.content.len();
+        }
+        if content.len() > max_size {
+            return;
+        }
+        while cached.size + content.len() > max_size {
+            let oldest = cached.files.iter().min_by_key(|(_, file)| file.last_used).map(|(path, _)| path.clone());
+            match oldest.and_then(|oldest| cached.files.remove(&oldest)) {
+                Some(file) => cached.size -= file.content.len(),
+                None => break,
+            }
+        }
+        cached.clock += 1;
+        let last_used = cached.clock;
+        cached.size += content.len();
+        cached.files.insert(path.to_path_buf(), CachedFile { content, modified, last_used });
+    }
+
+}
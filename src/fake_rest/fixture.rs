@@ -0,0 +1,112 @@
+use std::{fmt::Write, ops::Deref};
+use crate::error::Error;
+use super::{instance::FakeRestServer, journal::RequestFilter, server_config::{ConfigFormat, ConfigSource}};
+
+/// start a `TestServer` from a toml snippet in an async test, the test fails when it can't start:
+///
+/// ```ignore
+/// let mut server = fake_rest::test_server!(r#"
+///     [[data]]
+///     method = "GET"
+///     path = "/hello"
+///     result_type = "direct"
+///     result = "hi"
+/// "#);
+/// server.expect("GET", "/hello", 1);
+/// let body = reqwest::get(server.url_for("/hello")).await.unwrap().text().await.unwrap();
+/// ```
+#[macro_export]
+macro_rules! test_server {
+    ($config:expr) => {
+        match $crate::TestServer::start($config).await {
+            Ok(server) => server,
+            Err(e) => panic!("can't start the fake-rest server: {}", e),
+        }
+    };
+}
+
+/// a server for a test, it's stopped at the end of the test and its expectations are verified then.
+pub struct TestServer {
+    server: FakeRestServer,
+    expectations: Vec<Expectation>,
+}
+
+struct Expectation {
+    method: String,
+    path: String,
+    times: usize,
+}
+
+impl TestServer {
+
+    /// start the server of a toml config, the snippets without `[config]` or `[[server]]` listen on a
+    /// free port of `127.0.0.1`. all of the requests are recorded for the expectations.
+    pub async fn start(config: &str) -> Result<TestServer, Error> {
+        let value: toml::Value = toml::from_str(config)?;
+        let mut config = config.to_string();
+        if value.get("config").is_none() && value.get("server").is_none() {
+            // a table at the end can't take the top level keys of the snippet
+            config.push_str("\n[config]\nhost = \"127.0.0.1\"\nport = 0\n");
+        }
+        let server = FakeRestServer::start(ConfigSource::Text(config, ConfigFormat::Toml)).await?;
+        server.server().journal.start_recording();
+        Ok(TestServer { server, expectations: Vec::new() })
+    }
+
+    /// the url of a path of the server, like `http://127.0.0.1:40123/users`.
+    pub fn url_for(&self, path: &str) -> String {
+        format!("{}{}", self.server.url(), path)
+    }
+
+    /// expect `times` requests of the method to the path by the end of the test, the path can be a route
+    /// path like `/users/{id}`.
+    pub fn expect(&mut self, method: &str, path: &str, times: usize) -> &mut TestServer {
+        self.expectations.push(Expectation { method: method.to_string(), path: path.to_string(), times });
+        self
+    }
+
+    /// panic with the expectations that are not met.
+    pub fn verify(&self) {
+        let journal = self.server.server().journal.clone();
+        let mut failures = String::new();
+        for expectation in self.expectations.iter() {
+            let filter = RequestFilter {
+                path: Some(expectation.path.clone()),
+                method: Some(expectation.method.clone()),
+                ..Default::default()
+            };
+            let count = journal.count(&filter);
+            if count != expectation.times {
+                let _ = writeln!(
+                    failures,
+                    "expected {} `{} {}` requests, received {}",
+                    expectation.times,
+                    expectation.method,
+                    expectation.path,
+                    count
+                );
+            }
+        }
+        if !failures.is_empty() {
+            panic!("the fake-rest expectations are not met:\n{}", failures);
+        }
+    }
+
+}
+
+impl Deref for TestServer {
+    type Target = FakeRestServer;
+
+    fn deref(&self) -> &FakeRestServer {
+        &self.server
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        // a failed test already panics with its own error
+        if !std::thread::panicking() {
+            self.verify();
+        }
+    }
+}
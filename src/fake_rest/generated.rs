@@ -0,0 +1,90 @@
+use serde::Serialize;
+use crate::error::Error;
+use super::server_config::ConfigFormat;
+
+/// a config written by the `record` and `import` commands.
+#[derive(Serialize)]
+pub struct GeneratedServer {
+    pub config: GeneratedConfig,
+    pub data: Vec<GeneratedRoute>,
+}
+
+#[derive(Serialize)]
+pub struct GeneratedConfig {
+    pub host: String,
+    pub port: usize,
+}
+
+#[derive(Serialize, Default, Clone)]
+pub struct GeneratedRoute {
+    pub method: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_regex: Option<String>,
+    pub result_type: String,
+    pub result: String,
+    pub status_code: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub result_headers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queries: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cookies: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_equals: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_contains: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fault: Option<String>,
+    /// the last one, toml writes the tables after the values
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_json: Option<serde_json::Value>,
+}
+
+impl GeneratedServer {
+
+    pub fn new(host: String, port: usize) -> GeneratedServer {
+        GeneratedServer { config: GeneratedConfig { host, port }, data: Vec::new() }
+    }
+
+    pub fn to_string(&self, format: ConfigFormat) -> Result<String, Error> {
+        match format {
+            // toml has no null and mixed arrays, the json bodies can have them
+            ConfigFormat::Toml => toml::to_string_pretty(self).map_err(|e| Error::ConfigParsingError(
+                format!("{}, write a `.json` config instead", e)
+            )),
+            ConfigFormat::Json => serde_json::to_string_pretty(self).map_err(|e| Error::ConfigParsingError(e.to_string())),
+        }
+    }
+
+}
+
+/// the name of a body file of the `record` and `import` commands, like `3_get_users_1.json`.
+pub fn body_file_name(index: usize, method: &str, path: &str, content_type: &str) -> String {
+    format!(
+        "{}_{}{}.{}",
+        index,
+        method.to_lowercase(),
+        path.replace(|c: char| !c.is_ascii_alphanumeric(), "_"),
+        get_extension(content_type),
+    )
+}
+
+fn get_extension(content_type: &str) -> &str {
+    if content_type.contains("json") {
+        "json"
+    }else if content_type.contains("html") {
+        "html"
+    }else if content_type.contains("xml") {
+        "xml"
+    }else if content_type.starts_with("text/") {
+        "txt"
+    }else {
+        "bin"
+    }
+}
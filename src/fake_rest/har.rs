@@ -0,0 +1,89 @@
+use std::path::Path;
+use base64::Engine;
+use serde_json::Value;
+use crate::error::Error;
+use crate::server::helpers;
+use super::generated::{self, GeneratedRoute};
+
+/// headers of the recorded responses that are not worth keeping, the bodies of the HAR files are already decoded.
+const SKIPPED_HEADERS: [&str; 6] = ["content-length", "content-encoding", "transfer-encoding", "connection", "date", "server"];
+
+/// a route for every request of the HAR file, like the ones exported by the browsers.
+///
+/// the text bodies are kept in the config and the base64 ones are written in the `bodies` directory
+/// next to the `output`. the responses keep their recorded status, the repeated requests keep the response
+/// of their first one and the requests without a response, like the blocked ones with the status 0, are skipped.
+pub async fn routes(document: &Value, output: &Path) -> Result<Vec<GeneratedRoute>, Error> {
+    let entries = document.pointer("/log/entries").and_then(Value::as_array).into_iter().flatten();
+    let bodies = output.parent().unwrap_or(Path::new("")).join("bodies");
+
+    let mut routes: Vec<GeneratedRoute> = Vec::new();
+    for entry in entries {
+        let request = &entry["request"];
+        let response = &entry["response"];
+        let status_code = response["status"].as_u64().unwrap_or(0) as usize;
+        let url = match request["url"].as_str().and_then(|url| reqwest::Url::parse(url).ok()) {
+            Some(url) if (100..600).contains(&status_code) => url,
+            _ => continue,
+        };
+
+        let method = request["method"].as_str().unwrap_or("GET").to_uppercase();
+        let path = helpers::percent_decode(url.path(), false);
+        let queries: Vec<String> = url
+            .query_pairs()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        let queries = if queries.is_empty() { None } else { Some(queries) };
+        let body_equals = request
+            .pointer("/postData/text")
+            .and_then(Value::as_str)
+            .filter(|text| !text.is_empty())
+            .map(str::to_string);
+
+        let is_repeated = routes.iter().any(|route| {
+            route.method == method && route.path == path && route.queries == queries && route.body_equals == body_equals
+        });
+        if is_repeated {
+            continue;
+        }
+
+        let result_headers = response["headers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|header| Some((header["name"].as_str()?, header["value"].as_str()?)))
+            // http/2 pseudo headers like `:status`
+            .filter(|(name, _)| !name.starts_with(':'))
+            .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.to_lowercase().as_str()))
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect();
+
+        let content = &response["content"];
+        let text = content["text"].as_str().unwrap_or("");
+        let (result_type, result) = if content["encoding"].as_str() == Some("base64") {
+            let body = base64::engine::general_purpose::STANDARD.decode(text.trim()).map_err(|e| Error::ParsingError(
+                format!("the base64 body of `{} {}` is invalid: {}", method, url, e)
+            ))?;
+            let mime_type = content["mimeType"].as_str().unwrap_or("");
+            tokio::fs::create_dir_all(&bodies).await?;
+            let body_path = bodies.join(generated::body_file_name(routes.len(), &method, &path, mime_type));
+            tokio::fs::write(&body_path, body).await?;
+            ("file", body_path.to_string_lossy().to_string())
+        }else {
+            ("direct", text.to_string())
+        };
+
+        routes.push(GeneratedRoute {
+            method,
+            path,
+            result_type: result_type.to_string(),
+            result,
+            status_code,
+            result_headers,
+            queries,
+            body_equals,
+            ..Default::default()
+        });
+    }
+    Ok(routes)
+}
@@ -0,0 +1,81 @@
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+use crate::error::{Error, FakeRestResult};
+use super::{generated::GeneratedServer, har, openapi, server_config::ConfigFormat, wiremock};
+
+/// write the routes of an OpenAPI 3 or Swagger 2 document, a HAR file or WireMock mappings to the `output`
+/// config, the kind of the file is detected from its content. a directory is read as WireMock mappings.
+pub async fn run(file: PathBuf, output: PathBuf, host: String, port: usize) -> FakeRestResult {
+    let (kind, routes) = if file.is_dir() {
+        let mut stubs = Vec::new();
+        for path in mapping_files(&file).await? {
+            let document = read_document(&path).await?;
+            if wiremock::is_mapping(&document) {
+                stubs.extend(wiremock::mappings(&document));
+            }
+        }
+        ("WireMock", wiremock::routes(stubs, mappings_root(&file), &output).await?)
+    }else {
+        let document = read_document(&file).await?;
+        if document.pointer("/log/entries").is_some() {
+            ("HAR", har::routes(&document, &output).await?)
+        }else if document.get("openapi").or_else(|| document.get("swagger")).is_some() {
+            ("OpenAPI", openapi::routes(&document)?)
+        }else if wiremock::is_mapping(&document) {
+            let dir = file.parent().unwrap_or(Path::new(""));
+            ("WireMock", wiremock::routes(wiremock::mappings(&document), mappings_root(dir), &output).await?)
+        }else {
+            return Err(Error::ParsingError(
+                format!("`{}` is not an OpenAPI document, a HAR file or a WireMock mapping", file.display())
+            ));
+        }
+    };
+
+    let mut server = GeneratedServer::new(host, port);
+    server.data = routes;
+    let format = ConfigFormat::from_path(&output).unwrap_or(ConfigFormat::Toml);
+    tokio::fs::write(&output, server.to_string(format)?).await?;
+    println!(
+        "Imported {} routes from the {} file `{}` into `{}`",
+        server.data.len(),
+        kind,
+        file.display(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// the directory of the `__files`, it's next to the `mappings` one.
+fn mappings_root(dir: &Path) -> &Path {
+    match dir.parent() {
+        Some(parent) if dir.file_name().is_some_and(|name| name == "mappings") => parent,
+        _ => dir,
+    }
+}
+
+/// the `.json` files of the directory, sorted by their name.
+async fn mapping_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// a json or yaml document, yaml is read for the other extensions.
+async fn read_document(path: &Path) -> Result<Value, Error> {
+    let content = tokio::fs::read_to_string(path).await.map_err(|e| Error::ConfigFileOpenError(
+        format!("can't read `{}`: {}", path.display(), e)
+    ))?;
+    let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json") || ext.eq_ignore_ascii_case("har"));
+    if is_json {
+        serde_json::from_str(&content).map_err(|e| Error::ParsingError(format!("`{}`: {}", path.display(), e)))
+    }else {
+        serde_yaml::from_str(&content).map_err(|e| Error::ParsingError(format!("`{}`: {}", path.display(), e)))
+    }
+}
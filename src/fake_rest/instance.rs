@@ -0,0 +1,303 @@
+use std::{net::SocketAddr, sync::{Arc, RwLock}, time::{Duration, Instant}};
+use tokio::{net::{TcpListener, TcpStream}, task::{JoinHandle, JoinSet}};
+use tokio_rustls::TlsAcceptor;
+use crate::error::{Error, FakeRestResult};
+use crate::server::{connection::Connection, request::{Method, RequestLimits}, response::Response, status::Status, stream::Stream, tls};
+use super::{
+    journal::{self, RecordedRequest},
+    log_file,
+    metrics,
+    print,
+    reload::{self, SharedServer},
+    server_config::{Config, ConfigSource, Server},
+    trace::SpanContext,
+};
+
+/// the seconds of the `read_timeout` and `write_timeout` when they're not set.
+const DEFAULT_TIMEOUT: u64 = 30;
+
+/// the servers of a config that answer the requests in the background, like in the tests:
+///
+/// ```ignore
+/// let server = FakeRestServer::start(ConfigSource::File("server.toml".into(), None)).await?;
+/// let response = reqwest::get(format!("{}/users", server.url())).await?;
+/// server.stop().await;
+/// ```
+///
+/// the listeners with `port = 0` get a free port, `addr()` and `url()` return the bound one.
+/// the servers stop when it's dropped too.
+pub struct FakeRestServer {
+    source: ConfigSource,
+    listeners: Vec<Listener>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+struct Listener {
+    addr: SocketAddr,
+    url: String,
+    server: SharedServer,
+}
+
+impl FakeRestServer {
+
+    /// bind the listeners of the config and start to accept the connections.
+    ///
+    /// all of the listeners are bound first, so a taken port doesn't start any of them.
+    pub async fn start(source: ConfigSource) -> Result<FakeRestServer, Error> {
+        let servers = source.parse().await?;
+        let mut bound = Vec::new();
+        for mut server in servers {
+            let host_and_port = format!("{}:{}", server.config.host, server.config.port);
+            let listener = TcpListener::bind(&host_and_port).await.map_err(|e| Error::IoError(
+                format!("can't listen on `{}`: {}", host_and_port, e)
+            ))?;
+            let tls = match &server.config.tls {
+                Some(tls_config) => Some(tls::load_acceptor(tls_config)?),
+                None => None,
+            };
+            let addr = listener.local_addr()?;
+            // the free port that is picked for `port = 0`
+            server.config.port = addr.port() as usize;
+            let scheme = if tls.is_some() { "https" } else { "http" };
+            let url = format!("{}://{}:{}", scheme, server.config.host, addr.port());
+            bound.push((listener, tls, Listener { addr, url, server: Arc::new(RwLock::new(Arc::new(server))) }));
+        }
+
+        let mut listeners = Vec::new();
+        let mut tasks = Vec::new();
+        for (listener, tls, bound) in bound {
+            tasks.push(tokio::spawn(accept(listener, tls, bound.server.clone())));
+            listeners.push(bound);
+        }
+        Ok(FakeRestServer { source, listeners, tasks })
+    }
+
+    /// reload the config when it or the files it reads change.
+    pub fn watch(&mut self) {
+        let servers = self.listeners.iter().map(|listener| listener.server.clone()).collect();
+        self.tasks.push(tokio::spawn(reload::watch(self.source.clone(), servers)));
+    }
+
+    /// the address of the first listener.
+    pub fn addr(&self) -> SocketAddr {
+        self.listeners[0].addr
+    }
+
+    /// the base url of the first listener, like `http://127.0.0.1:40123`.
+    pub fn url(&self) -> &str {
+        &self.listeners[0].url
+    }
+
+    /// the base urls of the listeners, in the order of the config.
+    pub fn urls(&self) -> Vec<&str> {
+        self.listeners.iter().map(|listener| listener.url.as_str()).collect()
+    }
+
+    /// the server that answers the next request of the first listener, with its routes, metrics and requests.
+    pub fn server(&self) -> Arc<Server> {
+        reload::current(&self.listeners[0].server)
+    }
+
+    /// serve until the process is stopped.
+    pub async fn wait(mut self) {
+        for task in std::mem::take(&mut self.tasks) {
+            let _ = task.await;
+        }
+    }
+
+    /// close the listeners and the open connections.
+    pub async fn stop(mut self) {
+        for task in std::mem::take(&mut self.tasks) {
+            task.abort();
+            let _ = task.await;
+        }
+    }
+
+}
+
+impl Drop for FakeRestServer {
+    fn drop(&mut self) {
+        for task in self.tasks.iter() {
+            task.abort();
+        }
+    }
+}
+
+/// accept the connections until the task is aborted, the open connections are closed with it.
+///
+/// the connections over `max_connections` are answered with 503, or they wait in the backlog of the
+/// listener until one of the open connections is closed with `queue_connections`.
+async fn accept(listener: TcpListener, tls: Option<TlsAcceptor>, server: SharedServer) {
+    let mut connections = JoinSet::new();
+    let mut rejected = JoinSet::new();
+    loop {
+        let current = reload::current(&server);
+        let full = current.config.max_connections.is_some_and(|max_connections| connections.len() >= max_connections);
+        let queued = full && current.config.queue_connections;
+        drop(current);
+        tokio::select! {
+            con = listener.accept(), if !queued => match con {
+                Ok(connection) if full => {
+                    let server = server.clone();
+                    let tls = tls.clone();
+                    rejected.spawn(async move {
+                        if let Err(e) = reject(connection.0, &server, tls).await {
+                            println!("{}", e);
+                        }
+                    });
+                },
+                Ok(connection) => {
+                    let server = server.clone();
+                    let tls = tls.clone();
+                    connections.spawn(async move {
+                        match handle(connection.0, &server, tls).await {
+                            Ok(_) => {},
+                            Err(e) => println!("{}", e)
+                        };
+                    });
+                },
+                Err(e) => println!("{}", e),
+            },
+            // forget the finished connections
+            Some(_) = connections.join_next() => {},
+            Some(_) = rejected.join_next() => {},
+        }
+    }
+}
+
+/// answer the first request of a connection over `max_connections` with 503 and close it.
+async fn reject(socket: TcpStream, shared: &SharedServer, tls: Option<TlsAcceptor>) -> FakeRestResult {
+    let socket = match tls {
+        Some(acceptor) => Stream::Tls(Box::new(acceptor.accept(socket).await?)),
+        None => Stream::Plain(socket),
+    };
+    let mut connection = Connection::new(socket);
+    let server = reload::current(shared);
+    server.metrics.reject_connection();
+    let (idle_timeout, read_timeout, write_timeout) = timeouts(&server.config);
+    connection.write_timeout = Some(write_timeout);
+    // the request is read first, the clients could miss the response of a connection that is closed with unread data
+    if let Ok(None) = connection.read_request(idle_timeout, read_timeout, request_limits(&server.config)).await {
+        return Ok(());
+    }
+    let mut response = Response::text(Status::service_unavailable(), "Too many connections");
+    response.headers.insert("Connection".to_string(), "close".to_string());
+    let _ = connection.respond(response).await;
+    Ok(())
+}
+
+async fn handle(socket: TcpStream, shared: &SharedServer, tls: Option<TlsAcceptor>) -> FakeRestResult {
+    let socket = match tls {
+        Some(acceptor) => Stream::Tls(Box::new(acceptor.accept(socket).await?)),
+        None => Stream::Plain(socket),
+    };
+    let mut connection = Connection::new(socket);
+    let _connection_guard = reload::current(shared).metrics.connection();
+    loop {
+        let (idle_timeout, read_timeout, write_timeout) = timeouts(&reload::current(shared).config);
+        connection.write_timeout = Some(write_timeout);
+        let limits = request_limits(&reload::current(shared).config);
+        let mut request = match connection.read_request(idle_timeout, read_timeout, limits).await {
+            Ok(Some(request)) => request,
+            Ok(None) => break,
+            Err(e) => match rejection(&e) {
+                // the rest of the request is not read, so the connection can't be used anymore
+                Some(mut response) => {
+                    response.headers.insert("Connection".to_string(), "close".to_string());
+                    let _ = connection.respond(response).await;
+                    break;
+                },
+                None => return Err(e),
+            },
+        };
+        let started = Instant::now();
+        // a reloaded config is used from the next request of the connection
+        let server = reload::current(shared);
+        if server.config.tracing.is_some() {
+            request.span = Some(SpanContext::new(&request));
+        }
+        match Response::new(&mut request, &server).await {
+            Ok(mut response) => {
+                // the same headers as `GET`, including the `Content-Length`, without the body
+                if request.method == Method::HEAD {
+                    response.body.clear();
+                    response.file = None;
+                    response.events = None;
+                }
+                // the connection belongs to the websocket after the upgrade
+                let upgraded = response.websocket.is_some();
+                let close = !request.keep_alive()
+                    || response.fault.is_some()
+                    || response.headers.get("Connection").is_some_and(|connection| connection.eq_ignore_ascii_case("close"));
+                if close {
+                    response.headers.insert("Connection".to_string(), "close".to_string());
+                }
+                let (status, size) = (response.status.code, response.body_len());
+                // the requests of the admin api are not recorded for it
+                let recorded = match &server.config.admin_path {
+                    Some(admin_path) if request.route.as_ref() == Some(admin_path) => None,
+                    Some(_) => Some(RecordedRequest::new(&request, &response)),
+                    None if server.journal.is_recording() => Some(RecordedRequest::new(&request, &response)),
+                    None => None,
+                };
+                let logged_response = server.config.request_log.as_ref().map(|request_log| request_log.response(&response));
+                connection.respond(response).await?;
+                if let Some(recorded) = recorded {
+                    server.journal.record(recorded, server.config.history_size.unwrap_or(journal::DEFAULT_HISTORY_SIZE));
+                }
+                let route = request.route.as_deref().unwrap_or(metrics::UNMATCHED);
+                server.metrics.observe(&request.method.to_string(), route, status, size, started.elapsed());
+                if let (Some(tracing), Some(span)) = (&server.config.tracing, &request.span) {
+                    tracing.export(&request, span, server.config.port, status, started.elapsed());
+                }
+                let log = server.config.log.as_ref();
+                if let Some(access_log) = &server.config.access_log {
+                    access_log.write(&request, status, size, started.elapsed(), log).await;
+                }
+                if let (Some(request_log), Some(logged_response)) = (&server.config.request_log, logged_response) {
+                    request_log.write(&request, logged_response, started.elapsed()).await;
+                }
+                log_file::output(log, &print::format_for_print(&request)).await;
+                if close || upgraded {
+                    break;
+                }
+            },
+            Err(e) => {
+                log_file::output(server.config.log.as_ref(), &format!("Error on handling the request: {}", e)).await;
+                // the client gets the failure, like a template that can't be rendered, instead of a closed connection
+                let mut response = Response::text(Status::internal_server_error(), &e.to_string());
+                response.headers.insert("Connection".to_string(), "close".to_string());
+                let _ = connection.respond(response).await;
+                break;
+            },
+        }
+    }
+    Ok(())
+}
+
+/// the keep-alive, read and write timeouts of the server.
+fn timeouts(config: &Config) -> (Duration, Duration, Duration) {
+    (
+        Duration::from_secs(config.keep_alive_timeout.unwrap_or(5)),
+        Duration::from_secs(config.read_timeout.unwrap_or(DEFAULT_TIMEOUT)),
+        Duration::from_secs(config.write_timeout.unwrap_or(DEFAULT_TIMEOUT)),
+    )
+}
+
+fn request_limits(config: &Config) -> RequestLimits {
+    let defaults = RequestLimits::default();
+    RequestLimits {
+        request_line: config.max_request_line.unwrap_or(defaults.request_line),
+        headers: config.max_header_size.unwrap_or(defaults.headers),
+        body: config.max_body_size.or(defaults.body),
+    }
+}
+
+/// the response to a request that is not read because it's too slow or too large.
+fn rejection(e: &Error) -> Option<Response> {
+    match e {
+        Error::RequestTimeout => Some(Response::text(Status::request_timeout(), "Request Timeout")),
+        Error::RequestTooLarge(status, message) => Some(Response::text(Status::from(*status), message)),
+        _ => None,
+    }
+}
@@ -0,0 +1,207 @@
+use std::{collections::{HashMap, VecDeque}, sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Mutex}, time::{Duration, SystemTime, UNIX_EPOCH}};
+use base64::Engine;
+use serde_json::{json, Value};
+use crate::server::{headers::Headers, request::Request, response::Response, router};
+
+/// the bodies of the responses are kept up to this many bytes, they're only previewed.
+const RESPONSE_PREVIEW_SIZE: usize = 4096;
+
+/// the requests that are kept when the `history_size` of the config is not set.
+pub const DEFAULT_HISTORY_SIZE: usize = 1000;
+
+/// a request that the server answered.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// the number of the request, it's given by the journal
+    pub id: u64,
+    pub time: SystemTime,
+    pub method: String,
+    /// the percent-decoded path
+    pub path: String,
+    pub raw_uri: String,
+    pub query_strings: HashMap<String, String>,
+    pub headers: Headers,
+    pub body: Vec<u8>,
+    /// the path (or `path_regex`) of the route that served it
+    pub route: Option<String>,
+    pub status: usize,
+    pub response_headers: Headers,
+    /// the start of the response body
+    pub response_body: Vec<u8>,
+    pub response_size: usize,
+}
+
+impl RecordedRequest {
+
+    /// the request and its response, it's taken before the response is written.
+    pub fn new(request: &Request, response: &Response) -> RecordedRequest {
+        let mut response_headers = response.headers.clone();
+        for cookie in response.cookies.iter() {
+            response_headers.append("Set-Cookie", cookie);
+        }
+        RecordedRequest {
+            id: 0,
+            time: SystemTime::now(),
+            method: request.method.to_string(),
+            path: request.uri.clone(),
+            raw_uri: request.raw_uri.clone(),
+            query_strings: request.query_strings.clone(),
+            headers: request.headers.clone(),
+            body: request.body.clone(),
+            route: request.route.clone(),
+            status: response.status.code,
+            response_headers,
+            response_body: response.body[..response.body.len().min(RESPONSE_PREVIEW_SIZE)].to_vec(),
+            response_size: response.body_len(),
+        }
+    }
+
+    /// the request as json, the text bodies are kept as they are and the binary ones in base64.
+    pub fn to_json(&self) -> Value {
+        let mut response = json!({
+            "status": self.status,
+            "headers": self.response_headers,
+            "size": self.response_size,
+        });
+        add_body(&mut response, &self.response_body);
+        let mut value = json!({
+            "id": self.id,
+            "time": httpdate::fmt_http_date(self.time),
+            "method": self.method,
+            "path": self.path,
+            "uri": self.raw_uri,
+            "query": self.query_strings,
+            "headers": self.headers,
+            "route": self.route,
+            "status": self.status,
+        });
+        add_body(&mut value, &self.body);
+        value["response"] = response;
+        value
+    }
+
+}
+
+/// the text bodies are kept as they are and the binary ones in base64.
+fn add_body(value: &mut Value, body: &[u8]) {
+    match std::str::from_utf8(body) {
+        Ok(text) => value["body"] = json!(text),
+        // the preview can cut a multi-byte character at the end
+        Err(e) if e.error_len().is_none() => value["body"] = json!(String::from_utf8_lossy(&body[..e.valid_up_to()])),
+        Err(_) => value["body_base64"] = json!(base64::engine::general_purpose::STANDARD.encode(body)),
+    }
+}
+
+/// the conditions of a search of the requests, the ones that are not set match any request.
+#[derive(Debug, Default)]
+pub struct RequestFilter {
+    /// a path like the ones of the routes, `/users/{id}` or `/files/**` match the paths of their routes
+    pub path: Option<String>,
+    pub method: Option<String>,
+    /// a header like `X-Request-Id: 42`, or only its name to match the requests that have it
+    pub header: Option<(String, Option<String>)>,
+    /// the status of the response, like 404
+    pub status: Option<usize>,
+    /// only the requests after the one with this id
+    pub after: Option<u64>,
+    /// only the requests received at or after this time
+    pub since: Option<SystemTime>,
+    /// only the requests received at or before this time
+    pub until: Option<SystemTime>,
+}
+
+impl RequestFilter {
+
+    /// the filter of the query of an admin request, an error is returned for the values that can't be read.
+    pub fn from_query(query: &HashMap<String, String>) -> Result<RequestFilter, String> {
+        let header = query.get("header").map(|header| match header.split_once(':') {
+            Some((name, value)) => (name.trim().to_string(), Some(value.trim().to_string())),
+            None => (header.trim().to_string(), None),
+        });
+        Ok(RequestFilter {
+            path: query.get("path").cloned(),
+            method: query.get("method").cloned(),
+            header,
+            status: parse(query, "status", |value| value.parse().ok(), "a status code like 404")?,
+            after: parse(query, "after", |value| value.parse().ok(), "the id of a request")?,
+            since: parse(query, "since", parse_time, "a unix timestamp in seconds or an http date")?,
+            until: parse(query, "until", parse_time, "a unix timestamp in seconds or an http date")?,
+        })
+    }
+
+    fn matches(&self, request: &RecordedRequest) -> bool {
+        self.path.as_ref().is_none_or(|path| router::match_path(path, &request.path).is_some())
+            && self.method.as_ref().is_none_or(|method| method.eq_ignore_ascii_case(&request.method))
+            && self.header.as_ref().is_none_or(|(name, value)| match (request.headers.get(name), value) {
+                (Some(actual), Some(value)) => actual == value,
+                (actual, None) => actual.is_some(),
+                (None, Some(_)) => false,
+            })
+            && self.status.is_none_or(|status| request.status == status)
+            && self.after.is_none_or(|after| request.id > after)
+            && self.since.is_none_or(|since| request.time >= since)
+            && self.until.is_none_or(|until| request.time <= until)
+    }
+
+}
+
+fn parse<T>(query: &HashMap<String, String>, name: &str, parser: impl Fn(&str) -> Option<T>, expected: &str) -> Result<Option<T>, String> {
+    match query.get(name) {
+        Some(value) => parser(value).map(Some).ok_or_else(|| format!("`{}` must be {}", name, expected)),
+        None => Ok(None),
+    }
+}
+
+/// a time like `1700000000` or `Tue, 14 Nov 2023 22:13:20 GMT`.
+fn parse_time(value: &str) -> Option<SystemTime> {
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(UNIX_EPOCH + Duration::from_secs(seconds)),
+        Err(_) => httpdate::parse_http_date(value).ok(),
+    }
+}
+
+/// the last requests of a listener in the order they're answered, they're kept when the config is reloaded.
+#[derive(Debug, Default)]
+pub struct Journal {
+    requests: Mutex<VecDeque<RecordedRequest>>,
+    /// the id of the last request
+    last_id: AtomicU64,
+    /// record the requests without the admin api, like for the expectations of a test
+    recording: AtomicBool,
+}
+
+impl Journal {
+
+    /// the requests are recorded when the admin api is served or this is turned on.
+    pub fn start_recording(&self) {
+        self.recording.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.load(Ordering::SeqCst)
+    }
+
+    /// add a request, the oldest ones are dropped to keep at most `history_size` of them.
+    pub fn record(&self, mut request: RecordedRequest, history_size: usize) {
+        // the ids are given while the requests are locked, so they stay in order
+        let mut requests = self.requests.lock().unwrap();
+        request.id = self.last_id.fetch_add(1, Ordering::SeqCst) + 1;
+        requests.push_back(request);
+        while requests.len() > history_size {
+            requests.pop_front();
+        }
+    }
+
+    pub fn find(&self, filter: &RequestFilter) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().iter().filter(|request| filter.matches(request)).cloned().collect()
+    }
+
+    pub fn count(&self, filter: &RequestFilter) -> usize {
+        self.requests.lock().unwrap().iter().filter(|request| filter.matches(request)).count()
+    }
+
+    pub fn clear(&self) {
+        self.requests.lock().unwrap().clear();
+    }
+
+}
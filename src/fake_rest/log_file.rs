@@ -0,0 +1,94 @@
+use std::{path::PathBuf, sync::Arc};
+use serde::Deserialize;
+use tokio::{fs::File, io::AsyncWriteExt, sync::Mutex};
+
+/// the old files of a rotated log that are kept by default.
+const DEFAULT_MAX_FILES: usize = 5;
+
+/// start a new file when the log gets bigger than `max_size` bytes, the old ones are renamed to
+/// `file.1` (the newest), `file.2`, ... and the ones after `max_files` are removed.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Rotation {
+    /// the file is never rotated without it
+    pub max_size: Option<u64>,
+    /// default is 5
+    pub max_files: Option<usize>,
+}
+
+/// a file the log lines are appended to, it's opened on the first line.
+#[derive(Debug, Default)]
+pub struct LogFile {
+    /// the file and its size
+    file: Mutex<Option<(File, u64)>>,
+}
+
+impl LogFile {
+
+    /// append the line to the file of `path`, the errors are printed and the line is dropped.
+    pub async fn append(&self, path: &str, rotation: &Rotation, line: &str) {
+        let line = format!("{}\n", line);
+        let mut file = self.file.lock().await;
+        let rotate = match (&*file, rotation.max_size) {
+            (Some((_, size)), Some(max_size)) => *size > 0 && size + line.len() as u64 > max_size,
+            _ => false,
+        };
+        if rotate {
+            *file = None;
+            if let Err(e) = rotate_files(path, rotation.max_files.unwrap_or(DEFAULT_MAX_FILES)).await {
+                println!("can't rotate the log file `{}`: {}", path, e);
+            }
+        }
+        if file.is_none() {
+            match tokio::fs::OpenOptions::new().create(true).append(true).open(path).await {
+                Ok(opened) => {
+                    let size = opened.metadata().await.map(|metadata| metadata.len()).unwrap_or(0);
+                    *file = Some((opened, size));
+                },
+                Err(e) => return println!("can't open the log file `{}`: {}", path, e),
+            }
+        }
+        if let Some((file, size)) = file.as_mut() {
+            match file.write_all(line.as_bytes()).await {
+                Ok(_) => *size += line.len() as u64,
+                Err(e) => println!("can't write the log file `{}`: {}", path, e),
+            }
+        }
+    }
+
+}
+
+/// move `file` to `file.1` and the old files one step back, the last one is removed.
+async fn rotate_files(path: &str, max_files: usize) -> std::io::Result<()> {
+    let old_file = |index: usize| PathBuf::from(format!("{}.{}", path, index));
+    if max_files == 0 {
+        return tokio::fs::remove_file(path).await;
+    }
+    if tokio::fs::try_exists(old_file(max_files)).await? {
+        tokio::fs::remove_file(old_file(max_files)).await?;
+    }
+    for index in (1..max_files).rev() {
+        if tokio::fs::try_exists(old_file(index)).await? {
+            tokio::fs::rename(old_file(index), old_file(index + 1)).await?;
+        }
+    }
+    tokio::fs::rename(path, old_file(1)).await
+}
+
+/// the output of a server, like the requests and the errors, in a file instead of the stdout.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Log {
+    pub file: String,
+    #[serde(flatten)]
+    pub rotation: Rotation,
+    /// the file is opened on the first line, it's shared between the clones of the config
+    #[serde(skip)]
+    writer: Arc<LogFile>,
+}
+
+/// write the text to the log of the server, or the stdout when it has none.
+pub async fn output(log: Option<&Log>, text: &str) {
+    match log {
+        Some(log) => log.writer.append(&log.file, &log.rotation, text).await,
+        None => println!("{}", text),
+    }
+}
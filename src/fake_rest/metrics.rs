@@ -0,0 +1,124 @@
+use std::{collections::BTreeMap, fmt::Write, sync::{atomic::{AtomicI64, AtomicU64, Ordering}, Arc, Mutex}, time::Duration};
+
+/// the upper bounds of the latency buckets in seconds, the default ones of the prometheus clients.
+const BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// the route label of the requests that no route served.
+pub const UNMATCHED: &str = "unmatched";
+
+/// the counters of a listener in the prometheus text format, they're kept when the config is reloaded.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    active_connections: AtomicI64,
+    /// the connections over `max_connections` that are answered with 503
+    rejected_connections: AtomicU64,
+    /// the request counts by method, route and status
+    requests: Mutex<BTreeMap<(String, String, usize), u64>>,
+    /// the latencies and the bytes served by method and route
+    routes: Mutex<BTreeMap<(String, String), RouteMetrics>>,
+}
+
+#[derive(Debug, Default)]
+struct RouteMetrics {
+    /// the count of every bucket, not cumulative
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+    bytes: u64,
+}
+
+/// an open connection, it's counted until it's dropped.
+pub struct ConnectionGuard(Arc<Metrics>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.active_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+
+    pub fn connection(self: &Arc<Self>) -> ConnectionGuard {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+        ConnectionGuard(self.clone())
+    }
+
+    pub fn reject_connection(&self) {
+        self.rejected_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// count a request of the route that is answered with `status` and a body of `size` bytes.
+    pub fn observe(&self, method: &str, route: &str, status: usize, size: usize, elapsed: Duration) {
+        let key = (method.to_string(), route.to_string());
+        *self.requests.lock().unwrap().entry((key.0.clone(), key.1.clone(), status)).or_insert(0) += 1;
+
+        let mut routes = self.routes.lock().unwrap();
+        let route = routes.entry(key).or_default();
+        let seconds = elapsed.as_secs_f64();
+        if let Some(bucket) = BUCKETS.iter().position(|bound| seconds <= *bound) {
+            route.buckets[bucket] += 1;
+        }
+        route.count += 1;
+        route.sum += seconds;
+        route.bytes += size as u64;
+    }
+
+    /// the metrics in the prometheus text format.
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "# HELP fake_rest_active_connections The open connections.");
+        let _ = writeln!(text, "# TYPE fake_rest_active_connections gauge");
+        let _ = writeln!(text, "fake_rest_active_connections {}", self.active_connections.load(Ordering::Relaxed));
+
+        let _ = writeln!(text, "# HELP fake_rest_rejected_connections_total The connections over max_connections.");
+        let _ = writeln!(text, "# TYPE fake_rest_rejected_connections_total counter");
+        let _ = writeln!(text, "fake_rest_rejected_connections_total {}", self.rejected_connections.load(Ordering::Relaxed));
+
+        let _ = writeln!(text, "# HELP fake_rest_requests_total The served requests.");
+        let _ = writeln!(text, "# TYPE fake_rest_requests_total counter");
+        for ((method, route, status), count) in self.requests.lock().unwrap().iter() {
+            let _ = writeln!(
+                text,
+                "fake_rest_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                label(method),
+                label(route),
+                status,
+                count
+            );
+        }
+
+        let routes = self.routes.lock().unwrap();
+        let _ = writeln!(text, "# HELP fake_rest_request_duration_seconds The time it took to answer the requests.");
+        let _ = writeln!(text, "# TYPE fake_rest_request_duration_seconds histogram");
+        for ((method, route), metrics) in routes.iter() {
+            let labels = format!("method=\"{}\",route=\"{}\"", label(method), label(route));
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS.iter().zip(metrics.buckets.iter()) {
+                cumulative += count;
+                let _ = writeln!(text, "fake_rest_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, bound, cumulative);
+            }
+            let _ = writeln!(text, "fake_rest_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, metrics.count);
+            let _ = writeln!(text, "fake_rest_request_duration_seconds_sum{{{}}} {}", labels, metrics.sum);
+            let _ = writeln!(text, "fake_rest_request_duration_seconds_count{{{}}} {}", labels, metrics.count);
+        }
+
+        let _ = writeln!(text, "# HELP fake_rest_response_bytes_total The bytes of the served bodies.");
+        let _ = writeln!(text, "# TYPE fake_rest_response_bytes_total counter");
+        for ((method, route), metrics) in routes.iter() {
+            let _ = writeln!(
+                text,
+                "fake_rest_response_bytes_total{{method=\"{}\",route=\"{}\"}} {}",
+                label(method),
+                label(route),
+                metrics.bytes
+            );
+        }
+        text
+    }
+
+}
+
+/// the backslashes, quotes and new lines of the label values are escaped.
+fn label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
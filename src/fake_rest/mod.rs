@@ -1,2 +1,23 @@
 pub mod server_config;
-pub mod print;
\ No newline at end of file
+pub mod print;
+pub mod delay;
+pub mod store;
+pub mod record;
+pub mod rate_limit;
+pub mod reload;
+pub mod env;
+pub mod validate;
+pub mod generated;
+pub mod openapi;
+pub mod import;
+pub mod har;
+pub mod wiremock;
+pub mod access_log;
+pub mod log_file;
+pub mod request_log;
+pub mod metrics;
+pub mod trace;
+pub mod journal;
+pub mod instance;
+pub mod fixture;
+pub mod file_cache;
@@ -0,0 +1,479 @@
+use std::path::{Path, PathBuf};
+use serde_json::{json, Map, Value};
+use crate::error::{Error, FakeRestResult};
+use crate::server::{auth::Auth, content_type::ContentType, matcher::{Requirement, ValueMatcher}, request::Method, resource, router, status::Status, template};
+use super::{generated::GeneratedRoute, server_config::{ConfigSource, Server, ServerDataSchema}};
+
+const METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// `$ref`s are followed up to this many times in a row.
+const MAX_DEPTH: usize = 8;
+
+/// a route for every operation of the OpenAPI 3 or Swagger 2 document.
+///
+/// the status and the body are the ones of the first successful response, the body is its example or
+/// made from its schema when it has none.
+/// the required query and header parameters become the `queries` and `headers` of the route.
+pub fn routes(document: &Value) -> Result<Vec<GeneratedRoute>, Error> {
+    let paths = document.get("paths").and_then(Value::as_object).ok_or_else(|| Error::ParsingError(
+        "the OpenAPI document has no `paths`".to_string()
+    ))?;
+    let base_path = base_path(document);
+
+    let mut routes = Vec::new();
+    for (path, item) in paths.iter() {
+        let item = resolve(document, item);
+        for method in METHODS {
+            if let Some(operation) = item.get(method) {
+                routes.push(operation_route(document, item, operation, method, &format!("{}{}", base_path, path)));
+            }
+        }
+    }
+    Ok(routes)
+}
+
+/// the `basePath` of swagger 2 or the path of the first server of OpenAPI 3, without the trailing `/`.
+fn base_path(document: &Value) -> String {
+    let base_path = match document.get("basePath").and_then(Value::as_str) {
+        Some(base_path) => base_path.to_string(),
+        None => {
+            let url = document
+                .pointer("/servers/0/url")
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            let path = match url.split_once("://") {
+                Some((_, rest)) => rest.find('/').map(|index| &rest[index..]).unwrap_or(""),
+                None => url,
+            };
+            // the server variables can't be known
+            if path.contains('{') { String::new() } else { path.to_string() }
+        },
+    };
+    base_path.trim_end_matches('/').to_string()
+}
+
+fn operation_route(document: &Value, item: &Value, operation: &Value, method: &str, path: &str) -> GeneratedRoute {
+    let mut headers = Vec::new();
+    let mut queries = Vec::new();
+    let parameters = item.get("parameters").and_then(Value::as_array).into_iter().flatten()
+        .chain(operation.get("parameters").and_then(Value::as_array).into_iter().flatten());
+    for parameter in parameters.map(|parameter| resolve(document, parameter)) {
+        let required = parameter.get("required").and_then(Value::as_bool).unwrap_or(false);
+        let name = parameter.get("name").and_then(Value::as_str).unwrap_or("");
+        match parameter.get("in").and_then(Value::as_str) {
+            Some("query") if required => queries.push(name.to_string()),
+            Some("header") if required => headers.push(name.to_string()),
+            _ => {},
+        }
+    }
+
+    let (status_code, response) = pick_response(document, operation);
+    let (content_type, body) = match response {
+        Some(response) => response_body(document, operation, response),
+        None => (None, String::new()),
+    };
+
+    GeneratedRoute {
+        method: method.to_uppercase(),
+        path: path.to_string(),
+        result_type: "direct".to_string(),
+        result: body,
+        status_code,
+        result_headers: content_type.map(|content_type| format!("Content-Type: {}", content_type)).into_iter().collect(),
+        headers: if headers.is_empty() { None } else { Some(headers) },
+        queries: if queries.is_empty() { None } else { Some(queries) },
+        ..Default::default()
+    }
+}
+
+/// the first successful response, or `default`, or the first one.
+fn pick_response<'a>(document: &'a Value, operation: &'a Value) -> (usize, Option<&'a Value>) {
+    let responses = match operation.get("responses").and_then(Value::as_object) {
+        Some(responses) => responses,
+        None => return (200, None),
+    };
+    let successful = responses
+        .iter()
+        .filter_map(|(code, response)| code.parse::<usize>().ok().map(|code| (code, response)))
+        .filter(|(code, _)| (200..300).contains(code))
+        .min_by_key(|(code, _)| *code);
+    let (code, response) = match successful {
+        Some(found) => found,
+        None => match responses.get("default") {
+            Some(response) => (200, response),
+            None => match responses.iter().next() {
+                Some((code, response)) => (code.parse().unwrap_or(200), response),
+                None => return (200, None),
+            },
+        },
+    };
+    (code, Some(resolve(document, response)))
+}
+
+/// the content type and the body of the response, json is preferred over the other content types.
+fn response_body(document: &Value, operation: &Value, response: &Value) -> (Option<String>, String) {
+    // openapi 3
+    if let Some(content) = response.get("content").and_then(Value::as_object) {
+        let (content_type, media) = match content.get_key_value("application/json").or_else(|| content.iter().next()) {
+            Some(found) => found,
+            None => return (None, String::new()),
+        };
+        let example = media.get("example").cloned()
+            .or_else(|| {
+                media.get("examples")
+                    .and_then(Value::as_object)
+                    .and_then(|examples| examples.values().next())
+                    .map(|example| resolve(document, example))
+                    .and_then(|example| example.get("value").cloned())
+            })
+            .or_else(|| media.get("schema").map(|schema| example_of(document, schema, &mut Vec::new())));
+        return (Some(content_type.clone()), example.map(|example| to_body(&example)).unwrap_or_default());
+    }
+
+    // swagger 2
+    let content_type = operation.get("produces")
+        .or_else(|| document.get("produces"))
+        .and_then(|produces| produces.get(0))
+        .and_then(Value::as_str)
+        .unwrap_or("application/json")
+        .to_string();
+    let example = response.get("examples")
+        .and_then(Value::as_object)
+        .and_then(|examples| examples.get(&content_type).or_else(|| examples.values().next()).cloned())
+        .or_else(|| response.get("schema").map(|schema| example_of(document, schema, &mut Vec::new())));
+    match example {
+        Some(example) => (Some(content_type), to_body(&example)),
+        None => (None, String::new()),
+    }
+}
+
+fn to_body(example: &Value) -> String {
+    match example {
+        Value::String(text) => text.clone(),
+        _ => serde_json::to_string_pretty(example).unwrap_or_default(),
+    }
+}
+
+/// follow the `$ref`s of the document like `#/components/schemas/User`.
+fn resolve<'a>(document: &'a Value, value: &'a Value) -> &'a Value {
+    let mut value = value;
+    for _ in 0..MAX_DEPTH {
+        match value.get("$ref").and_then(Value::as_str).and_then(|reference| reference.strip_prefix('#')) {
+            Some(pointer) => match document.pointer(pointer) {
+                Some(target) => value = target,
+                None => break,
+            },
+            None => break,
+        }
+    }
+    value
+}
+
+/// an example value of the schema, its own `example`, `default` or first `enum` value is used when it has one.
+/// `refs` are the schemas that are being made, a recursive schema is `null` inside itself.
+pub fn example_of(document: &Value, schema: &Value, refs: &mut Vec<String>) -> Value {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        if refs.iter().any(|item| item == reference) {
+            return Value::Null;
+        }
+        refs.push(reference.to_string());
+        let example = example_of(document, resolve(document, schema), refs);
+        refs.pop();
+        return example;
+    }
+    if let Some(example) = schema.get("example").or_else(|| schema.get("default")) {
+        return example.clone();
+    }
+    if let Some(first) = schema.get("enum").and_then(|values| values.get(0)) {
+        return first.clone();
+    }
+    if let Some(all_of) = schema.get("allOf").and_then(Value::as_array) {
+        let mut merged = Map::new();
+        for part in all_of.iter() {
+            if let Value::Object(object) = example_of(document, part, refs) {
+                merged.extend(object);
+            }
+        }
+        return Value::Object(merged);
+    }
+    for key in ["oneOf", "anyOf"] {
+        if let Some(first) = schema.get(key).and_then(|schemas| schemas.get(0)) {
+            return example_of(document, first, refs);
+        }
+    }
+
+    let schema_type = match schema.get("type") {
+        Some(Value::String(schema_type)) => schema_type.as_str(),
+        // openapi 3.1 types like ["string", "null"]
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).find(|item| *item != "null").unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ if schema.get("items").is_some() => "array",
+        _ => "",
+    };
+    match schema_type {
+        "object" => {
+            let mut object = Map::new();
+            for (name, property) in schema.get("properties").and_then(Value::as_object).into_iter().flatten() {
+                object.insert(name.clone(), example_of(document, property, refs));
+            }
+            Value::Object(object)
+        },
+        "array" => match schema.get("items").map(|items| example_of(document, items, refs)) {
+            Some(Value::Null) | None => json!([]),
+            Some(item) => json!([item]),
+        },
+        "string" => json!(match schema.get("format").and_then(Value::as_str) {
+            Some("date-time") => "2024-01-01T00:00:00Z",
+            Some("date") => "2024-01-01",
+            Some("email") => "user@example.com",
+            Some("uuid") => "3fa85f64-5717-4562-b3fc-2c963f66afa6",
+            Some("uri") | Some("url") => "https://example.com",
+            Some("ipv4") => "127.0.0.1",
+            _ => "string",
+        }),
+        "integer" => schema.get("minimum").cloned().unwrap_or(json!(0)),
+        "number" => schema.get("minimum").cloned().unwrap_or(json!(0.0)),
+        "boolean" => json!(true),
+        _ => Value::Null,
+    }
+}
+
+/// write the OpenAPI 3 document of the server on the `port`, or the first one, to `output` or print it.
+pub async fn export_to(source: ConfigSource, output: Option<PathBuf>, port: Option<usize>) -> FakeRestResult {
+    let servers = source.parse().await?;
+    let server = match port {
+        Some(port) => servers.iter().find(|server| server.config.port == port).ok_or_else(|| Error::ConfigValidationError(
+            format!("no server of `{}` listens on port {}", source.path().display(), port)
+        ))?,
+        None => &servers[0],
+    };
+    let document = export(server);
+
+    let output = match output {
+        Some(output) => output,
+        None => {
+            println!("{}", serde_json::to_string_pretty(&document).unwrap_or_default());
+            return Ok(());
+        },
+    };
+    let is_yaml = output.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+    let content = if is_yaml {
+        serde_yaml::to_string(&document).map_err(|e| Error::ParsingError(e.to_string()))?
+    }else {
+        serde_json::to_string_pretty(&document).unwrap_or_default()
+    };
+    tokio::fs::write(&output, content).await?;
+    println!("Exported {} paths into `{}`", document["paths"].as_object().map(Map::len).unwrap_or(0), output.display());
+    Ok(())
+}
+
+/// an OpenAPI 3 document of the routes of the server.
+///
+/// the routes with a `path_regex` or a `*` in their path can't be described and are left out,
+/// the routes of the same path and method are one operation with the responses of all of them.
+pub fn export(server: &Server) -> Value {
+    let mut paths = Map::new();
+    let mut security_schemes = Map::new();
+    for item in server.data.iter() {
+        if item.path_regex.is_some() || item.path.split('/').any(|segment| segment == "*" || segment == "**") {
+            continue;
+        }
+        for (path, method) in operations_of(item) {
+            let method = method.to_string().to_lowercase();
+            if !METHODS.contains(&method.as_str()) {
+                continue;
+            }
+            let path_item = paths.entry(path.clone()).or_insert_with(|| json!({}));
+            if path_item.get(&method).is_none() {
+                path_item[&method] = operation(server, item, &path, &mut security_schemes);
+            }
+            add_responses(&mut path_item[&method], item, &method, path != item.path);
+        }
+    }
+
+    let scheme = if server.config.tls.is_some() { "https" } else { "http" };
+    let mut document = json!({
+        "openapi": "3.0.3",
+        "info": { "title": "fake-rest", "version": env!("CARGO_PKG_VERSION") },
+        "servers": [{ "url": format!("{}://{}:{}", scheme, server.config.host, server.config.port) }],
+        "paths": paths,
+    });
+    if !security_schemes.is_empty() {
+        document["components"] = json!({ "securitySchemes": security_schemes });
+    }
+    document
+}
+
+/// the paths and methods of the route, a `resource` route has the ones of its collection and items,
+/// and a `dataset` route the method of the route on both of them.
+fn operations_of(item: &ServerDataSchema) -> Vec<(String, Method)> {
+    let item_path = format!("{}/{{{}}}", item.path, resource::RESOURCE_ID);
+    if item.result_type == "dataset" {
+        return vec![(item.path.clone(), item.method.clone()), (item_path, item.method.clone())];
+    }
+    if item.result_type != "resource" {
+        return vec![(item.path.clone(), item.method.clone())];
+    }
+    resource::methods(false)
+        .into_iter()
+        .map(|method| (item.path.clone(), method))
+        .chain(resource::methods(true).into_iter().map(|method| (item_path.clone(), method)))
+        .collect()
+}
+
+fn operation(server: &Server, item: &ServerDataSchema, path: &str, security_schemes: &mut Map<String, Value>) -> Value {
+    let mut parameters: Vec<Value> = path
+        .split('/')
+        .filter_map(router::get_param_name)
+        .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }))
+        .collect();
+    for (location, requirements) in [
+        ("query", &item.compiled_queries),
+        ("header", &item.compiled_headers),
+        ("cookie", &item.compiled_cookies),
+    ] {
+        parameters.extend(requirements.iter().map(|requirement| parameter(location, requirement)));
+    }
+
+    let mut operation = json!({});
+    if !parameters.is_empty() {
+        operation["parameters"] = Value::Array(parameters);
+    }
+    if let Some(request_body) = request_body(item) {
+        operation["requestBody"] = request_body;
+    }
+    if let Some(auth) = &item.auth {
+        let (name, scheme) = match auth {
+            Auth::Basic { .. } => ("basic", json!({ "type": "http", "scheme": "basic" })),
+            Auth::Bearer { jwt: Some(_), .. } => ("jwt", json!({ "type": "http", "scheme": "bearer", "bearerFormat": "JWT" })),
+            Auth::Bearer { .. } => ("bearer", json!({ "type": "http", "scheme": "bearer" })),
+            Auth::ApiKey { .. } => ("api_key", json!({
+                "type": "apiKey",
+                "in": "header",
+                "name": server.config.api_key_header.as_deref().unwrap_or("X-Api-Key"),
+            })),
+        };
+        security_schemes.insert(name.to_string(), scheme);
+        operation["security"] = json!([{ name: [] }]);
+    }
+    operation["responses"] = json!({});
+    operation
+}
+
+fn parameter(location: &str, requirement: &Requirement) -> Value {
+    let schema = match &requirement.matcher {
+        ValueMatcher::Any => json!({ "type": "string" }),
+        ValueMatcher::Equals(value) => json!({ "type": "string", "enum": [value] }),
+        ValueMatcher::Regex(regex) => json!({ "type": "string", "pattern": regex.as_str() }),
+    };
+    json!({ "name": requirement.name, "in": location, "required": true, "schema": schema })
+}
+
+/// the `body_schema` of the route, or its `body_json` as an example.
+fn request_body(item: &ServerDataSchema) -> Option<Value> {
+    let schema = item.body_schema
+        .as_ref()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok());
+    let media = match (schema, &item.body_json) {
+        (Some(schema), _) => json!({ "schema": schema }),
+        (None, Some(body_json)) => json!({ "example": body_json }),
+        (None, None) => return None,
+    };
+    Some(json!({ "required": true, "content": { "application/json": media } }))
+}
+
+/// add the status codes and bodies of the route and its `responses` and `representations` to the operation.
+fn add_responses(operation: &mut Value, item: &ServerDataSchema, method: &str, is_item: bool) {
+    let mut responses: Vec<(usize, Option<String>, Option<Value>)> = Vec::new();
+    if item.result_type == "resource" {
+        let status = match method {
+            "post" => 201,
+            "delete" => 204,
+            _ => 200,
+        };
+        let content = (status != 204).then(|| "application/json".to_string());
+        let example = (method == "get" && !is_item).then(|| json!([]));
+        responses.push((status, content, example));
+    }else if item.result_type == "dataset" {
+        // the first record is the example of the records
+        let example = match item.records.first() {
+            Some(record) if is_item => Some(record.clone()),
+            Some(record) => Some(json!([record])),
+            None => None,
+        };
+        responses.push((200, Some("application/json".to_string()), example));
+        if is_item {
+            responses.push((404, Some("application/json".to_string()), Some(json!({ "error": "Not Found" }))));
+        }
+    }else {
+        let status = item.status_code.unwrap_or(if item.result_type == "redirect" { 302 } else { 200 });
+        let (content_type, example) = result_content(&item.result_type, &item.result, item.result_headers.as_ref());
+        responses.push((status, content_type, example));
+        for response in item.responses.iter().flatten() {
+            let result_type = response.result_type.as_ref().unwrap_or(&item.result_type);
+            let result = response.result.as_ref().unwrap_or(&item.result);
+            let result_headers = response.result_headers.as_ref().or(item.result_headers.as_ref());
+            let (content_type, example) = result_content(result_type, result, result_headers);
+            responses.push((response.status_code.unwrap_or(status), content_type, example));
+        }
+        for representation in item.representations.iter().flatten() {
+            let result_type = representation.result_type.as_ref().unwrap_or(&item.result_type);
+            let result = representation.result.as_ref().unwrap_or(&item.result);
+            let (_, example) = result_content(result_type, result, None);
+            responses.push((representation.status_code.unwrap_or(status), Some(representation.content_type.clone()), example));
+        }
+    }
+
+    for (status, content_type, example) in responses {
+        let entry = operation["responses"]
+            .as_object_mut()
+            .expect("responses is an object")
+            .entry(status.to_string())
+            .or_insert_with(|| json!({ "description": description(status) }));
+        if let Some(content_type) = content_type {
+            if entry.get("content").and_then(|content| content.get(&content_type)).is_none() {
+                let media = match example {
+                    Some(example) => json!({ "example": example }),
+                    None => json!({}),
+                };
+                entry["content"][content_type] = media;
+            }
+        }
+    }
+}
+
+/// the content type and the example body of a result, the files are not read and the templates
+/// don't have an example.
+fn result_content(result_type: &str, result: &str, result_headers: Option<&Vec<String>>) -> (Option<String>, Option<Value>) {
+    let content_type = result_headers
+        .into_iter()
+        .flatten()
+        .filter_map(|header| header.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("Content-Type"))
+        .map(|(_, value)| value.trim().to_string());
+
+    match result_type {
+        "direct" if !result.is_empty() => match serde_json::from_str::<Value>(result) {
+            Ok(json) => (content_type.or(Some("application/json".to_string())), Some(json)),
+            Err(_) => (content_type.or(Some("text/plain".to_string())), Some(Value::String(result.to_string()))),
+        },
+        "file" | "dl" => {
+            let guessed = Path::new(result)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(ContentType::get_mime_type)
+                .filter(|mime| !mime.is_empty());
+            (content_type.or(guessed), None)
+        },
+        "template_file" => (content_type.or(template::content_type(Path::new(result))), None),
+        "base64" => (content_type.or(Some("application/octet-stream".to_string())), None),
+        "xml" => (content_type.or(Some("text/xml".to_string())), None),
+        "oauth2_token" => (Some("application/json".to_string()), None),
+        _ => (content_type, None),
+    }
+}
+
+fn description(status: usize) -> String {
+    Status::from(status).message
+}
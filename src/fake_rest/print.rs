@@ -1,7 +1,7 @@
 use crate::server::request::Request;
 
-
-pub fn format_for_print(request: &Request) {
+/// the request as a block of lines, to print it or write it in the log.
+pub fn format_for_print(request: &Request) -> String {
     let mut query_strings = String::new();
     if request.query_strings.is_empty() {
         query_strings.push_str("\n-      -- Empty --");
@@ -16,6 +16,20 @@ pub fn format_for_print(request: &Request) {
         }
     }
     
+    let mut path_params = String::new();
+    if request.path_params.is_empty() {
+        path_params.push_str("\n-      -- Empty --");
+    }else {
+        for param in request.path_params.iter() {
+            let param_style = format!(
+                "\n-       -{} = {}", 
+                param.0, 
+                param.1
+            );
+            path_params.push_str(&param_style);
+        }
+    }
+
     let mut headers = String::new();
     for header in request.headers.iter(){
         let header_style = format!(
@@ -25,15 +39,49 @@ pub fn format_for_print(request: &Request) {
         );
         headers.push_str(&header_style);
     }
-    println!();
-    println!("------------------------ Start Request-------------------------");
-    let printable = format!(
-        "-- Version: {}\n-- Type: {}\n-- Path: {}\n-- Query Strings:{}\n-- Headers:{}", 
+    let mut body = String::new();
+    if request.body.is_empty() {
+        body.push_str("\n-      -- Empty --");
+    }else if let Some(json) = request.json() {
+        body.push_str(&format!("\n{}", serde_json::to_string_pretty(json).unwrap_or_default()));
+    }else if let Some(multipart) = request.multipart() {
+        for field in multipart.fields.iter() {
+            body.push_str(&format!("\n-       -{} = {}", field.0, field.1));
+        }
+        for file in multipart.files.iter() {
+            body.push_str(&format!(
+                "\n-       -{} = {} ({}, {} bytes)",
+                file.name,
+                file.filename,
+                file.content_type,
+                file.size
+            ));
+        }
+    }else if is_form(request) {
+        for field in request.form().iter() {
+            let field_style = format!(
+                "\n-       -{} = {}", 
+                field.0, 
+                field.1
+            );
+            body.push_str(&field_style);
+        }
+    }else {
+        body.push_str(&format!("\n{}", String::from_utf8_lossy(&request.body)));
+    }
+
+    format!(
+        "\n------------------------ Start Request-------------------------\n\
+        -- Version: {}\n-- Type: {}\n-- Path: {}\n-- Path Params:{}\n-- Query Strings:{}\n-- Headers:{}\n-- Body:{}\n\
+        ------------------------ End  Request-------------------------",
         request.version, 
         request.method, 
         request.uri, 
-        query_strings, headers
-    );
-    println!("{}", printable);
-    println!("------------------------ End  Request-------------------------");
-}
\ No newline at end of file
+        path_params, query_strings, headers, body
+    )
+}
+
+fn is_form(request: &Request) -> bool {
+    request.header("Content-Type")
+        .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"))
+}
@@ -0,0 +1,85 @@
+use std::{collections::HashMap, sync::Mutex, time::{Duration, Instant}};
+use serde::Deserialize;
+use crate::error::Error;
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBy {
+    /// all the requests of the route share the limit
+    #[default]
+    Route,
+    /// every api key has its own limit
+    Key,
+    /// every client address has its own limit
+    Ip,
+}
+
+/// at most `requests` requests in every `window_secs` seconds, the others get 429.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub window_secs: u64,
+    #[serde(default)]
+    pub per: RateLimitBy,
+}
+
+impl RateLimit {
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.requests == 0 || self.window_secs == 0 {
+            return Err(Error::ConfigValidationError(
+                "`requests` and `window_secs` of the rate_limit must be more than 0".to_string()
+            ));
+        }
+        Ok(())
+    }
+
+}
+
+/// the state of a limit after a request.
+pub struct RateLimitState {
+    pub limit: u32,
+    pub remaining: u32,
+    /// seconds until the window starts over
+    pub reset_secs: u64,
+    pub exceeded: bool,
+}
+
+/// the fixed windows of a route, it lives as long as the process.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+
+    /// count a request of the `key` and return the state of its window.
+    pub fn hit(&self, limit: &RateLimit, key: &str) -> RateLimitState {
+        let window = Duration::from_secs(limit.window_secs);
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let (start, count) = windows.entry(key.to_string()).or_insert((now, 0));
+        if now.duration_since(*start) >= window {
+            *start = now;
+            *count = 0;
+        }
+
+        let exceeded = *count >= limit.requests;
+        if !exceeded {
+            *count += 1;
+        }
+        let elapsed = now.duration_since(*start);
+        RateLimitState {
+            limit: limit.requests,
+            remaining: limit.requests - *count,
+            reset_secs: window.saturating_sub(elapsed).as_secs_f64().ceil() as u64,
+            exceeded,
+        }
+    }
+
+    /// start all of the windows over.
+    pub fn reset(&self) {
+        self.windows.lock().unwrap().clear();
+    }
+
+}
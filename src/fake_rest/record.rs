@@ -0,0 +1,115 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use tokio::{net::TcpListener, sync::Mutex};
+use crate::error::{Error, FakeRestResult};
+use crate::server::{connection::Connection, proxy, request::{Request, RequestLimits}, response::Response, status::Status, stream::Stream};
+use super::{print, generated::{self, GeneratedRoute, GeneratedServer}, server_config::ConfigFormat};
+
+/// headers of the upstream response that are not worth keeping in the config.
+const SKIPPED_HEADERS: [&str; 3] = ["content-length", "date", "server"];
+
+struct Recorder {
+    upstream: String,
+    output: PathBuf,
+    server: Mutex<GeneratedServer>,
+}
+
+/// serve as a proxy to the `upstream` and write every request and response
+/// as a route to `output/server.toml`, the bodies are saved in `output/bodies`.
+pub async fn run(upstream: String, output: PathBuf, host: String, port: usize) -> FakeRestResult {
+    tokio::fs::create_dir_all(output.join("bodies")).await?;
+
+    let recorder = Arc::new(Recorder {
+        upstream,
+        output,
+        server: Mutex::new(GeneratedServer::new(host.clone(), port)),
+    });
+
+    let host_and_port = format!("{}:{}", host, port);
+    let listener = TcpListener::bind(&host_and_port).await?;
+    println!(
+        "Recording <{}> at <http://{}> into `{}`...",
+        recorder.upstream,
+        host_and_port,
+        recorder.output.join("server.toml").display()
+    );
+
+    loop {
+        let (socket, _) = listener.accept().await?;
+        let recorder = recorder.clone();
+        tokio::spawn(async move {
+            if let Err(e) = record(socket, &recorder).await {
+                println!("Error on recording the request: {}", e);
+            }
+        });
+    }
+}
+
+async fn record(socket: tokio::net::TcpStream, recorder: &Recorder) -> FakeRestResult {
+    let mut connection = Connection::new(Stream::Plain(socket));
+    let request = match connection.read_request(Duration::from_secs(5), Duration::from_secs(30), RequestLimits::default()).await? {
+        Some(request) => request,
+        None => return Ok(()),
+    };
+    let mut response = match proxy::forward(proxy::client(), &recorder.upstream, &request).await {
+        Ok(response) => response,
+        // the failures of the upstream are not recorded
+        Err(Error::ProxyError(e)) => {
+            let mut response = Response::text(Status::bad_gateway(), &format!("the upstream `{}` failed: {}", recorder.upstream, e));
+            response.headers.insert("Connection".to_string(), "close".to_string());
+            connection.respond(response).await?;
+            return Err(Error::ProxyError(e));
+        },
+        Err(e) => return Err(e),
+    };
+
+    let mut server = recorder.server.lock().await;
+    let route = save_route(recorder, server.data.len(), &request, &response).await?;
+    server.data.push(route);
+    let content = server.to_string(ConfigFormat::Toml)?;
+    tokio::fs::write(recorder.output.join("server.toml"), content).await?;
+    drop(server);
+
+    // one request per connection is enough for recording
+    response.headers.insert("Connection".to_string(), "close".to_string());
+    connection.respond(response).await?;
+    println!("{}", print::format_for_print(&request));
+    Ok(())
+}
+
+async fn save_route(recorder: &Recorder, index: usize, request: &Request, response: &Response) -> Result<GeneratedRoute, Error> {
+    let content_type = response.headers.get("Content-Type").map(|value| value.as_str()).unwrap_or("");
+
+    let file_name = generated::body_file_name(index, &request.method.to_string(), &request.uri, content_type);
+    let body_path = recorder.output.join("bodies").join(file_name);
+    tokio::fs::write(&body_path, &response.body).await?;
+
+    let result_headers = response.headers
+        .iter()
+        .filter(|(key, _)| !SKIPPED_HEADERS.contains(&key.to_lowercase().as_str()))
+        .map(|(key, value)| format!("{}: {}", key, value))
+        .chain(response.cookies.iter().map(|cookie| format!("Set-Cookie: {}", cookie)))
+        .collect();
+
+    let queries = if request.query_strings.is_empty() {
+        None
+    }else {
+        Some(request.query_strings.iter().map(|(key, value)| format!("{}={}", key, value)).collect())
+    };
+
+    let body_equals = match String::from_utf8(request.body.clone()) {
+        Ok(body) if !body.is_empty() => Some(body),
+        _ => None,
+    };
+
+    Ok(GeneratedRoute {
+        method: request.method.to_string(),
+        path: request.uri.clone(),
+        result_type: "file".to_string(),
+        result: body_path.to_string_lossy().to_string(),
+        status_code: response.status.code,
+        result_headers,
+        queries,
+        body_equals,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,118 @@
+use std::{path::PathBuf, sync::{Arc, RwLock}, time::{Duration, SystemTime}};
+use crate::server::auth::Auth;
+use super::server_config::{ConfigSource, Server};
+
+/// the server of a listener, it's swapped when the config is reloaded.
+pub type SharedServer = Arc<RwLock<Arc<Server>>>;
+
+/// the server to answer the next request with, the ones in flight keep their own.
+pub fn current(server: &SharedServer) -> Arc<Server> {
+    server.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// check the config files and the files read on their load every second and swap the
+/// servers of the listeners when one of them changes, is added or removed.
+///
+/// an invalid config or one with other listeners (host and port) is not applied, the old one keeps serving.
+/// the routes that didn't change keep their state, like the items of their resources and their sequences.
+/// the `file` and `dl` results are read on every request, so they're always up to date.
+pub async fn watch(source: ConfigSource, servers: Vec<SharedServer>) {
+    let mut files = watched_files(&source, &servers).await;
+    let mut modified = modified_times(&files);
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let new_files = watched_files(&source, &servers).await;
+        let new_modified = modified_times(&new_files);
+        if new_files == files && new_modified == modified {
+            continue;
+        }
+        files = new_files;
+        modified = new_modified;
+
+        let new_servers = match source.parse().await {
+            Ok(s) => s,
+            Err(e) => {
+                println!("Can't reload the config, the old one is kept: {}", e);
+                continue;
+            },
+        };
+        if !same_listeners(&servers, &new_servers) {
+            println!("The listeners of the config are changed, restart to apply it");
+            continue;
+        }
+        for (shared, mut server) in servers.iter().zip(new_servers) {
+            let old_server = current(shared);
+            server.metrics = old_server.metrics.clone();
+            server.journal = old_server.journal.clone();
+            keep_route_states(&old_server, &mut server);
+            // the free port that is picked for `port = 0`
+            server.config.port = old_server.config.port;
+            *shared.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(server);
+        }
+        files = watched_files(&source, &servers).await;
+        modified = modified_times(&files);
+        println!("Reloaded the config <{}>", source.path().display());
+    }
+}
+
+/// give the unchanged routes the call counts, sequences, resources, rate limits, script states and the
+/// admin switch of their old ones, the changed and the new routes start over.
+fn keep_route_states(old_server: &Server, server: &mut Server) {
+    // the same route can be repeated, like the requests of a recorded config, every old one is used once
+    let mut used = vec![false; old_server.data.len()];
+    for item in server.data.iter_mut() {
+        let found = old_server.data.iter().enumerate().find(|(index, old)| !used[*index] && old.fingerprint == item.fingerprint);
+        let Some((index, old)) = found else {
+            continue;
+        };
+        used[index] = true;
+        item.calls = old.calls.clone();
+        item.hits = old.hits.clone();
+        item.disabled = old.disabled.clone();
+        // a new seed file starts the items over, the `persist` ones are the same items anyway
+        if old.store.same_seed(&item.store) {
+            item.store = old.store.clone();
+        }
+        item.rate_limiter = old.rate_limiter.clone();
+        item.script_state = old.script_state.clone();
+    }
+}
+
+fn same_listeners(servers: &[SharedServer], new_servers: &[Server]) -> bool {
+    servers.len() == new_servers.len() && servers.iter().zip(new_servers).all(|(server, new_server)| {
+        let server = current(server);
+        server.config.host == new_server.config.host && (new_server.config.port == 0 || server.config.port == new_server.config.port)
+    })
+}
+
+/// the config files, their includes and the schemas, resource seeds, datasets and keys they point to.
+async fn watched_files(source: &ConfigSource, servers: &[SharedServer]) -> Vec<PathBuf> {
+    let mut files = source.files().await.unwrap_or_default();
+    for server in servers.iter().map(current) {
+        for item in server.data.iter() {
+            files.push(item.source.clone());
+            if let Some(body_schema) = &item.body_schema {
+                files.push(PathBuf::from(body_schema));
+            }
+            if matches!(item.result_type.as_str(), "resource" | "dataset") && !item.result.is_empty() {
+                files.push(PathBuf::from(item.result.as_str()));
+            }
+            if let Some(Auth::Bearer { jwt: Some(jwt), .. }) = &item.auth {
+                files.extend(jwt.public_key.iter().map(PathBuf::from));
+            }
+            if let Some(oauth2) = &item.oauth2 {
+                files.extend(oauth2.private_key.iter().map(PathBuf::from));
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    files
+}
+
+fn modified_times(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| std::fs::metadata(file).and_then(|metadata| metadata.modified()).ok())
+        .collect()
+}
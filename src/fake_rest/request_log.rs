@@ -0,0 +1,77 @@
+use std::{sync::Arc, time::{Duration, SystemTime}};
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use crate::server::{request::Request, response::Response};
+use super::log_file::{LogFile, Rotation};
+
+/// the bodies are cut after this many bytes by default.
+const DEFAULT_MAX_BODY_SIZE: usize = 4096;
+
+/// a json line for every request with the request and the response, their headers and bodies.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RequestLog {
+    pub file: String,
+    /// the bodies are cut after this many bytes, default is 4096
+    pub max_body_size: Option<usize>,
+    #[serde(flatten)]
+    pub rotation: Rotation,
+    /// the file is opened on the first request, it's shared between the clones of the config
+    #[serde(skip)]
+    writer: Arc<LogFile>,
+}
+
+impl RequestLog {
+
+    /// the logged part of the response, it's taken before the response is written.
+    pub fn response(&self, response: &Response) -> Value {
+        let mut logged = json!({
+            "status": response.status.code,
+            "headers": response.headers,
+        });
+        if !response.cookies.is_empty() {
+            logged["cookies"] = json!(response.cookies);
+        }
+        self.add_body(&mut logged, &response.body);
+        logged
+    }
+
+    pub async fn write(&self, request: &Request, response: Value, elapsed: Duration) {
+        let mut logged_request = json!({
+            "method": request.method.to_string(),
+            "uri": request.raw_uri,
+            "version": request.version,
+            "headers": request.headers,
+        });
+        self.add_body(&mut logged_request, &request.body);
+
+        let line = json!({
+            "time": httpdate::fmt_http_date(SystemTime::now()),
+            "client": request.peer_ip.map(|ip| ip.to_string()),
+            "duration_ms": elapsed.as_micros() as f64 / 1000.0,
+            "request": logged_request,
+            "response": response,
+        });
+        self.writer.append(&self.file, &self.rotation, &line.to_string()).await;
+    }
+
+    /// the text bodies are kept as they are and the binary ones in base64, `body_size` is the size before cutting.
+    fn add_body(&self, logged: &mut Value, body: &[u8]) {
+        if body.is_empty() {
+            return;
+        }
+        let max_body_size = self.max_body_size.unwrap_or(DEFAULT_MAX_BODY_SIZE);
+        let kept = &body[..body.len().min(max_body_size)];
+        match std::str::from_utf8(kept) {
+            Ok(text) => logged["body"] = json!(text),
+            // a multi-byte character can be cut at the end
+            Err(e) if e.error_len().is_none() => logged["body"] = json!(String::from_utf8_lossy(&kept[..e.valid_up_to()])),
+            Err(_) => logged["body_base64"] = json!(base64::engine::general_purpose::STANDARD.encode(kept)),
+        }
+        logged["body_size"] = json!(body.len());
+        if kept.len() < body.len() {
+            logged["body_truncated"] = json!(true);
+        }
+    }
+
+}
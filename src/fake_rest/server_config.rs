@@ -1,34 +1,1085 @@
-use std::path::PathBuf;
-use serde::Deserialize;
-use crate::server::request::Method;
+use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}, ops::Deref, path::{Path, PathBuf}, sync::{atomic::{AtomicBool, AtomicUsize}, Arc, Mutex}};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, de::DeserializeOwned};
+use crate::server::{request::Method, response, matcher::Requirement, fault::Fault, cookie::SetCookie, auth::Auth, oauth2::OAuth2, ip_filter::IpFilter, static_dir::Listing, callback::Callback, sse::Sse, websocket::WebSocket, graphql::GraphQl, grpc::Grpc, xml::{self, Soap}, dataset};
+use crate::error::Error;
+use super::{env, file_cache::FileCache, metrics::Metrics, journal::Journal, trace::Tracing, access_log::AccessLog, request_log::RequestLog, log_file::Log, delay::Delay, store::Store, rate_limit::{RateLimit, RateLimiter}};
+
+/// a config file, it's a single server or several `[[server]]` blocks on different ports.
+#[derive(Debug, Deserialize)]
+struct ConfigFile {
+    config: Option<Config>,
+    #[serde(default)]
+    api_keys: HashMap<String, String>,
+    /// the values of the routes of this file and its includes that don't set them
+    #[serde(default)]
+    defaults: Defaults,
+    /// the routes of the single server and the blocks without their own `data`
+    #[serde(default, deserialize_with = "routes")]
+    data: Vec<ServerDataSchema>,
+    /// files with more `[[data]]` routes, relative to this file
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    server: Vec<ServerBlock>,
+    /// the canonical path of the file
+    #[serde(skip)]
+    path: PathBuf,
+    /// the canonical paths of the included files
+    #[serde(skip)]
+    included: Vec<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServerBlock {
+    config: Config,
+    api_keys: Option<HashMap<String, String>>,
+    /// the defaults of the `data` of the block, default is the `[defaults]` of the file
+    defaults: Option<Defaults>,
+    #[serde(default, deserialize_with = "optional_routes")]
+    data: Option<Vec<ServerDataSchema>>,
+    include: Option<Vec<String>>,
+}
+
+/// the values of the routes that don't set them, to not repeat them on every route.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Defaults {
+    /// the `host` of the routes without one, to mock a whole service on a shared port
+    pub host: Option<String>,
+    /// the status of the routes without `status_code`, except the redirects
+    pub status_code: Option<usize>,
+    /// added before the `result_headers` of the routes and their responses, so they can override them
+    pub result_headers: Option<Vec<String>>,
+    /// the delay of the routes without `delay_ms` or `delay`
+    pub delay_ms: Option<u64>,
+    pub delay: Option<Delay>,
+}
+
+/// an included file, it can include other files too.
+#[derive(Debug, Deserialize)]
+struct IncludeFile {
+    #[serde(default, deserialize_with = "routes")]
+    data: Vec<ServerDataSchema>,
+    #[serde(default)]
+    include: Vec<String>,
+}
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Server {
     pub config: Config,
-    pub data: Vec<ServerDataSchema>
+    /// the api keys and their tenants, like `key-123 = "acme"`
+    #[serde(default)]
+    pub api_keys: HashMap<String, String>,
+    pub data: Vec<ServerDataSchema>,
+    /// the counters of the listener, they're moved to the reloaded server
+    #[serde(skip)]
+    pub metrics: Arc<Metrics>,
+    /// the requests of the listener for the admin api, they're moved to the reloaded server
+    #[serde(skip)]
+    pub journal: Arc<Journal>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
     pub host: String,
-    pub port: usize
+    pub port: usize,
+    /// forward the requests that don't match any route to this url, a failed upstream is answered with 502
+    pub fallback_proxy: Option<String>,
+    /// serve https with these pem files
+    pub tls: Option<TlsConfig>,
+    /// seconds to keep an idle connection open for the next request, default is 5
+    pub keep_alive_timeout: Option<u64>,
+    /// seconds to read the rest of a request after its first byte, default is 30, it's answered with 408 after that
+    pub read_timeout: Option<u64>,
+    /// seconds the client has to take each part of a response, default is 30, the connection is closed after that
+    pub write_timeout: Option<u64>,
+    /// the longest request line in bytes, default is 8 KiB, the longer ones are answered with 414
+    pub max_request_line: Option<usize>,
+    /// the most bytes of the request headers, default is 64 KiB, the bigger ones are answered with 431
+    pub max_header_size: Option<usize>,
+    /// the most bytes of a request body, default is no limit, the bigger ones are answered with 413
+    pub max_body_size: Option<usize>,
+    /// keep the files of the `file` and `dl` results in memory
+    pub file_cache: Option<FileCache>,
+    /// the most open connections, the others are answered with 503
+    pub max_connections: Option<usize>,
+    /// the connections over `max_connections` wait until one of the open connections is closed, instead of 503
+    #[serde(default)]
+    pub queue_connections: bool,
+    pub compression: Option<CompressionConfig>,
+    /// add an `ETag` made from the body to the responses and answer `If-None-Match` with 304
+    #[serde(default)]
+    pub etag: bool,
+    /// answer the preflight requests and add the `Access-Control-*` headers to the responses
+    pub cors: Option<CorsConfig>,
+    /// the header of the `api_keys`, default is `X-Api-Key`
+    pub api_key_header: Option<String>,
+    /// the clients that can use the server, the others get 403
+    pub ip_filter: Option<IpFilter>,
+    /// serve an OpenAPI 3 document of the routes on this path, like `/openapi.json`
+    pub openapi_path: Option<String>,
+    /// serve the prometheus metrics of the server on this path, like `/__metrics`
+    pub metrics_path: Option<String>,
+    /// serve the admin api under this path, like `/__admin`
+    pub admin_path: Option<String>,
+    /// the most requests that the admin api keeps, the oldest ones are dropped first, default is 1000
+    pub history_size: Option<usize>,
+    /// a prefix of the paths of all of the routes, like `/api/v2`
+    pub base_path: Option<String>,
+    /// log every request in the Common or Combined Log Format
+    pub access_log: Option<AccessLog>,
+    /// log every request and its response as a json line, with their headers and bodies
+    pub request_log: Option<RequestLog>,
+    /// write the requests and the errors of the server in a file instead of the stdout
+    pub log: Option<Log>,
+    /// send a span for every request to an OpenTelemetry collector
+    pub tracing: Option<Tracing>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CorsConfig {
+    /// default is any origin
+    pub allowed_origins: Option<Vec<String>>,
+    /// default is the methods of the path
+    pub allowed_methods: Option<Vec<String>>,
+    /// default is the headers that the preflight request asks for
+    pub allowed_headers: Option<Vec<String>>,
+    pub exposed_headers: Option<Vec<String>>,
+    #[serde(default)]
+    pub allow_credentials: bool,
+    /// seconds the browsers can cache the preflight response
+    pub max_age: Option<u64>,
+}
+
+/// compress the response bodies based on the `Accept-Encoding` of the request.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CompressionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// smaller bodies are not compressed, default is 1024 bytes
+    pub min_size: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TlsConfig {
+    pub cert: String,
+    pub key: String,
+    /// verify the client certificates with this ca
+    pub client_ca: Option<String>,
+    #[serde(default)]
+    pub client_auth: ClientAuth,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAuth {
+    /// reject the clients without a valid certificate
+    #[default]
+    Required,
+    /// accept the clients without a certificate too
+    Optional,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ServerDataSchema {
+    #[serde(default)]
     pub method: Method,
+    #[serde(default)]
     pub path: String,
+    pub path_regex: Option<String>,
+    #[serde(skip)]
+    pub compiled_path_regex: Option<Regex>,
+    /// only serve the requests with this `Host` header, like `api.example.test` or `*.example.test`
+    pub host: Option<String>,
+    #[serde(default)]
     pub result_type: String,
-    pub result: String,
+    #[serde(default)]
+    pub result: ResultValue,
+    pub result_headers: Option<Vec<String>>,
+    /// cookies to set with `Set-Cookie` headers
+    pub set_cookies: Option<Vec<SetCookie>>,
+    pub status_code: Option<usize>,
+    pub delay_ms: Option<u64>,
+    pub delay: Option<Delay>,
+    /// a fixed `ETag` of the route, it's used even when `etag` of the config is off
+    pub etag: Option<String>,
+    /// override `compression.enabled` of the config for this route
+    pub compress: Option<bool>,
+    pub fault: Option<Fault>,
+    /// write the body at this many kilobits per second, like a slow network
+    pub throttle_kbps: Option<u64>,
+    /// requests to send after the route is served, like webhooks
+    pub callbacks: Option<Vec<Callback>>,
+    /// credentials the request must have, answered with 401 otherwise
+    pub auth: Option<Auth>,
+    /// the clients that can use the route, the others get 403
+    pub ip_filter: Option<IpFilter>,
+    /// answer 429 to the requests over the limit
+    pub rate_limit: Option<RateLimit>,
+    /// the request counts of the `rate_limit`, it's shared between the clones of the route
+    #[serde(skip)]
+    pub rate_limiter: Arc<RateLimiter>,
+    /// the clients, users and signing key of an `oauth2_token` route
+    pub oauth2: Option<OAuth2>,
+    /// the events of an `sse` route
+    pub sse: Option<Sse>,
+    /// the messages of a `websocket` route
+    pub websocket: Option<WebSocket>,
+    /// the responses of the operations of a `graphql` route
+    pub graphql: Option<GraphQl>,
+    /// wrap the result of an `xml` route in the envelope of this soap version, `1.1` or `1.2`
+    pub soap: Option<Soap>,
+    /// the proto file and the status of a `grpc` route
+    pub grpc: Option<Grpc>,
+    pub headers: Option<Vec<String>>,
+    pub headers_status: Option<usize>,
+    pub headers_message: Option<String>,
+    #[serde(skip)]
+    pub compiled_headers: Vec<Requirement>,
+    pub queries: Option<Vec<String>>,
+    #[serde(skip)]
+    pub compiled_queries: Vec<Requirement>,
+    /// cookies the request must have, like the `queries`
+    pub cookies: Option<Vec<String>>,
+    #[serde(skip)]
+    pub compiled_cookies: Vec<Requirement>,
+    /// form fields the request must have, urlencoded or multipart, like the `queries`
+    pub form: Option<Vec<String>>,
+    #[serde(skip)]
+    pub compiled_form: Vec<Requirement>,
+    /// files the multipart request must have, the value is matched against the file name
+    pub files: Option<Vec<String>>,
+    #[serde(skip)]
+    pub compiled_files: Vec<Requirement>,
+    /// save the uploaded files of the multipart requests in this directory
+    pub upload_dir: Option<String>,
+    pub body_equals: Option<String>,
+    pub body_contains: Option<String>,
+    pub body_json: Option<serde_json::Value>,
+    /// the `SOAPAction` header or the `action` of the `Content-Type` of the request, to route the operations of one soap url
+    pub soap_action: Option<String>,
+    /// xpath expressions that must be true for the xml body, like `//GetUser` or `//GetUser/id = '42'`
+    pub body_xpath: Option<Vec<String>>,
+    /// the prefixes of the namespaces of the `body_xpath`, like `{ u = "urn:users" }`
+    pub xml_namespaces: Option<HashMap<String, String>>,
+    /// the subject of the client certificate must contain this, like `CN=alice`
+    pub client_cert_subject: Option<String>,
+    pub body_schema: Option<String>,
+    pub body_schema_status: Option<usize>,
+    #[serde(skip)]
+    pub compiled_body_schema: Option<Arc<jsonschema::Validator>>,
+    pub responses: Option<Vec<ResponseVariant>>,
+    /// the same response in several content types, picked by the `Accept` header of the request
+    pub representations: Option<Vec<Representation>>,
+    #[serde(default)]
+    pub sequence: Sequence,
+    /// how many times the route is served, it's shared between the clones of the route
+    #[serde(skip)]
+    pub calls: Arc<AtomicUsize>,
+    /// how many requests the route matched, for the admin api
+    #[serde(skip)]
+    pub hits: Arc<AtomicUsize>,
+    /// turned off by the admin api, the router skips it
+    #[serde(skip)]
+    pub disabled: Arc<AtomicBool>,
+    /// the id field of the items of a `resource` or a `dataset` route, default is `id`
+    pub id_field: Option<String>,
+    /// a json file to save the changes of a `resource` route and load them on the next start
+    pub persist: Option<String>,
+    /// serve the `index.html` of a `static_dir` route for the missing files, for the client side routing of the single page apps
+    #[serde(default)]
+    pub spa: bool,
+    /// the seconds a `command` result can run, default is 10, it's killed and answered with 504 after that
+    pub command_timeout: Option<u64>,
+    /// list the directories of a `static_dir` route without an `index.html` as `html` or `json`
+    pub listing: Option<Listing>,
+    /// items of a `resource` route, it's shared between the clones of the route
+    #[serde(skip)]
+    pub store: Arc<Store>,
+    /// the records of a `dataset` route
+    #[serde(skip)]
+    pub records: Arc<Vec<serde_json::Value>>,
+    /// the `state` of a `script` route, it's shared between the clones of the route
+    #[serde(skip)]
+    pub script_state: Arc<Mutex<rhai::Map>>,
+    /// the config file the route is written in
+    #[serde(skip)]
+    pub source: PathBuf,
+    /// the hash of the config of the route, a reload keeps the state of the routes with the same one
+    #[serde(skip)]
+    pub fingerprint: u64,
+}
+
+/// the `result` of a route, a string or a table or an array that is served as json.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultValue {
+    pub text: String,
+    /// it's written as a table or an array in the config and `text` is its json
+    pub json: bool,
+}
+
+impl<'de> Deserialize<'de> for ResultValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<ResultValue, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Text(String),
+            Json(serde_json::Value),
+        }
+
+        Ok(match Raw::deserialize(deserializer)? {
+            Raw::Text(text) => ResultValue { text, json: false },
+            Raw::Json(value) => ResultValue { text: value.to_string(), json: true },
+        })
+    }
+}
+
+impl From<String> for ResultValue {
+    fn from(text: String) -> ResultValue {
+        ResultValue { text, json: false }
+    }
+}
+
+impl Deref for ResultValue {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.text
+    }
+}
+
+impl std::fmt::Display for ResultValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// a response of the route, the fields that are set override the ones of the route.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ResponseVariant {
+    pub result_type: Option<String>,
+    pub result: Option<ResultValue>,
     pub result_headers: Option<Vec<String>>,
+    pub set_cookies: Option<Vec<SetCookie>>,
     pub status_code: Option<usize>,
+    pub delay_ms: Option<u64>,
+    pub fault: Option<Fault>,
+    /// the chance of the response with `sequence = "random"`, default is 1
+    pub weight: Option<u32>,
+    /// the response is only served when the request matches this
+    pub when: Option<When>,
+}
+
+/// conditions of a response variant, checked in order before the variants without `when`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct When {
     pub headers: Option<Vec<String>>,
-    pub queries: Option<Vec<String>>
+    #[serde(skip)]
+    pub compiled_headers: Vec<Requirement>,
+    pub queries: Option<Vec<String>>,
+    #[serde(skip)]
+    pub compiled_queries: Vec<Requirement>,
+    pub cookies: Option<Vec<String>>,
+    #[serde(skip)]
+    pub compiled_cookies: Vec<Requirement>,
+    /// the tenant of the api key of the request
+    pub tenant: Option<String>,
+    /// json paths of the body like `user.role` and their expected values
+    pub json_path: Option<HashMap<String, serde_json::Value>>,
+}
+
+/// a content type of the route, the fields that are set override the ones of the route.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Representation {
+    pub content_type: String,
+    pub result_type: Option<String>,
+    pub result: Option<ResultValue>,
+    pub status_code: Option<usize>,
+}
+
+/// how the responses of the `responses` list are served.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Sequence {
+    /// one after another and keep serving the last response at the end
+    #[default]
+    Stick,
+    /// one after another and start over from the first response at the end
+    Repeat,
+    /// pick a random one based on the `weight`s
+    Random,
+}
+
+
+/// the language of a config file.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+
+    /// the format of the `.toml` and `.json` files, `None` for the other extensions.
+    pub fn from_path(path: &Path) -> Option<ConfigFormat> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Some(ConfigFormat::Json),
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Some(ConfigFormat::Toml),
+            _ => None,
+        }
+    }
+
+    fn parse<T: DeserializeOwned>(&self, content: &str) -> Result<T, String> {
+        match self {
+            ConfigFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+        }
+    }
+
+}
+
+/// where the config is read from.
+#[derive(Debug, Clone)]
+pub enum ConfigSource {
+    /// a config file, in the given format or the one of its extension (default toml)
+    File(PathBuf, Option<ConfigFormat>),
+    /// every `.toml` and `.json` file of the directory tree, merged like a single file
+    Dir(PathBuf),
+    /// the content of a config, its relative paths like the includes are read from the current directory
+    Text(String, ConfigFormat),
+}
+
+/// the path of the routes of a `Text` config, it's not a real file.
+const TEXT_PATH: &str = "<text>";
+
+impl ConfigSource {
+
+    pub fn path(&self) -> &Path {
+        match self {
+            ConfigSource::File(path, _) | ConfigSource::Dir(path) => path,
+            ConfigSource::Text(..) => Path::new(TEXT_PATH),
+        }
+    }
+
+    /// parse the servers of the config.
+    ///
+    /// the `[[server]]` blocks without `data` or `include` share the top level routes, along with their
+    /// call counts, resources and rate limits.
+    pub async fn parse(&self) -> Result<Vec<Server>, Error> {
+        let files = match self {
+            ConfigSource::File(path, format) => vec![read_config_file(path, *format).await?],
+            ConfigSource::Dir(dir) => {
+                let mut files = Vec::new();
+                for path in config_files(dir).await? {
+                    files.push(read_config_file(&path, None).await?);
+                }
+                skip_included(files)
+            },
+            ConfigSource::Text(content, format) => {
+                let path = std::env::current_dir()?.join(TEXT_PATH);
+                vec![parse_config_file(&path, content, *format).await?]
+            },
+        };
+        build_servers(files).await
+    }
+
+    /// the config files, the directory is listed on every call to find the new files too.
+    pub async fn files(&self) -> Result<Vec<PathBuf>, Error> {
+        match self {
+            ConfigSource::File(path, _) => Ok(vec![path.clone()]),
+            ConfigSource::Dir(dir) => config_files(dir).await,
+            ConfigSource::Text(..) => Ok(Vec::new()),
+        }
+    }
+
+}
+
+/// read the config file and the routes of its includes.
+async fn read_config_file(path: &Path, format: Option<ConfigFormat>) -> Result<ConfigFile, Error> {
+    let content = tokio::fs::read_to_string(path).await.map_err(|e| Error::ConfigFileOpenError(
+        format!("can't read the config `{}`: {}", path.display(), e)
+    ))?;
+    let format = format.or_else(|| ConfigFormat::from_path(path)).unwrap_or(ConfigFormat::Toml);
+    let mut config_file = parse_config_file(path, &content, format).await?;
+    config_file.path = tokio::fs::canonicalize(path).await?;
+    Ok(config_file)
+}
+
+/// parse the content of the config file at `path` and load the routes of its includes.
+async fn parse_config_file(path: &Path, content: &str, format: ConfigFormat) -> Result<ConfigFile, Error> {
+    let content = interpolate_env(path, content)?;
+    let mut config_file: ConfigFile = format.parse(&content).map_err(|e| Error::ConfigParsingError(
+        format!("in `{}`: {}", path.display(), e)
+    ))?;
+    config_file.path = path.to_path_buf();
+
+    set_source(&mut config_file.data, path);
+    let include = std::mem::take(&mut config_file.include);
+    config_file.included = load_includes(path, format, &include, &mut config_file.data).await?;
+    apply_defaults(&config_file.defaults, &mut config_file.data);
+    for block in config_file.server.iter_mut() {
+        if block.data.is_none() && block.include.is_none() {
+            continue;
+        }
+        let mut data = block.data.take().unwrap_or_default();
+        set_source(&mut data, path);
+        let included = load_includes(path, format, &block.include.take().unwrap_or_default(), &mut data).await?;
+        config_file.included.extend(included);
+        apply_defaults(block.defaults.as_ref().unwrap_or(&config_file.defaults), &mut data);
+        block.data = Some(data);
+    }
+    Ok(config_file)
 }
 
+/// fill the fields that the routes don't set from the `defaults`.
+fn apply_defaults(defaults: &Defaults, data: &mut [ServerDataSchema]) {
+    for item in data.iter_mut() {
+        if item.host.is_none() {
+            item.host = defaults.host.clone();
+        }
+        // the redirects have their own default status
+        if item.status_code.is_none() && item.result_type != "redirect" {
+            item.status_code = defaults.status_code;
+        }
+        if item.delay_ms.is_none() && item.delay.is_none() {
+            item.delay_ms = defaults.delay_ms;
+            item.delay = defaults.delay.clone();
+        }
+        if let Some(headers) = &defaults.result_headers {
+            item.result_headers = Some(with_default_headers(headers, item.result_headers.take()));
+            // the responses with `result_headers` replace the ones of the route
+            for response in item.responses.iter_mut().flatten() {
+                if let Some(result_headers) = response.result_headers.take() {
+                    response.result_headers = Some(with_default_headers(headers, Some(result_headers)));
+                }
+            }
+        }
+    }
+}
+
+/// the later headers win, so the default ones go first.
+fn with_default_headers(defaults: &[String], headers: Option<Vec<String>>) -> Vec<String> {
+    defaults.iter().cloned().chain(headers.into_iter().flatten()).collect()
+}
+
+fn interpolate_env(path: &Path, content: &str) -> Result<String, Error> {
+    env::interpolate(content).map_err(|missing| Error::ConfigValidationError(format!(
+        "missing environment variables in `{}`: {}",
+        path.display(),
+        missing.join(", ")
+    )))
+}
+
+/// the `.toml` and `.json` files of the directory tree, sorted by their path.
+/// the json files that aren't an object, like the items of the `resource` routes, are not configs.
+async fn config_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await.map_err(|e| Error::ConfigFileOpenError(
+            format!("can't read the config dir `{}`: {}", dir.display(), e)
+        ))?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let is_config = match ConfigFormat::from_path(&path) {
+                Some(ConfigFormat::Toml) => true,
+                Some(ConfigFormat::Json) => is_json_object(&path).await,
+                None => false,
+            };
+            if is_config {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+async fn is_json_object(path: &Path) -> bool {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => serde_json::from_str::<serde_json::Value>(&content).is_ok_and(|value| value.is_object()),
+        Err(_) => false,
+    }
+}
+
+/// drop the files of the directory that are included by the other ones, their routes are already
+/// merged into those. the files that nobody includes go first, so a cycle of includes keeps one of them.
+fn skip_included(mut files: Vec<ConfigFile>) -> Vec<ConfigFile> {
+    let included: Vec<PathBuf> = files.iter().flat_map(|file| file.included.clone()).collect();
+    files.sort_by_key(|file| included.contains(&file.path));
+
+    let mut merged: Vec<PathBuf> = Vec::new();
+    let mut kept = Vec::new();
+    for file in files {
+        if merged.contains(&file.path) {
+            continue;
+        }
+        merged.extend(file.included.iter().cloned());
+        kept.push(file);
+    }
+    kept
+}
+
+/// merge the files into the servers, every `[config]` serves the routes of all of the files.
+async fn build_servers(files: Vec<ConfigFile>) -> Result<Vec<Server>, Error> {
+    let mut data = Vec::new();
+    let mut api_keys = HashMap::new();
+    let mut configs = Vec::new();
+    let mut blocks = Vec::new();
+    for mut file in files {
+        data.append(&mut file.data);
+        api_keys.extend(file.api_keys);
+        configs.extend(file.config);
+        blocks.append(&mut file.server);
+    }
+    load_data(&mut data).await?;
+
+    let mut servers: Vec<Server> = configs
+        .into_iter()
+        .map(|config| Server { config, api_keys: api_keys.clone(), data: data.clone(), metrics: Default::default(), journal: Default::default() })
+        .collect();
+    for block in blocks {
+        let data = match block.data {
+            Some(mut data) => {
+                load_data(&mut data).await?;
+                data
+            },
+            None => data.clone(),
+        };
+        let api_keys = block.api_keys.unwrap_or_else(|| api_keys.clone());
+        servers.push(Server { config: block.config, api_keys, data, metrics: Default::default(), journal: Default::default() });
+    }
+    if servers.is_empty() {
+        return Err(Error::ConfigValidationError(
+            "one of `[config]` or `[[server]]` is required".to_string()
+        ));
+    }
+
+    let mut listeners = Vec::new();
+    for server in servers.iter_mut() {
+        let host_and_port = format!("{}:{}", server.config.host, server.config.port);
+        if listeners.contains(&host_and_port) {
+            return Err(Error::ConfigValidationError(
+                format!("more than one server listens on `{}`", host_and_port)
+            ));
+        }
+        listeners.push(host_and_port);
+        if let Some(ip_filter) = &mut server.config.ip_filter {
+            ip_filter.compile()?;
+        }
+        if server.config.max_connections == Some(0) {
+            return Err(Error::ConfigValidationError(
+                format!("`max_connections` of the server on port {} must be more than 0", server.config.port)
+            ));
+        }
+        if let Some(base_path) = &server.config.base_path {
+            apply_base_path(base_path, &mut server.data)?;
+        }
+    }
+    Ok(servers)
+}
+
+/// prepend the base path to the paths of the routes, the `path_regex`es have to start with it too.
+fn apply_base_path(base_path: &str, data: &mut [ServerDataSchema]) -> Result<(), Error> {
+    let base_path = format!("/{}", base_path.trim_matches('/'));
+    if base_path == "/" {
+        return Ok(());
+    }
+    for item in data.iter_mut() {
+        if let Some(path_regex) = &item.path_regex {
+            let path_regex = match path_regex.strip_prefix('^') {
+                Some(rest) => format!("^{}{}", regex::escape(&base_path), rest),
+                None => format!("^{}.*(?:{})", regex::escape(&base_path), path_regex),
+            };
+            let regex = Regex::new(&path_regex).map_err(|e| Error::ConfigValidationError(
+                format!("invalid path_regex `{}`: {}", path_regex, e)
+            ))?;
+            item.compiled_path_regex = Some(regex);
+            item.path_regex = Some(path_regex);
+        }else if item.path == "/" {
+            item.path = base_path.clone();
+        }else {
+            item.path = format!("{}{}", base_path, item.path);
+        }
+    }
+    Ok(())
+}
+
+fn set_source(data: &mut [ServerDataSchema], path: &Path) {
+    for item in data.iter_mut() {
+        item.source = path.to_path_buf();
+    }
+}
+
+/// append the routes of the included files and return their canonical paths, every file is read once
+/// and its routes come before the ones of the files it includes.
+/// the files without a `.toml` or `.json` extension have the format of the file that includes them.
+async fn load_includes(path: &Path, format: ConfigFormat, include: &[String], data: &mut Vec<ServerDataSchema>) -> Result<Vec<PathBuf>, Error> {
+    // the `Text` configs are not real files
+    let root = tokio::fs::canonicalize(path).await.unwrap_or_else(|_| path.to_path_buf());
+    let mut visited = vec![root.clone()];
+    let mut pending: Vec<(PathBuf, ConfigFormat)> = include.iter().rev().map(|file| included_file(path, format, file)).collect();
+    while let Some((file, format)) = pending.pop() {
+        let canonical = tokio::fs::canonicalize(&file).await.map_err(|e| Error::ConfigFileOpenError(
+            format!("can't read the include `{}`: {}", file.display(), e)
+        ))?;
+        if visited.contains(&canonical) {
+            continue;
+        }
+        visited.push(canonical);
+
+        let content = tokio::fs::read_to_string(&file).await.map_err(|e| Error::ConfigFileOpenError(
+            format!("can't read the include `{}`: {}", file.display(), e)
+        ))?;
+        let content = interpolate_env(&file, &content)?;
+        let mut included: IncludeFile = format.parse(&content).map_err(|e| Error::ConfigParsingError(
+            format!("in the include `{}`: {}", file.display(), e)
+        ))?;
+        set_source(&mut included.data, &file);
+        data.append(&mut included.data);
+        pending.extend(included.include.iter().rev().map(|include| included_file(&file, format, include)));
+    }
+    visited.retain(|file| *file != root);
+    Ok(visited)
+}
+
+/// the path of the include relative to the file that includes it, and its format.
+fn included_file(path: &Path, format: ConfigFormat, file: &str) -> (PathBuf, ConfigFormat) {
+    let file = path.parent().unwrap_or(Path::new("")).join(file);
+    let format = ConfigFormat::from_path(&file).unwrap_or(format);
+    (file, format)
+}
+
+/// the routes of different files with the same method, path and requirements, the later one could
+/// never be served. the same file can have them, like the repeated requests of a recorded config.
+fn check_duplicates(data: &[ServerDataSchema]) -> Result<(), Error> {
+    let mut routes: HashMap<String, &Path> = HashMap::new();
+    for item in data.iter() {
+        let key = format!(
+            "{:?}",
+            (
+                (&item.method, &item.host), &item.path, &item.path_regex, &item.headers, &item.queries, &item.cookies,
+                &item.form, &item.files, &item.body_equals, &item.body_contains, &item.body_json, (&item.soap_action, &item.body_xpath, &item.client_cert_subject),
+            )
+        );
+        match routes.get(&key) {
+            Some(source) if *source != item.source => return Err(Error::ConfigValidationError(format!(
+                "the route `{} {}` of `{}` is already defined in `{}`",
+                item.method,
+                item.path_regex.as_ref().unwrap_or(&item.path),
+                item.source.display(),
+                source.display()
+            ))),
+            Some(_) => {},
+            None => {
+                routes.insert(key, &item.source);
+            },
+        }
+    }
+    Ok(())
+}
+
+async fn load_data(data: &mut [ServerDataSchema]) -> Result<(), Error> {
+    check_duplicates(data)?;
+    validate_data(data)?;
+    load_body_schemas(data).await?;
+    load_resources(data).await?;
+    load_datasets(data).await?;
+    load_auth(data).await?;
+    load_grpc(data)?;
+    set_fingerprints(data);
+    Ok(())
+}
+
+/// the routes of a config file, each one with the hash of its table as it's written.
+fn routes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<ServerDataSchema>, D::Error> {
+    let tables = Vec::<serde_json::Value>::deserialize(deserializer)?;
+    tables.into_iter().map(|table| {
+        let mut hasher = DefaultHasher::new();
+        table.to_string().hash(&mut hasher);
+        let name = format!(
+            "{} {}",
+            table.get("method").and_then(|method| method.as_str()).unwrap_or("GET"),
+            table.get("path").or_else(|| table.get("path_regex")).and_then(|path| path.as_str()).unwrap_or_default()
+        );
+        let mut item: ServerDataSchema = serde_json::from_value(table).map_err(|e| serde::de::Error::custom(
+            format!("{} in the route `{}`", e, name)
+        ))?;
+        item.fingerprint = hasher.finish();
+        Ok(item)
+    }).collect()
+}
+
+fn optional_routes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<ServerDataSchema>>, D::Error> {
+    routes(deserializer).map(Some)
+}
+
+/// add the file, the defaults and the base path of the routes to the hash of their tables, they're
+/// only the same routes when all of them are the same.
+fn set_fingerprints(data: &mut [ServerDataSchema]) {
+    for item in data.iter_mut() {
+        let mut hasher = DefaultHasher::new();
+        item.fingerprint.hash(&mut hasher);
+        item.source.hash(&mut hasher);
+        (&item.host, &item.path, &item.path_regex, item.status_code, item.delay_ms).hash(&mut hasher);
+        format!("{:?}", item.delay).hash(&mut hasher);
+        item.result_headers.hash(&mut hasher);
+        for response in item.responses.iter().flatten() {
+            response.result_headers.hash(&mut hasher);
+        }
+        item.fingerprint = hasher.finish();
+    }
+}
 
-pub async fn parse_config_file(path: PathBuf) -> tokio::io::Result<Server> {
-    let content = tokio::fs::read_to_string(path).await?;
-    let parsed_server = toml::from_str(&content)?;
-    Ok(parsed_server)
-}
\ No newline at end of file
+fn validate_data(data: &mut [ServerDataSchema]) -> Result<(), Error> {
+    for item in data.iter_mut() {
+        if let Some(path_regex) = &item.path_regex {
+            let regex = Regex::new(path_regex).map_err(|e| Error::ConfigValidationError(
+                format!("invalid path_regex `{}`: {}", path_regex, e)
+            ))?;
+            item.compiled_path_regex = Some(regex);
+        }else if item.path.is_empty() {
+            return Err(Error::ConfigValidationError(
+                format!("one of `path` or `path_regex` is required for `{} {}`", item.method, item.result)
+            ));
+        }
+
+        // a table or an array is a json body by itself
+        if item.result_type.is_empty() && item.result.json {
+            item.result_type = "direct".to_string();
+        }
+
+        match &item.responses {
+            Some(responses) if responses.is_empty() => return Err(Error::ConfigValidationError(
+                format!("`responses` of `{} {}` can't be empty", item.method, item.path)
+            )),
+            Some(responses) if responses.iter().all(|response| response.weight == Some(0)) => return Err(Error::ConfigValidationError(
+                format!("at least one of the `responses` of `{} {}` needs a weight", item.method, item.path)
+            )),
+            None if item.result_type.is_empty() => return Err(Error::ConfigValidationError(
+                format!("`result_type` is required for `{} {}`", item.method, item.path)
+            )),
+            _ => {},
+        }
+
+        if item.result_type == "sse" && item.sse.is_none() {
+            return Err(Error::ConfigValidationError(
+                format!("`sse` is required for the sse route `{} {}`", item.method, item.path)
+            ));
+        }
+
+        if item.result_type == "websocket" {
+            let Some(websocket) = &item.websocket else {
+                return Err(Error::ConfigValidationError(
+                    format!("`websocket` is required for the websocket route `{} {}`", item.method, item.path)
+                ));
+            };
+            for binary in websocket.messages.iter().filter_map(|message| message.binary.as_ref()) {
+                response::decode_base64(binary).map_err(|e| Error::ConfigValidationError(
+                    format!("a binary websocket message of `{} {}` is invalid: {}", item.method, item.path, e)
+                ))?;
+            }
+        }
+
+        if item.result_type == "graphql" && item.graphql.is_none() {
+            return Err(Error::ConfigValidationError(
+                format!("`graphql` is required for the graphql route `{} {}`", item.method, item.path)
+            ));
+        }
+
+        for expression in item.body_xpath.iter().flatten() {
+            xml::compile_xpath(expression).map_err(|e| Error::ConfigValidationError(
+                format!("the body_xpath `{}` of `{} {}` is invalid: {}", expression, item.method, item.path, e)
+            ))?;
+        }
+
+        if item.soap.is_some() && item.result_type != "xml" {
+            return Err(Error::ConfigValidationError(
+                format!("`soap` of `{} {}` is only for the xml routes", item.method, item.path)
+            ));
+        }
+
+        if item.result_type == "grpc" && item.grpc.is_none() {
+            return Err(Error::ConfigValidationError(
+                format!("`grpc` is required for the grpc route `{} {}`", item.method, item.path)
+            ));
+        }
+
+        if item.result_type == "oauth2_token" && item.oauth2.is_none() {
+            return Err(Error::ConfigValidationError(
+                format!("`oauth2` is required for the oauth2_token route `{} {}`", item.method, item.path)
+            ));
+        }
+
+        let results = std::iter::once((Some(&item.result_type), Some(&item.result)))
+            .chain(item.responses.iter().flatten().map(|response| (response.result_type.as_ref(), response.result.as_ref())))
+            .chain(item.representations.iter().flatten().map(|representation| (representation.result_type.as_ref(), representation.result.as_ref())));
+        for (result_type, result) in results {
+            let result_type = result_type.unwrap_or(&item.result_type);
+            let result = result.unwrap_or(&item.result);
+            if result_type == "base64" {
+                response::decode_base64(result).map_err(|e| Error::ConfigValidationError(
+                    format!("the base64 result of `{} {}` is invalid: {}", item.method, item.path, e)
+                ))?;
+            }
+        }
+
+        if item.result_type == "redirect" && !matches!(item.status_code, None | Some(301 | 302 | 303 | 307 | 308)) {
+            return Err(Error::ConfigValidationError(
+                format!("`status_code` of the redirect `{} {}` must be one of 301, 302, 303, 307 or 308", item.method, item.path)
+            ));
+        }
+
+        // the status line can only have the codes of http, the defaults are already set on the routes
+        let statuses = [item.status_code, item.headers_status, item.body_schema_status]
+            .into_iter()
+            .chain(item.responses.iter().flatten().map(|response| response.status_code))
+            .chain(item.representations.iter().flatten().map(|representation| representation.status_code));
+        if let Some(status) = statuses.flatten().find(|status| !(100..600).contains(status)) {
+            return Err(Error::ConfigValidationError(
+                format!("status code `{}` of `{} {}` must be between 100 and 599", status, item.method, item.path)
+            ));
+        }
+
+        if item.representations.as_ref().is_some_and(|representations| representations.is_empty()) {
+            return Err(Error::ConfigValidationError(
+                format!("`representations` of `{} {}` can't be empty", item.method, item.path)
+            ));
+        }
+
+        if let Some(delay) = &item.delay {
+            delay.validate()?;
+        }
+
+        if let Some(rate_limit) = &item.rate_limit {
+            rate_limit.validate()?;
+        }
+
+        if let Some(ip_filter) = &mut item.ip_filter {
+            ip_filter.compile()?;
+        }
+
+        if let Some(headers) = &item.headers {
+            for header in headers.iter() {
+                item.compiled_headers.push(Requirement::parse(header, ':')?);
+            }
+        }
+
+        if let Some(queries) = &item.queries {
+            for query in queries.iter() {
+                item.compiled_queries.push(Requirement::parse(query, '=')?);
+            }
+        }
+
+        for field in item.form.iter().flatten() {
+            item.compiled_form.push(Requirement::parse(field, '=')?);
+        }
+
+        for file in item.files.iter().flatten() {
+            item.compiled_files.push(Requirement::parse(file, '=')?);
+        }
+
+        let variant_cookies = item.responses.iter().flatten().flat_map(|response| response.set_cookies.iter().flatten());
+        if let Some(cookie) = item.set_cookies.iter().flatten().chain(variant_cookies).find(|cookie| cookie.name.is_empty()) {
+            return Err(Error::ConfigValidationError(
+                format!("the `name` of the cookie `{}` of `{} {}` can't be empty", cookie.to_header(), item.method, item.path)
+            ));
+        }
+
+        for cookie in item.cookies.iter().flatten() {
+            item.compiled_cookies.push(Requirement::parse(cookie, '=')?);
+        }
+
+        for response in item.responses.iter_mut().flatten() {
+            if let Some(when) = &mut response.when {
+                for header in when.headers.iter().flatten() {
+                    when.compiled_headers.push(Requirement::parse(header, ':')?);
+                }
+                for query in when.queries.iter().flatten() {
+                    when.compiled_queries.push(Requirement::parse(query, '=')?);
+                }
+                for cookie in when.cookies.iter().flatten() {
+                    when.compiled_cookies.push(Requirement::parse(cookie, '=')?);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn load_body_schemas(data: &mut [ServerDataSchema]) -> Result<(), Error> {
+    for item in data.iter_mut() {
+        if let Some(schema_path) = &item.body_schema {
+            let content = tokio::fs::read_to_string(schema_path).await.map_err(|e| Error::ConfigFileOpenError(
+                format!("can't read body_schema `{}`: {}", schema_path, e)
+            ))?;
+            let schema: serde_json::Value = serde_json::from_str(&content).map_err(|e| Error::ConfigValidationError(
+                format!("body_schema `{}` is not a valid json: {}", schema_path, e)
+            ))?;
+            let validator = jsonschema::validator_for(&schema).map_err(|e| Error::ConfigValidationError(
+                format!("body_schema `{}` is not a valid json schema: {}", schema_path, e)
+            ))?;
+            item.compiled_body_schema = Some(Arc::new(validator));
+        }
+    }
+    Ok(())
+}
+
+async fn load_auth(data: &mut [ServerDataSchema]) -> Result<(), Error> {
+    for item in data.iter_mut() {
+        if let Some(auth) = &mut item.auth {
+            auth.load().await?;
+        }
+        if let Some(oauth2) = &mut item.oauth2 {
+            oauth2.load().await?;
+        }
+    }
+    Ok(())
+}
+
+/// read the records of the `dataset` routes from their `result` file.
+async fn load_datasets(data: &mut [ServerDataSchema]) -> Result<(), Error> {
+    for item in data.iter_mut().filter(|item| item.result_type == "dataset") {
+        item.records = Arc::new(dataset::load(&item.result).await?);
+    }
+    Ok(())
+}
+
+/// compile the proto files of the `grpc` routes and find their methods.
+fn load_grpc(data: &mut [ServerDataSchema]) -> Result<(), Error> {
+    for item in data.iter_mut() {
+        if let Some(grpc) = &mut item.grpc {
+            grpc.load(&item.path)?;
+        }
+    }
+    Ok(())
+}
+
+/// seed the store of the `resource` routes from the json array of their `persist` file
+/// if it exists, otherwise from their `result` file.
+async fn load_resources(data: &mut [ServerDataSchema]) -> Result<(), Error> {
+    for item in data.iter_mut().filter(|item| item.result_type == "resource") {
+        let persist = item.persist.as_ref().map(PathBuf::from);
+        let seed = match &persist {
+            Some(persist) if persist.is_file() => persist.to_string_lossy().to_string(),
+            _ => item.result.to_string(),
+        };
+
+        let items = if seed.is_empty() {
+            Vec::new()
+        }else {
+            let content = tokio::fs::read_to_string(&seed).await.map_err(|e| Error::ConfigFileOpenError(
+                format!("can't read the resource file `{}`: {}", seed, e)
+            ))?;
+            serde_json::from_str(&content).map_err(|e| Error::ConfigValidationError(
+                format!("the resource file `{}` must be a json array: {}", seed, e)
+            ))?
+        };
+        item.store = Arc::new(Store::new(items, persist));
+    }
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use std::{path::PathBuf, sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex}};
+use serde_json::Value;
+
+/// in memory items of a `resource` route, it lives as long as the process.
+/// with a `persist` file, every change is written to it too.
+#[derive(Debug, Default)]
+pub struct Store {
+    items: Mutex<Vec<Value>>,
+    /// the items the store is loaded with, for the reset
+    initial: Vec<Value>,
+    persist: Option<PathBuf>,
+    /// the number of the last change, and the one that is written to the `persist` file
+    version: AtomicU64,
+    written: Arc<tokio::sync::Mutex<u64>>,
+}
+
+impl Store {
+
+    pub fn new(items: Vec<Value>, persist: Option<PathBuf>) -> Self {
+        Store { items: Mutex::new(items.clone()), initial: items, persist, ..Default::default() }
+    }
+
+    /// whether the stores are loaded with the same items.
+    pub fn same_seed(&self, other: &Store) -> bool {
+        self.initial == other.initial
+    }
+
+    /// go back to the items the store is loaded with.
+    pub fn reset(&self) {
+        let mut items = self.items.lock().unwrap();
+        *items = self.initial.clone();
+        self.save(&items);
+    }
+
+    pub fn list(&self) -> Vec<Value> {
+        self.items.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id_field: &str, id: &str) -> Option<Value> {
+        self.items
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|item| has_id(item, id_field, id))
+            .cloned()
+    }
+
+    /// add the item and give it an id if it doesn't have one.
+    pub fn create(&self, id_field: &str, mut item: Value) -> Value {
+        let mut items = self.items.lock().unwrap();
+        if let Value::Object(object) = &mut item {
+            if !object.contains_key(id_field) {
+                object.insert(id_field.to_string(), next_id(&items, id_field));
+            }
+        }
+        items.push(item.clone());
+        self.save(&items);
+        item
+    }
+
+    /// replace the whole item, the id stays the same.
+    pub fn replace(&self, id_field: &str, id: &str, mut item: Value) -> Option<Value> {
+        let mut items = self.items.lock().unwrap();
+        let current = items.iter_mut().find(|item| has_id(item, id_field, id))?;
+        if let (Value::Object(object), Some(current_id)) = (&mut item, current.get(id_field)) {
+            object.insert(id_field.to_string(), current_id.clone());
+        }
+        *current = item.clone();
+        self.save(&items);
+        Some(item)
+    }
+
+    /// merge the fields of the given object into the item.
+    pub fn update(&self, id_field: &str, id: &str, fields: Value) -> Option<Value> {
+        let mut items = self.items.lock().unwrap();
+        let current = items.iter_mut().find(|item| has_id(item, id_field, id))?;
+        if let (Value::Object(current_object), Value::Object(fields)) = (&mut *current, fields) {
+            for (key, value) in fields {
+                if key != id_field {
+                    current_object.insert(key, value);
+                }
+            }
+        }
+        let updated = current.clone();
+        self.save(&items);
+        Some(updated)
+    }
+
+    pub fn delete(&self, id_field: &str, id: &str) -> Option<Value> {
+        let mut items = self.items.lock().unwrap();
+        let position = items.iter().position(|item| has_id(item, id_field, id))?;
+        let deleted = items.remove(position);
+        self.save(&items);
+        Some(deleted)
+    }
+
+    /// write the items to the `persist` file. they're serialized while they're locked and written after
+    /// that in the background, the older changes that are late are not written over the newer ones.
+    fn save(&self, items: &[Value]) {
+        let path = match &self.persist {
+            Some(path) => path.clone(),
+            None => return,
+        };
+
+        let content = match serde_json::to_string_pretty(items) {
+            Ok(content) => content,
+            Err(e) => return println!("failed to serialize the resource `{}`: {}", path.display(), e),
+        };
+        let version = self.version.fetch_add(1, Ordering::SeqCst) + 1;
+        let written = self.written.clone();
+        tokio::spawn(async move {
+            let mut written = written.lock().await;
+            if *written > version {
+                return;
+            }
+            // write to a temporary file first, so a crash never leaves a half written file
+            let temp_path = path.with_extension("tmp");
+            let result = match tokio::fs::write(&temp_path, content).await {
+                Ok(()) => tokio::fs::rename(&temp_path, &path).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => *written = version,
+                Err(e) => println!("failed to persist the resource `{}`: {}", path.display(), e),
+            }
+        });
+    }
+
+}
+
+fn has_id(item: &Value, id_field: &str, id: &str) -> bool {
+    match item.get(id_field) {
+        Some(Value::String(value)) => value == id,
+        Some(value) => value.to_string().as_str() == id,
+        None => false,
+    }
+}
+
+/// the biggest numeric id plus one, or a uuid if the ids are not numbers.
+fn next_id(items: &[Value], id_field: &str) -> Value {
+    let ids: Vec<&Value> = items.iter().filter_map(|item| item.get(id_field)).collect();
+    if ids.iter().all(|id| id.is_u64()) {
+        let max = ids.iter().filter_map(|id| id.as_u64()).max().unwrap_or(0);
+        Value::from(max + 1)
+    }else {
+        Value::from(uuid::Uuid::new_v4().to_string())
+    }
+}
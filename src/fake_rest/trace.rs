@@ -0,0 +1,177 @@
+use std::{sync::{Arc, OnceLock}, time::{Duration, SystemTime, UNIX_EPOCH}};
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use crate::server::request::Request;
+
+/// the most spans that are sent in one request, the waiting ones are sent together.
+const BATCH_SIZE: usize = 512;
+
+/// send a span for every request to an OpenTelemetry collector with OTLP over http (json).
+#[derive(Debug, Deserialize, Clone)]
+pub struct Tracing {
+    /// the url of the collector like `http://localhost:4318`, `/v1/traces` is added when it's missing
+    pub endpoint: String,
+    /// the `service.name` of the spans, default is `fake-rest`
+    pub service_name: Option<String>,
+    /// the exporter starts on the first span, it's shared between the clones of the config
+    #[serde(skip)]
+    exporter: Arc<Exporter>,
+}
+
+/// the span of a request, it's the child of the span of the `traceparent` header when the request has one.
+#[derive(Debug, Clone)]
+pub struct SpanContext {
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    /// the `trace-flags` of w3c trace context, like `01` for the sampled traces
+    pub flags: String,
+}
+
+impl SpanContext {
+
+    pub fn new(request: &Request) -> SpanContext {
+        let parent = request.header("traceparent").and_then(|value| parse_traceparent(value));
+        let mut rng = rand::rng();
+        let span_id = format!("{:016x}", rng.random_range(1..=u64::MAX));
+        match parent {
+            Some((trace_id, parent_span_id, flags)) => SpanContext { trace_id, span_id, parent_span_id: Some(parent_span_id), flags },
+            None => SpanContext {
+                trace_id: format!("{:032x}", rng.random_range(1..=u128::MAX)),
+                span_id,
+                parent_span_id: None,
+                flags: "01".to_string(),
+            },
+        }
+    }
+
+    /// the `traceparent` header of the requests to the other services, with this span as the parent.
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-{}", self.trace_id, self.span_id, self.flags)
+    }
+
+}
+
+/// the trace id, parent id and flags of a header like `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+fn parse_traceparent(value: &str) -> Option<(String, String, String)> {
+    let parts: Vec<&str> = value.trim().split('-').collect();
+    let is_hex = |part: &str, len: usize| part.len() == len && part.chars().all(|c| c.is_ascii_hexdigit());
+    let is_zero = |part: &str| part.chars().all(|c| c == '0');
+    match parts.as_slice() {
+        [version, trace_id, parent_id, flags, ..]
+            if is_hex(version, 2) && *version != "ff" && is_hex(trace_id, 32) && is_hex(parent_id, 16) && is_hex(flags, 2)
+                && !is_zero(trace_id) && !is_zero(parent_id) => {
+            Some((trace_id.to_lowercase(), parent_id.to_lowercase(), flags.to_string()))
+        },
+        _ => None,
+    }
+}
+
+impl Tracing {
+
+    /// send the span of the request that is answered with `status`.
+    pub fn export(&self, request: &Request, span: &SpanContext, port: usize, status: usize, elapsed: Duration) {
+        let end = SystemTime::now();
+        let start = end.checked_sub(elapsed).unwrap_or(end);
+        let route = request.route.as_deref();
+        let mut attributes = vec![
+            attribute("http.request.method", json!({ "stringValue": request.method.to_string() })),
+            attribute("url.path", json!({ "stringValue": request.uri })),
+            attribute("http.response.status_code", json!({ "intValue": status.to_string() })),
+            attribute("server.port", json!({ "intValue": port.to_string() })),
+        ];
+        if let Some(route) = route {
+            attributes.push(attribute("http.route", json!({ "stringValue": route })));
+        }
+        if let Some(ip) = request.peer_ip {
+            attributes.push(attribute("client.address", json!({ "stringValue": ip.to_string() })));
+        }
+
+        let mut value = json!({
+            "traceId": span.trace_id,
+            "spanId": span.span_id,
+            "name": match route {
+                Some(route) => format!("{} {}", request.method, route),
+                None => request.method.to_string(),
+            },
+            // server
+            "kind": 2,
+            "startTimeUnixNano": unix_nanos(start).to_string(),
+            "endTimeUnixNano": unix_nanos(end).to_string(),
+            "attributes": attributes,
+            // error for the server errors, unset for the others
+            "status": { "code": if status >= 500 { 2 } else { 0 } },
+        });
+        if let Some(parent_span_id) = &span.parent_span_id {
+            value["parentSpanId"] = json!(parent_span_id);
+        }
+
+        let sender = self.exporter.sender.get_or_init(|| {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            let service_name = self.service_name.clone().unwrap_or_else(|| "fake-rest".to_string());
+            tokio::spawn(send_batches(traces_url(&self.endpoint), service_name, receiver));
+            sender
+        });
+        let _ = sender.send(value);
+    }
+
+}
+
+#[derive(Debug, Default)]
+struct Exporter {
+    sender: OnceLock<UnboundedSender<Value>>,
+}
+
+fn traces_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    }else {
+        format!("{}/v1/traces", endpoint)
+    }
+}
+
+/// send the spans in batches until the config is dropped, like after a reload.
+async fn send_batches(url: String, service_name: String, mut receiver: UnboundedReceiver<Value>) {
+    let client = reqwest::Client::new();
+    let mut spans = Vec::new();
+    loop {
+        let closed = match tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await {
+            Ok(Some(span)) => {
+                spans.push(span);
+                false
+            },
+            Ok(None) => true,
+            Err(_) => false,
+        };
+        if !spans.is_empty() && (closed || spans.len() >= BATCH_SIZE || receiver.is_empty()) {
+            let body = json!({
+                "resourceSpans": [{
+                    "resource": { "attributes": [attribute("service.name", json!({ "stringValue": service_name }))] },
+                    "scopeSpans": [{ "scope": { "name": "fake-rest" }, "spans": std::mem::take(&mut spans) }],
+                }],
+            });
+            let request = client.post(&url).header(reqwest::header::CONTENT_TYPE, "application/json").body(body.to_string());
+            match request.send().await {
+                Ok(response) if !response.status().is_success() => {
+                    println!("can't export the spans to `{}`: {}", url, response.status());
+                },
+                Ok(_) => {},
+                Err(e) => println!("can't export the spans to `{}`: {}", url, e),
+            }
+        }
+        if closed {
+            break;
+        }
+    }
+}
+
+fn attribute(key: &str, value: Value) -> Value {
+    json!({ "key": key, "value": value })
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH).map(|duration| duration.as_nanos()).unwrap_or(0)
+}
@@ -0,0 +1,212 @@
+use std::{collections::HashMap, path::{Path, PathBuf}};
+use crate::server::{plugin, script};
+use super::server_config::{ConfigSource, Server, ServerDataSchema};
+
+const RESULT_TYPES: [&str; 19] = ["direct", "template", "xml", "template_file", "command", "script", "wasm", "sse", "websocket", "graphql", "grpc", "base64", "resource", "dataset", "oauth2_token", "redirect", "file", "dl", "static_dir"];
+
+/// a problem of a route, the errors stop the validation from passing and the warnings don't.
+struct Issue {
+    error: bool,
+    file: PathBuf,
+    line: Option<usize>,
+    message: String,
+}
+
+/// parse the config like the server does and check the things that are only noticed when the routes are
+/// served, like the missing files, unknown result types, broken headers and the routes
+/// that are never served. it prints the problems and returns whether the config is valid.
+pub async fn run(source: ConfigSource) -> bool {
+    let servers = match source.parse().await {
+        Ok(servers) => servers,
+        Err(e) => {
+            println!("error: {}", e);
+            return false;
+        },
+    };
+
+    let mut issues: Vec<Issue> = Vec::new();
+    let mut contents: HashMap<PathBuf, String> = HashMap::new();
+    for server in servers.iter() {
+        check_server(server, source.path(), &mut issues);
+        for (index, item) in server.data.iter().enumerate() {
+            let mut messages = Vec::new();
+            check_route(item, &mut messages);
+            let shadowed = server.data[..index].iter().position(|other| {
+                other.source == item.source && same_requirements(other, item)
+            });
+
+            if messages.is_empty() && shadowed.is_none() {
+                continue;
+            }
+            let line = route_line(&mut contents, &server.data, index);
+            for message in messages {
+                push_issue(&mut issues, Issue { error: true, file: item.source.clone(), line, message });
+            }
+            if let Some(first) = shadowed {
+                let first_line = route_line(&mut contents, &server.data, first).map(|line| format!(" at line {}", line));
+                push_issue(&mut issues, Issue {
+                    error: false,
+                    file: item.source.clone(),
+                    line,
+                    message: format!(
+                        "`{}` is never served, the same route{} is matched first",
+                        route_name(item),
+                        first_line.unwrap_or_default()
+                    ),
+                });
+            }
+        }
+    }
+
+    for issue in issues.iter() {
+        let location = match issue.line {
+            Some(line) => format!("{}:{}", issue.file.display(), line),
+            None => issue.file.display().to_string(),
+        };
+        println!("{}: {}: {}", if issue.error { "error" } else { "warning" }, location, issue.message);
+    }
+
+    let errors = issues.iter().filter(|issue| issue.error).count();
+    if errors == 0 {
+        let routes: usize = servers.iter().map(|server| server.data.len()).sum();
+        println!("`{}` is valid: {} servers and {} routes", source.path().display(), servers.len(), routes);
+    }else {
+        println!("`{}` has {} errors", source.path().display(), errors);
+    }
+    errors == 0
+}
+
+/// the servers can share the routes, so the same problem is reported once.
+fn push_issue(issues: &mut Vec<Issue>, issue: Issue) {
+    let reported = issues.iter().any(|item| {
+        item.file == issue.file && item.line == issue.line && item.message == issue.message
+    });
+    if !reported {
+        issues.push(issue);
+    }
+}
+
+fn check_server(server: &Server, config: &Path, issues: &mut Vec<Issue>) {
+    let tls = match &server.config.tls {
+        Some(tls) => tls,
+        None => return,
+    };
+    for file in [Some(&tls.cert), Some(&tls.key), tls.client_ca.as_ref()].into_iter().flatten() {
+        if !Path::new(file).is_file() {
+            push_issue(issues, Issue {
+                error: true,
+                file: config.to_path_buf(),
+                line: None,
+                message: format!("the tls file `{}` of `{}:{}` doesn't exist", file, server.config.host, server.config.port),
+            });
+        }
+    }
+}
+
+fn check_route(item: &ServerDataSchema, messages: &mut Vec<String>) {
+    let name = route_name(item);
+
+    let mut results = vec![(item.result_type.as_str(), item.result.as_str())];
+    let mut headers: Vec<&String> = item.result_headers.iter().flatten().collect();
+    for response in item.responses.iter().flatten() {
+        results.push((
+            response.result_type.as_deref().unwrap_or(&item.result_type),
+            response.result.as_deref().unwrap_or(&item.result),
+        ));
+        headers.extend(response.result_headers.iter().flatten());
+    }
+    for representation in item.representations.iter().flatten() {
+        results.push((
+            representation.result_type.as_deref().unwrap_or(&item.result_type),
+            representation.result.as_deref().unwrap_or(&item.result),
+        ));
+    }
+
+    for (result_type, result) in results {
+        if !RESULT_TYPES.contains(&result_type) {
+            messages.push(format!(
+                "unknown result_type `{}` of `{}`, it's one of {}",
+                result_type,
+                name,
+                RESULT_TYPES.join(", ")
+            ));
+        }else if matches!(result_type, "file" | "dl" | "template_file" | "script" | "wasm" | "dataset") && !Path::new(result).is_file() {
+            messages.push(format!("the result file `{}` of `{}` doesn't exist", result, name));
+        }else if result_type == "script" {
+            let compiled = std::fs::read_to_string(result).map_err(|e| e.to_string()).and_then(|source| script::compile(&source));
+            if let Err(e) = compiled {
+                messages.push(format!("the script `{}` of `{}` is invalid: {}", result, name, e));
+            }
+        }else if result_type == "wasm" {
+            let compiled = std::fs::read(result).map_err(|e| e.to_string()).and_then(|wasm| plugin::compile(&wasm).map(|_| ()));
+            if let Err(e) = compiled {
+                messages.push(format!("the plugin `{}` of `{}` is invalid: {}", result, name, e));
+            }
+        }else if result_type == "static_dir" && !Path::new(result).is_dir() {
+            messages.push(format!("the result directory `{}` of `{}` doesn't exist", result, name));
+        }
+    }
+    for header in headers {
+        let valid = header
+            .split_once(':')
+            .is_some_and(|(key, _)| !key.trim().is_empty() && !key.trim().contains(char::is_whitespace));
+        if !valid {
+            messages.push(format!("the result header `{}` of `{}` must be like `Name: value`", header, name));
+        }
+    }
+}
+
+/// the routes that are matched by the same requests, the later one is never served.
+fn same_requirements(first: &ServerDataSchema, second: &ServerDataSchema) -> bool {
+    first.method == second.method
+        && first.host == second.host
+        && first.path == second.path
+        && first.path_regex == second.path_regex
+        && first.headers == second.headers
+        && first.queries == second.queries
+        && first.cookies == second.cookies
+        && first.form == second.form
+        && first.files == second.files
+        && first.body_equals == second.body_equals
+        && first.body_contains == second.body_contains
+        && first.body_json == second.body_json
+        && first.soap_action == second.soap_action
+        && first.body_xpath == second.body_xpath
+        && first.client_cert_subject == second.client_cert_subject
+        && first.responses.iter().flatten().all(|response| response.when.is_none())
+}
+
+fn route_name(item: &ServerDataSchema) -> String {
+    format!("{} {}", item.method, item.path_regex.as_ref().unwrap_or(&item.path))
+}
+
+/// the line of the route in its file, it's the line of its `path` (or `path_regex`)
+/// after the ones of the routes with the same path before it.
+fn route_line(contents: &mut HashMap<PathBuf, String>, data: &[ServerDataSchema], index: usize) -> Option<usize> {
+    let item = &data[index];
+    let (key, value) = match &item.path_regex {
+        Some(path_regex) => ("path_regex", path_regex),
+        None => ("path", &item.path),
+    };
+    let skip = data[..index]
+        .iter()
+        .filter(|other| other.source == item.source && other.path == item.path && other.path_regex == item.path_regex)
+        .count();
+
+    let content = contents
+        .entry(item.source.clone())
+        .or_insert_with(|| std::fs::read_to_string(&item.source).unwrap_or_default());
+    let double_quoted = format!("\"{}\"", value);
+    let single_quoted = format!("'{}'", value);
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim_start().trim_start_matches('"');
+            line.starts_with(key)
+                && line[key.len()..].trim_start_matches('"').trim_start().starts_with(['=', ':'])
+                && (line.contains(&double_quoted) || line.contains(&single_quoted))
+        })
+        .nth(skip)
+        .map(|(number, _)| number + 1)
+}
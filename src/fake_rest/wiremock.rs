@@ -0,0 +1,204 @@
+use std::path::Path;
+use base64::Engine;
+use serde_json::Value;
+use crate::error::Error;
+use super::generated::{self, GeneratedRoute};
+
+/// the methods of the `ANY` stubs, a route is written for every one of them.
+const ANY_METHODS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+/// the priority of the stubs without one.
+const DEFAULT_PRIORITY: i64 = 5;
+
+/// the stubs of a WireMock mapping file, a `{ "mappings": [...] }` file or a single stub.
+pub fn mappings(document: &Value) -> Vec<Value> {
+    match document.get("mappings").and_then(Value::as_array) {
+        Some(mappings) => mappings.clone(),
+        None => vec![document.clone()],
+    }
+}
+
+pub fn is_mapping(document: &Value) -> bool {
+    document.get("mappings").is_some_and(Value::is_array)
+        || (document.get("request").is_some_and(Value::is_object) && document.get("response").is_some_and(Value::is_object))
+}
+
+/// a route for every WireMock stub, in the order WireMock picks them: by `priority` and the last added first.
+///
+/// the `bodyFileName`s are read from the `__files` directory of `root` and the `base64Body`s are written in
+/// the `bodies` directory next to the `output`. the matchers without an equivalent are reported and skipped.
+pub async fn routes(mut stubs: Vec<Value>, root: &Path, output: &Path) -> Result<Vec<GeneratedRoute>, Error> {
+    stubs.reverse();
+    stubs.sort_by_key(|stub| stub["priority"].as_i64().unwrap_or(DEFAULT_PRIORITY));
+    let bodies = output.parent().unwrap_or(Path::new("")).join("bodies");
+
+    let mut routes = Vec::new();
+    for stub in stubs.iter() {
+        let request = &stub["request"];
+        let response = &stub["response"];
+        let mut route = GeneratedRoute { status_code: 200, ..Default::default() };
+        let mut skipped = Vec::new();
+
+        match_url(request, &mut route);
+        let name = format!(
+            "{} {}",
+            request["method"].as_str().unwrap_or("ANY"),
+            route.path_regex.as_ref().unwrap_or(&route.path)
+        );
+        let mut queries = route.queries.take().unwrap_or_default();
+        queries.extend(requirements(&request["queryParameters"], "=", &mut skipped));
+        route.queries = non_empty(queries);
+        route.headers = non_empty(requirements(&request["headers"], ": ", &mut skipped));
+        route.cookies = non_empty(requirements(&request["cookies"], "=", &mut skipped));
+        for pattern in request["bodyPatterns"].as_array().into_iter().flatten() {
+            match_body(pattern, &mut route, &mut skipped);
+        }
+        if stub.get("scenarioName").is_some() {
+            skipped.push("scenarioName".to_string());
+        }
+
+        // the status codes are served as they are, only the ones out of http are left to 200
+        match response["status"].as_u64().map(|status| status as usize) {
+            Some(status) if (100..600).contains(&status) => route.status_code = status,
+            Some(status) => skipped.push(format!("status {}", status)),
+            None => {},
+        }
+        if response.get("statusMessage").is_some() {
+            skipped.push("statusMessage".to_string());
+        }
+
+        route.result_headers = response["headers"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .flat_map(|(name, value)| match value {
+                Value::Array(values) => values.iter().map(|value| format!("{}: {}", name, text(value))).collect(),
+                value => vec![format!("{}: {}", name, text(value))],
+            })
+            .collect();
+        route.delay_ms = response["fixedDelayMilliseconds"].as_u64();
+        route.fault = response["fault"].as_str().map(|fault| match fault {
+            "CONNECTION_RESET_BY_PEER" => "connection_reset",
+            "EMPTY_RESPONSE" => "empty_response",
+            _ => "malformed_response",
+        }.to_string());
+
+        let is_template = response["transformers"]
+            .as_array()
+            .is_some_and(|transformers| transformers.iter().any(|item| item == "response-template"));
+        route.result_type = if is_template { "template" } else { "direct" }.to_string();
+        if let Some(body) = response["body"].as_str() {
+            route.result = body.to_string();
+        }else if let Some(body) = response.get("jsonBody") {
+            route.result = serde_json::to_string_pretty(body).unwrap_or_default();
+        }else if let Some(body) = response["base64Body"].as_str() {
+            let content = base64::engine::general_purpose::STANDARD.decode(body.trim()).map_err(|e| Error::ParsingError(
+                format!("the base64Body of `{}` is invalid: {}", name, e)
+            ))?;
+            tokio::fs::create_dir_all(&bodies).await?;
+            let body_path = bodies.join(generated::body_file_name(routes.len(), "stub", &route.path, ""));
+            tokio::fs::write(&body_path, content).await?;
+            route.result_type = "file".to_string();
+            route.result = body_path.to_string_lossy().to_string();
+        }else if let Some(file_name) = response["bodyFileName"].as_str() {
+            route.result_type = "file".to_string();
+            route.result = root.join("__files").join(file_name).to_string_lossy().to_string();
+        }
+
+        for field in skipped {
+            println!("warning: `{}`: `{}` is not supported, it's skipped", name, field);
+        }
+        if is_template && route.result_type == "file" {
+            println!("warning: `{}`: the body file is not a template, it's served as it is", name);
+        }else if is_template {
+            println!("warning: `{}`: the response template is kept as it is, check its handlebars helpers", name);
+        }
+        match request["method"].as_str().unwrap_or("ANY") {
+            "ANY" => {
+                for method in ANY_METHODS {
+                    routes.push(GeneratedRoute { method: method.to_string(), ..route.clone() });
+                }
+            },
+            method => routes.push(GeneratedRoute { method: method.to_uppercase(), ..route }),
+        }
+    }
+    Ok(routes)
+}
+
+/// `url` and `urlPath` are exact, the `*Pattern` ones are regexes and `urlPathTemplate` has the same
+/// `{name}` path values as the routes.
+fn match_url(request: &Value, route: &mut GeneratedRoute) {
+    if let Some(url) = request["url"].as_str() {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        route.path = path.to_string();
+        let queries: Vec<String> = query.split('&').filter(|item| !item.is_empty()).map(str::to_string).collect();
+        if !queries.is_empty() {
+            route.queries = Some(queries);
+        }
+    }else if let Some(path) = request["urlPath"].as_str().or_else(|| request["urlPathTemplate"].as_str()) {
+        route.path = path.to_string();
+    }else if let Some(pattern) = request["urlPathPattern"].as_str().or_else(|| request["urlPattern"].as_str()) {
+        route.path_regex = Some(anchored(pattern));
+    }else {
+        route.path_regex = Some("^/.*$".to_string());
+    }
+}
+
+/// the `queryParameters`, `headers` or `cookies` matchers as the requirements of the route.
+fn requirements(matchers: &Value, delimiter: &str, skipped: &mut Vec<String>) -> Vec<String> {
+    let mut requirements = Vec::new();
+    for (name, matcher) in matchers.as_object().into_iter().flatten() {
+        match first_matcher(matcher) {
+            Some(("equalTo", value)) => requirements.push(format!("{}{}{}", name, delimiter, text(value))),
+            Some(("matches", value)) => requirements.push(format!("{} ~ {}", name, anchored(&text(value)))),
+            Some(("contains", value)) => requirements.push(format!("{} ~ {}", name, regex::escape(&text(value)))),
+            Some(("absent", _)) => skipped.push(format!("{} absent", name)),
+            Some((kind, _)) => {
+                skipped.push(format!("{} {}", name, kind));
+                requirements.push(name.clone());
+            },
+            None => requirements.push(name.clone()),
+        }
+    }
+    requirements
+}
+
+fn non_empty(items: Vec<String>) -> Option<Vec<String>> {
+    if items.is_empty() { None } else { Some(items) }
+}
+
+fn match_body(pattern: &Value, route: &mut GeneratedRoute, skipped: &mut Vec<String>) {
+    match first_matcher(pattern) {
+        Some(("equalToJson", Value::String(json))) => match serde_json::from_str(json) {
+            Ok(json) => route.body_json = Some(json),
+            Err(_) => route.body_equals = Some(json.clone()),
+        },
+        Some(("equalToJson", json)) => route.body_json = Some(json.clone()),
+        Some(("equalTo", value)) => route.body_equals = Some(text(value)),
+        Some(("contains", value)) => route.body_contains = Some(text(value)),
+        Some((kind, _)) => skipped.push(format!("bodyPatterns {}", kind)),
+        None => {},
+    }
+}
+
+/// the matcher of `{ "equalTo": "x", "caseInsensitive": true }`, the options are ignored.
+fn first_matcher(matcher: &Value) -> Option<(&str, &Value)> {
+    const OPTIONS: [&str; 3] = ["caseInsensitive", "ignoreArrayOrder", "ignoreExtraElements"];
+    matcher
+        .as_object()?
+        .iter()
+        .find(|(key, _)| !OPTIONS.contains(&key.as_str()))
+        .map(|(key, value)| (key.as_str(), value))
+}
+
+/// wiremock regexes match the whole value.
+fn anchored(pattern: &str) -> String {
+    format!("^{}$", pattern.trim_start_matches('^').trim_end_matches('$'))
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,6 @@
+pub mod error;
+pub mod server;
+pub mod fake_rest;
+
+pub use error::Error;
+pub use fake_rest::{instance::FakeRestServer, fixture::TestServer, server_config::{ConfigFormat, ConfigSource}};
@@ -1,20 +1,14 @@
-use fake_rest::server_config::Server;
-use tokio::net::{TcpListener, TcpStream};
 use std::path::PathBuf;
-use clap::Parser;
-
-mod error;
-mod server;
-mod fake_rest;
-
-use error::FakeRestResult;
-use crate::server::{
-    response::Response, 
-    connection::Connection, 
-};
-use crate::fake_rest::{
-    server_config,
-    print
+use clap::{CommandFactory, Parser, Subcommand};
+use fake_rest::{
+    FakeRestServer,
+    fake_rest::{
+        server_config::{ConfigFormat, ConfigSource},
+        record,
+        validate,
+        openapi,
+        import,
+    },
 };
 
 const FAKE_REST: &str = r"
@@ -36,51 +30,134 @@ const FAKE_REST: &str = r"
 ))]
 pub struct FakeRestArgs {
     #[arg(short, long)]
-    pub config: PathBuf
-}
+    pub config: Option<PathBuf>,
 
+    /// Load every `.toml` and `.json` config of the directory tree as a single config.
+    #[arg(long, conflicts_with_all = ["config", "format"])]
+    pub config_dir: Option<PathBuf>,
 
-async fn handle(socket: TcpStream, server: &Server) -> FakeRestResult {
-    let mut connection = Connection::new(socket).await?;
-    match Response::new(&connection.request, &server).await {
-        Ok(response) => {
-            connection.respond(response).await?;
-            print::format_for_print(&connection.request);
-        },
-        Err(e) => {
-            println!("Error on handling the request: {}", e);
-        },
-    }
-    Ok(())
+    /// Reload the config when it or the files it reads change, the unchanged routes keep their state.
+    #[arg(short, long)]
+    pub watch: bool,
+
+    /// Format of the config, detected from the `.toml` or `.json` extension by default.
+    #[arg(short, long, value_enum)]
+    pub format: Option<ConfigFormat>,
+
+    #[command(subcommand)]
+    pub command: Option<FakeRestCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum FakeRestCommand {
+    /// Proxy the requests to a real api and write them as a config file.
+    Record {
+        /// Base url of the real api, like `https://api.example.com`.
+        #[arg(short, long)]
+        upstream: String,
+        /// Directory to write the `server.toml` and the response bodies in.
+        #[arg(short, long, default_value = "recorded")]
+        output: PathBuf,
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        #[arg(short, long, default_value_t = 7000)]
+        port: usize,
+    },
+    /// Write the routes of an OpenAPI 3 or Swagger 2 document (json or yaml), a HAR file or WireMock mappings
+    /// (a file or a directory) as a config file.
+    Import {
+        file: PathBuf,
+        /// The config file to write, toml or `.json`.
+        #[arg(short, long, default_value = "server.toml")]
+        output: PathBuf,
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        #[arg(short, long, default_value_t = 7000)]
+        port: usize,
+    },
+    /// Write an OpenAPI 3 document of the routes of a config file or directory.
+    Export {
+        config: PathBuf,
+        /// The document to write, json or `.yaml`, it's printed when it's not set.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Format of the config, detected from the `.toml` or `.json` extension by default.
+        #[arg(short, long, value_enum)]
+        format: Option<ConfigFormat>,
+        /// The port of the server to export when the config has more than one, default is the first one.
+        #[arg(short, long)]
+        port: Option<usize>,
+    },
+    /// Check a config file or directory without starting the server, it exits with 1 on errors.
+    Validate {
+        config: PathBuf,
+        /// Format of the config, detected from the `.toml` or `.json` extension by default.
+        #[arg(short, long, value_enum)]
+        format: Option<ConfigFormat>,
+    },
 }
 
 
 #[tokio::main]
 async fn main() {
-    println!("{}", FAKE_REST);
-
     let args = FakeRestArgs::parse();
-    let server = match server_config::parse_config_file(args.config).await {
-        Ok(s) => s,
-        Err(e) => panic!("{}", e.to_string())
-    };
+    // the printed document of `export` can be piped to the other tools
+    if !matches!(args.command, Some(FakeRestCommand::Export { output: None, .. })) {
+        println!("{}", FAKE_REST);
+    }
+    match args.command {
+        Some(FakeRestCommand::Record { upstream, output, host, port }) => {
+            if let Err(e) = record::run(upstream, output, host, port).await {
+                panic!("{}", e.to_string())
+            }
+        },
+        Some(FakeRestCommand::Import { file, output, host, port }) => {
+            if let Err(e) = import::run(file, output, host, port).await {
+                panic!("{}", e.to_string())
+            }
+        },
+        Some(FakeRestCommand::Export { config, output, format, port }) => {
+            if let Err(e) = openapi::export_to(config_source(config, format), output, port).await {
+                panic!("{}", e.to_string())
+            }
+        },
+        Some(FakeRestCommand::Validate { config, format }) => {
+            if !validate::run(config_source(config, format)).await {
+                std::process::exit(1);
+            }
+        },
+        None => match (args.config, args.config_dir) {
+            (Some(config), _) => serve(ConfigSource::File(config, args.format), args.watch).await,
+            (None, Some(dir)) => serve(ConfigSource::Dir(dir), args.watch).await,
+            (None, None) => FakeRestArgs::command()
+                .error(
+                    clap::error::ErrorKind::MissingRequiredArgument,
+                    "the `--config <CONFIG>` or `--config-dir <CONFIG_DIR>` argument is required"
+                )
+                .exit(),
+        },
+    }
+}
 
-    let host_and_port = format!("{}:{}", server.config.host, server.config.port);
-    let listener = match TcpListener::bind(&host_and_port).await {
-        Ok(v) => v,
+/// the config of the `validate` and `export` commands, a file or a directory.
+fn config_source(config: PathBuf, format: Option<ConfigFormat>) -> ConfigSource {
+    if config.is_dir() {
+        ConfigSource::Dir(config)
+    }else {
+        ConfigSource::File(config, format)
+    }
+}
+
+async fn serve(source: ConfigSource, watch: bool) {
+    let mut server = match FakeRestServer::start(source).await {
+        Ok(server) => server,
         Err(e) => panic!("{}", e.to_string())
     };
-
-    println!("Start the server at <http://{}>...", host_and_port);
-    loop {
-        let con = listener.accept().await;
-        if let Ok(connection) = con {
-            match handle(connection.0, &server).await {
-                Ok(_) => {},
-                Err(e) => println!("{}", e.to_string())
-            };
-        }else {
-            println!("{}", con.err().unwrap().to_string())
-        }
+    for url in server.urls() {
+        println!("Start the server at <{}>...", url);
+    }
+    if watch {
+        server.watch();
     }
+    server.wait().await;
 }
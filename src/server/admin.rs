@@ -0,0 +1,129 @@
+use std::sync::atomic::Ordering;
+use serde_json::{json, Value};
+use crate::fake_rest::{journal::{RecordedRequest, RequestFilter}, server_config::{Server, ServerDataSchema}};
+use super::{request::{Method, Request}, response::Response, status::Status};
+
+/// a single page without external assets, it polls `/requests`.
+const DASHBOARD: &str = include_str!("dashboard.html");
+
+/// answer the requests of the admin api under the `admin_path` of the server:
+///
+/// - `GET /routes` lists the routes with their hit counts
+/// - `GET /routes/{id}` shows a route, the id is its index in the config
+/// - `POST /routes/{id}/disable` and `POST /routes/{id}/enable` turn a route off and on until the next reload
+/// - `GET /requests?path=/users/{id}&method=GET` lists the received requests, the filters are optional
+/// - `GET /requests/count?path=/users&expected=2` counts them, it's answered with 417 when the count is not `expected`
+/// - `GET /requests?after=12` lists the requests after the one with the id 12
+/// - `GET /requests?header=X-Request-Id: 42&status=404&since=1700000000&until=1700003600` filters them by a header,
+///   the status of the response and the time they're received, the last `history_size` requests are kept
+/// - `GET /dashboard` is a page that shows the requests as they come, with their routes and responses
+/// - `POST /reset` forgets the requests and starts the sequences, rate limits, resources and script states of the
+///   routes over
+///
+/// `None` is returned for the requests out of the admin path.
+pub fn handle(admin_path: &str, request: &Request, server: &Server) -> Option<Response> {
+    let admin_path = admin_path.trim_end_matches('/');
+    let path = match request.uri.strip_prefix(admin_path) {
+        Some(path) if path.is_empty() || path.starts_with('/') => path.trim_end_matches('/'),
+        _ => return None,
+    };
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+
+    let response = match (&request.method, segments.as_slice()) {
+        (Method::GET | Method::HEAD, []) => Response::json(Status::ok(), &json!({
+            "routes": format!("{}/routes", admin_path),
+            "requests": format!("{}/requests", admin_path),
+            "dashboard": format!("{}/dashboard", admin_path),
+        })),
+        (Method::GET | Method::HEAD, ["dashboard"]) => {
+            let mut response = Response::text(Status::ok(), DASHBOARD);
+            response.headers.insert("Content-Type".to_string(), "text/html; charset=utf-8".to_string());
+            response
+        },
+        (Method::GET | Method::HEAD, ["routes"]) => {
+            let routes: Vec<Value> = server.data.iter().enumerate().map(|(id, item)| route_json(id, item)).collect();
+            Response::json(Status::ok(), &json!(routes))
+        },
+        (Method::GET | Method::HEAD, ["routes", id]) => match find_route(server, id) {
+            Some((id, item)) => Response::json(Status::ok(), &route_json(id, item)),
+            None => not_found(),
+        },
+        (Method::POST, ["routes", id, action @ ("enable" | "disable")]) => match find_route(server, id) {
+            Some((id, item)) => {
+                item.disabled.store(*action == "disable", Ordering::SeqCst);
+                Response::json(Status::ok(), &route_json(id, item))
+            },
+            None => not_found(),
+        },
+        (Method::GET | Method::HEAD, ["requests"]) => {
+            match RequestFilter::from_query(&request.query_strings) {
+                Ok(filter) => {
+                    let requests: Vec<Value> = server.journal.find(&filter).iter().map(RecordedRequest::to_json).collect();
+                    Response::json(Status::ok(), &json!(requests))
+                },
+                Err(e) => bad_request(&e),
+            }
+        },
+        (Method::GET | Method::HEAD, ["requests", "count"]) => count_requests(request, server),
+        (Method::POST, ["reset"]) => {
+            reset(server);
+            Response::json(Status::ok(), &json!({ "reset": true }))
+        },
+        (_, ["routes", ..]) | (_, ["requests", ..]) | (_, ["reset"]) | (_, ["dashboard"]) | (_, []) => Response::json(Status::method_not_allowed(), &json!({ "error": "method not allowed" })),
+        _ => not_found(),
+    };
+    Some(response)
+}
+
+/// the state of the routes goes back to the one of the start, the disabled routes stay disabled.
+fn reset(server: &Server) {
+    server.journal.clear();
+    for item in server.data.iter() {
+        item.calls.store(0, Ordering::SeqCst);
+        item.hits.store(0, Ordering::SeqCst);
+        item.rate_limiter.reset();
+        item.store.reset();
+        item.script_state.lock().unwrap().clear();
+    }
+}
+
+fn count_requests(request: &Request, server: &Server) -> Response {
+    let count = match RequestFilter::from_query(&request.query_strings) {
+        Ok(filter) => server.journal.count(&filter),
+        Err(e) => return bad_request(&e),
+    };
+    let expected = match request.query_strings.get("expected").map(|expected| expected.parse::<usize>()) {
+        Some(Ok(expected)) => expected,
+        Some(Err(_)) => return bad_request("`expected` must be a number"),
+        None => return Response::json(Status::ok(), &json!({ "count": count })),
+    };
+    let status = if count == expected { Status::ok() } else { Status::expectation_failed() };
+    Response::json(status, &json!({ "count": count, "expected": expected }))
+}
+
+fn find_route<'a>(server: &'a Server, id: &str) -> Option<(usize, &'a ServerDataSchema)> {
+    let id: usize = id.parse().ok()?;
+    server.data.get(id).map(|item| (id, item))
+}
+
+fn route_json(id: usize, item: &ServerDataSchema) -> Value {
+    json!({
+        "id": id,
+        "method": item.method.to_string(),
+        "path": item.path_regex.as_ref().unwrap_or(&item.path),
+        "path_regex": item.path_regex.is_some(),
+        "host": item.host,
+        "result_type": item.result_type,
+        "source": item.source.display().to_string(),
+        "hits": item.hits.load(Ordering::SeqCst),
+        "enabled": !item.disabled.load(Ordering::SeqCst),
+    })
+}
+
+fn bad_request(error: &str) -> Response {
+    Response::json(Status::bad_request(), &json!({ "error": error }))
+}
+
+fn not_found() -> Response {
+    Response::json(Status::not_found(), &json!({ "error": "not found" }))
+}
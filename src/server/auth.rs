@@ -0,0 +1,208 @@
+use std::{collections::{HashMap, HashSet}, str::FromStr};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use jsonwebtoken::{errors::ErrorKind, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use serde_json::Value;
+use crate::error::Error;
+use super::{request::Request, response::Response, status::Status};
+
+/// the authentication a route requires.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Auth {
+    /// `Authorization: Basic base64(username:password)`
+    Basic {
+        /// the accepted `username:password` pairs
+        users: Vec<String>,
+        /// the realm of the `WWW-Authenticate` challenge, default is `fake-rest`
+        realm: Option<String>,
+    },
+    /// `Authorization: Bearer <token>`, the token is one of the `tokens` or a valid `jwt`
+    Bearer {
+        tokens: Option<Vec<String>>,
+        jwt: Option<Jwt>,
+    },
+    /// one of the `api_keys` of the server in the `api_key_header`
+    #[serde(rename = "api_key")]
+    ApiKey {
+        /// the tenants of the keys that can use the route, any tenant when it's not set
+        tenants: Option<Vec<String>>,
+    },
+}
+
+/// how the json web tokens are verified.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Jwt {
+    /// `HS256` (default), `HS384`, `HS512`, `RS256`, `RS384`, `RS512`, `PS256`, `PS384`, `PS512`, `ES256` or `ES384`
+    pub algorithm: Option<String>,
+    /// the secret of the `HS` algorithms, it's required for them
+    pub secret: Option<String>,
+    /// the pem file of the public key of the other algorithms
+    pub public_key: Option<String>,
+    #[serde(skip)]
+    pub public_key_pem: Vec<u8>,
+    /// the `iss` claim must be this
+    pub issuer: Option<String>,
+    /// the `aud` claim must contain this
+    pub audience: Option<String>,
+    /// the other claims the token must have, an array or a space separated claim like
+    /// `scope` only needs to contain the value. it's answered with 403 otherwise.
+    pub claims: Option<HashMap<String, Value>>,
+}
+
+impl Auth {
+
+    /// read the keys of the config and check that they're usable.
+    pub async fn load(&mut self) -> Result<(), Error> {
+        match self {
+            Auth::Basic { .. } | Auth::ApiKey { .. } => Ok(()),
+            Auth::Bearer { tokens: None, jwt: None } => Err(Error::ConfigValidationError(
+                "`bearer` auth needs `tokens` or `jwt`".to_string()
+            )),
+            Auth::Bearer { jwt: Some(jwt), .. } => {
+                if let Some(public_key) = &jwt.public_key {
+                    jwt.public_key_pem = tokio::fs::read(public_key).await.map_err(|e| Error::ConfigFileOpenError(
+                        format!("can't read the jwt public_key `{}`: {}", public_key, e)
+                    ))?;
+                }
+                if is_hmac(jwt.algorithm.as_deref()) && jwt.secret.as_deref().is_none_or(str::is_empty) {
+                    return Err(Error::ConfigValidationError(
+                        "the jwt `secret` is required for the `HS` algorithms".to_string()
+                    ));
+                }
+                jwt.decoding_key().map(|_| ()).map_err(|e| Error::ConfigValidationError(
+                    format!("invalid jwt config: {}", e)
+                ))
+            },
+            Auth::Bearer { .. } => Ok(()),
+        }
+    }
+
+    /// check the credentials of the request, it's the `401` or `403` response when they're not valid.
+    pub fn check(&self, request: &Request) -> Option<Response> {
+        match self {
+            Auth::Basic { users, realm } => {
+                match basic_credentials(request) {
+                    Some(credentials) if users.contains(&credentials) => None,
+                    _ => {
+                        let realm = realm.as_deref().unwrap_or("fake-rest");
+                        Some(challenge(Status::un_athorized(), format!("Basic realm=\"{}\", charset=\"UTF-8\"", realm)))
+                    },
+                }
+            },
+            Auth::Bearer { tokens, jwt } => {
+                let token = match request.header("Authorization").and_then(|value| strip_scheme(value, "Bearer")) {
+                    Some(token) => token.trim(),
+                    None => return Some(challenge(Status::un_athorized(), "Bearer".to_string())),
+                };
+                if tokens.as_ref().is_some_and(|tokens| tokens.iter().any(|valid| valid == token)) {
+                    return None;
+                }
+                match jwt.as_ref().map(|jwt| jwt.verify(token)) {
+                    Some(Ok(())) => None,
+                    Some(Err(JwtFailure::InsufficientClaims)) => Some(challenge(
+                        Status::forbidden(),
+                        "Bearer error=\"insufficient_scope\"".to_string()
+                    )),
+                    _ => Some(challenge(Status::un_athorized(), "Bearer error=\"invalid_token\"".to_string())),
+                }
+            },
+            Auth::ApiKey { tenants } => match (&request.tenant, tenants) {
+                (None, _) => Some(Response::text(Status::un_athorized(), "Unauthorized")),
+                (Some(tenant), Some(tenants)) if !tenants.contains(tenant) => Some(Response::text(Status::forbidden(), "Forbidden")),
+                _ => None,
+            },
+        }
+    }
+
+}
+
+enum JwtFailure {
+    /// a bad signature, an expired token or a wrong issuer or audience
+    InvalidToken,
+    /// a valid token without the required `claims`
+    InsufficientClaims,
+}
+
+impl Jwt {
+
+    fn decoding_key(&self) -> Result<(Algorithm, DecodingKey), jsonwebtoken::errors::Error> {
+        let algorithm = Algorithm::from_str(self.algorithm.as_deref().unwrap_or("HS256"))?;
+        let key = match algorithm {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                // an empty key would accept the tokens signed by anyone
+                let secret = self.secret.as_deref().filter(|secret| !secret.is_empty()).ok_or(ErrorKind::InvalidKeyFormat)?;
+                DecodingKey::from_secret(secret.as_bytes())
+            },
+            Algorithm::ES256 | Algorithm::ES384 => DecodingKey::from_ec_pem(&self.public_key_pem)?,
+            Algorithm::EdDSA => DecodingKey::from_ed_pem(&self.public_key_pem)?,
+            _ => DecodingKey::from_rsa_pem(&self.public_key_pem)?,
+        };
+        Ok((algorithm, key))
+    }
+
+    fn verify(&self, token: &str) -> Result<(), JwtFailure> {
+        let (algorithm, key) = self.decoding_key().map_err(|_| JwtFailure::InvalidToken)?;
+        let mut validation = Validation::new(algorithm);
+        // `exp` and `nbf` are checked when the token has them
+        validation.required_spec_claims = HashSet::new();
+        validation.validate_nbf = true;
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+
+        let claims = jsonwebtoken::decode::<Value>(token, &key, &validation)
+            .map_err(|_| JwtFailure::InvalidToken)?
+            .claims;
+        for (name, expected) in self.claims.iter().flatten() {
+            if !has_claim(claims.get(name), expected) {
+                return Err(JwtFailure::InsufficientClaims);
+            }
+        }
+        Ok(())
+    }
+
+}
+
+fn has_claim(actual: Option<&Value>, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Some(actual), expected) if actual == expected => true,
+        (Some(Value::Array(items)), expected) => items.contains(expected),
+        (Some(Value::String(items)), Value::String(expected)) => items.split(' ').any(|item| item == expected),
+        _ => false,
+    }
+}
+
+/// whether the algorithm of a `jwt` or an `oauth2` config is one of the `HS` ones, `HS256` is the default.
+pub fn is_hmac(algorithm: Option<&str>) -> bool {
+    matches!(algorithm.unwrap_or("HS256"), "HS256" | "HS384" | "HS512")
+}
+
+/// the `username:password` of the `Authorization: Basic` header.
+pub fn basic_credentials(request: &Request) -> Option<String> {
+    request.header("Authorization")
+        .and_then(|value| strip_scheme(value, "Basic"))
+        .and_then(|encoded| STANDARD.decode(encoded.trim()).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+}
+
+/// the value after the auth scheme of the `Authorization` header, the scheme is case insensitive.
+fn strip_scheme<'a>(value: &'a str, scheme: &str) -> Option<&'a str> {
+    let (value_scheme, credentials) = value.trim().split_once(' ')?;
+    if value_scheme.eq_ignore_ascii_case(scheme) {
+        Some(credentials)
+    }else {
+        None
+    }
+}
+
+fn challenge(status: Status, challenge: String) -> Response {
+    let body = if status.code == 403 { "Forbidden" } else { "Unauthorized" };
+    let mut response = Response::text(status, body);
+    response.headers.insert("WWW-Authenticate".to_string(), challenge);
+    response
+}
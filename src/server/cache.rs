@@ -0,0 +1,63 @@
+use std::time::SystemTime;
+use flate2::Crc;
+use super::{request::{Method, Request}, response::Response, status::Status};
+
+/// add the `ETag` and `Last-Modified` headers to a successful `GET` or `HEAD` response and turn it
+/// into a `304 Not Modified` when the validators of the request still match.
+///
+/// `etag` is the configured value of the route, otherwise a weak one is made from the body with `auto_etag`.
+pub fn apply(response: &mut Response, request: &Request, etag: Option<&str>, auto_etag: bool, last_modified: Option<SystemTime>) {
+    // the events are different on every request
+    if !matches!(request.method, Method::GET | Method::HEAD) || response.status.code != 200 || response.events.is_some() {
+        return;
+    }
+
+    let etag = match etag {
+        Some(etag) if etag.starts_with('"') || etag.starts_with("W/") => Some(etag.to_string()),
+        Some(etag) => Some(format!("\"{}\"", etag)),
+        None if auto_etag => match (&response.file, last_modified) {
+            // the streamed files are not read, they change with their size or modification time
+            (Some(file), Some(modified)) => {
+                let seconds = modified.duration_since(SystemTime::UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+                Some(format!("W/\"{:x}-{:x}\"", file.len, seconds))
+            },
+            _ => {
+                let mut crc = Crc::new();
+                crc.update(&response.body);
+                Some(format!("W/\"{:x}-{:08x}\"", response.body.len(), crc.sum()))
+            },
+        },
+        None => None,
+    };
+    if let Some(etag) = &etag {
+        response.headers.insert("ETag".to_string(), etag.clone());
+    }
+    if let Some(last_modified) = last_modified {
+        response.headers.insert("Last-Modified".to_string(), httpdate::fmt_http_date(last_modified));
+    }
+
+    // `If-None-Match` wins over `If-Modified-Since` when both of them are sent
+    let not_modified = match (request.header("If-None-Match"), request.header("If-Modified-Since")) {
+        (Some(if_none_match), _) => if_none_match
+            .split(',')
+            .any(|tag| tag.trim() == "*" || etag.as_deref().is_some_and(|etag| weak_eq(tag.trim(), etag))),
+        (None, Some(if_modified_since)) => match (last_modified, httpdate::parse_http_date(if_modified_since)) {
+            // the http dates have no sub-second part
+            (Some(last_modified), Ok(since)) => httpdate::HttpDate::from(last_modified) <= httpdate::HttpDate::from(since),
+            _ => false,
+        },
+        (None, None) => false,
+    };
+
+    if not_modified {
+        response.status = Status::not_modified();
+        response.body.clear();
+        response.file = None;
+        response.headers.remove("Content-Length");
+    }
+}
+
+/// compare the entity tags ignoring the weak `W/` prefix.
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+use serde::Deserialize;
+use crate::{error::Error, fake_rest::log_file::{self, Log}};
+use super::{proxy, request::Request, template};
+
+/// a request that a route sends after it's served, like the webhooks of the payment apis.
+/// the `url`, the values of the `headers` and the `body` are templates of the request that is served.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Callback {
+    pub url: String,
+    /// default is POST
+    pub method: Option<String>,
+    /// headers like `Content-Type: application/json`
+    pub headers: Option<Vec<String>>,
+    pub body: Option<String>,
+    /// wait this many milliseconds after the response, default is 0
+    pub delay_ms: Option<u64>,
+}
+
+/// a callback that is rendered for a request.
+struct Rendered {
+    method: reqwest::Method,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: String,
+    delay: Duration,
+}
+
+/// send the callbacks of a route in the background, the ones that can't be rendered or sent are logged.
+pub async fn fire(callbacks: &[Callback], request: &Request, log: Option<&Log>) {
+    for callback in callbacks.iter() {
+        let rendered = match render(callback, request) {
+            Ok(rendered) => rendered,
+            Err(e) => {
+                log_file::output(log, &format!("can't render the callback `{}`: {}", callback.url, e)).await;
+                continue;
+            },
+        };
+        let log = log.cloned();
+        tokio::spawn(async move {
+            tokio::time::sleep(rendered.delay).await;
+            let mut builder = proxy::client().request(rendered.method.clone(), &rendered.url).body(rendered.body);
+            for (name, value) in rendered.headers {
+                builder = builder.header(name, value);
+            }
+            let failure = match builder.send().await {
+                Ok(response) if response.status().is_success() => None,
+                Ok(response) => Some(format!("answered {}", response.status())),
+                Err(e) => Some(e.to_string()),
+            };
+            if let Some(failure) = failure {
+                let message = format!("the callback `{} {}` failed: {}", rendered.method, rendered.url, failure);
+                log_file::output(log.as_ref(), &message).await;
+            }
+        });
+    }
+}
+
+fn render(callback: &Callback, request: &Request) -> Result<Rendered, Error> {
+    let method = callback.method.as_deref().unwrap_or("POST");
+    let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+        .map_err(|_| Error::ParsingError(format!("invalid method `{}`", method)))?;
+    let mut headers = Vec::new();
+    for header in callback.headers.iter().flatten() {
+        let (name, value) = header.split_once(':').ok_or_else(|| Error::ConfigParsingError(header.to_string()))?;
+        headers.push((name.trim().to_string(), template::render(value.trim(), request)?));
+    }
+    Ok(Rendered {
+        method,
+        url: template::render(&callback.url, request)?,
+        headers,
+        body: match &callback.body {
+            Some(body) => template::render(body, request)?,
+            None => String::new(),
+        },
+        delay: Duration::from_millis(callback.delay_ms.unwrap_or(0)),
+    })
+}
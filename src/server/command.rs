@@ -0,0 +1,54 @@
+use std::{process::Stdio, time::Duration};
+use tokio::{io::AsyncWriteExt, process::Command};
+use crate::{error::Error, fake_rest::server_config::ServerDataSchema};
+use super::{request::Request, response::Response, status::Status, template};
+
+/// the seconds of the `command_timeout` when it's not set.
+const DEFAULT_TIMEOUT: u64 = 10;
+
+/// run the `result` of a `command` route with the shell, the request is written to its stdin as json with the
+/// values of the templates, like `{"method": "GET", "path": {"id": "1"}, "query": {}, "header": {}, "body": {...}}`.
+///
+/// the stdout of the command is the body of the response. a command that fails is answered with 500 and its
+/// stderr, and one that runs longer than `command_timeout` is killed and answered with 504.
+pub async fn run(item: &ServerDataSchema, request: &Request) -> Result<Result<Vec<u8>, Response>, Error> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    }else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    let mut child = command
+        .arg(item.result.as_str())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| Error::IoError(format!("can't run the command `{}`: {}", item.result, e)))?;
+
+    // the commands that don't read their stdin close it early, so it's written on the side
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = template::context(request).to_string();
+        tokio::spawn(async move {
+            let _ = stdin.write_all(input.as_bytes()).await;
+        });
+    }
+
+    let timeout = Duration::from_secs(item.command_timeout.unwrap_or(DEFAULT_TIMEOUT));
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => output?,
+        Err(_) => return Ok(Err(Response::text(
+            Status::gateway_timeout(),
+            &format!("the command didn't finish in {} seconds", timeout.as_secs())
+        ))),
+    };
+    if !output.status.success() {
+        let message = format!("the command failed with {}\n{}", output.status, String::from_utf8_lossy(&output.stderr));
+        return Ok(Err(Response::text(Status::internal_server_error(), &message)));
+    }
+    Ok(Ok(output.stdout))
+}
@@ -0,0 +1,85 @@
+use std::io::Write;
+use flate2::{write::{DeflateEncoder, GzEncoder}, Compression};
+use crate::error::Error;
+use super::{request::Request, response::Response};
+
+/// encodings in the order of preference when the client accepts several of them.
+const SUPPORTED_ENCODINGS: [&str; 3] = ["br", "gzip", "deflate"];
+
+/// compress the body with the best encoding the client accepts,
+/// bodies smaller than `min_size` and already encoded ones are left as they are.
+pub fn compress(response: &mut Response, request: &Request, min_size: usize) -> Result<(), Error> {
+    // a compressed slice of a file is not what the client asked for with `Range`, the streamed files, events and websockets are not read
+    if response.file.is_some() || response.events.is_some() || response.websocket.is_some() || response.body.len() < min_size || response.headers.contains("Content-Encoding") || response.headers.contains("Content-Range") {
+        return Ok(());
+    }
+    let encoding = match request.header("Accept-Encoding").and_then(|value| choose_encoding(value)) {
+        Some(encoding) => encoding,
+        None => return Ok(()),
+    };
+
+    response.body = encode(encoding, &response.body)?;
+    response.headers.insert("Content-Encoding".to_string(), encoding.to_string());
+    response.headers.append("Vary", "Accept-Encoding");
+    response.headers.insert("Content-Length".to_string(), response.body.len().to_string());
+    Ok(())
+}
+
+fn encode(encoding: &str, body: &[u8]) -> Result<Vec<u8>, Error> {
+    let encoded = match encoding {
+        "br" => {
+            let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+            encoder.write_all(body)?;
+            encoder.flush()?;
+            encoder.into_inner()
+        },
+        "gzip" => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()?
+        },
+        _ => {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()?
+        },
+    };
+    Ok(encoded)
+}
+
+/// pick the supported encoding with the highest `q` value of an `Accept-Encoding` like `gzip;q=0.8, deflate`.
+fn choose_encoding(accept_encoding: &str) -> Option<&'static str> {
+    let mut best: Option<(&'static str, f32)> = None;
+    for item in accept_encoding.split(',') {
+        let mut parts = item.split(';');
+        let name = parts.next().unwrap_or("").trim().to_lowercase();
+        let quality = parts
+            .find_map(|part| part.trim().strip_prefix("q="))
+            .and_then(|quality| quality.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if quality <= 0.0 {
+            continue;
+        }
+
+        let candidates: Vec<&'static str> = if name == "*" {
+            SUPPORTED_ENCODINGS.to_vec()
+        }else {
+            SUPPORTED_ENCODINGS.iter().copied().filter(|encoding| *encoding == name).collect()
+        };
+        for candidate in candidates {
+            let is_better = match best {
+                Some((best_name, best_quality)) => quality > best_quality
+                    || (quality == best_quality && preference(candidate) < preference(best_name)),
+                None => true,
+            };
+            if is_better {
+                best = Some((candidate, quality));
+            }
+        }
+    }
+    best.map(|(encoding, _)| encoding)
+}
+
+fn preference(encoding: &str) -> usize {
+    SUPPORTED_ENCODINGS.iter().position(|supported| *supported == encoding).unwrap_or(usize::MAX)
+}
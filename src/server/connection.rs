@@ -1,33 +1,146 @@
-use tokio::{net::TcpStream, io::AsyncWriteExt};
+use std::{io::SeekFrom, time::Duration};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader};
 use crate::error;
-use super::{request::Request, response::Response};
+use super::{request::{Request, RequestLimits}, response::{FileBody, Response}, stream::Stream, throttle::Throttle, sse::EventStream};
+
+/// the bytes of a body that are written at once, the client has `write_timeout` to take each of them.
+const CHUNK_SIZE: usize = 64 * 1024;
 
 
 pub struct Connection {
-    pub request: Request,
-    pub socket: TcpStream
+    /// the reads are buffered, the bytes of the next request stay in the buffer between the requests
+    pub socket: BufReader<Stream>,
+    /// the time the client has to take every write of a response, it's not limited when it's `None`
+    pub write_timeout: Option<Duration>,
 }
 
 impl Connection {
     
-    pub async fn new(mut socket: TcpStream) -> Result<Connection, error::Error> {
-        let request = Request::new(&mut socket).await?;
+    pub fn new(socket: Stream) -> Connection {
+        // the head and the body are written separately, they shouldn't wait for the ack of each other
+        let _ = socket.tcp().set_nodelay(true);
+        Connection { socket: BufReader::new(socket), write_timeout: None }
+    }
 
-        Ok(Connection {
-            request,
-            socket
-        })
+    /// read the next request of the connection. it's `None` when the client closes
+    /// the connection or doesn't send anything for `idle_timeout`, and `RequestTimeout` is returned
+    /// when the rest of the request doesn't arrive in `read_timeout`.
+    pub async fn read_request(&mut self, idle_timeout: Duration, read_timeout: Duration, limits: RequestLimits) -> Result<Option<Request>, error::Error> {
+        match tokio::time::timeout(idle_timeout, self.socket.fill_buf()).await {
+            Ok(Ok([])) | Err(_) => return Ok(None),
+            Ok(Ok(_)) => {},
+            Ok(Err(e)) => return Err(e.into()),
+        };
+        let mut request = match tokio::time::timeout(read_timeout, Request::new(&mut self.socket, limits)).await {
+            Ok(Ok(request)) => request,
+            Ok(Err(e)) => return Err(e),
+            Err(_) => return Err(error::Error::RequestTimeout),
+        };
+        let socket = self.socket.get_ref();
+        request.client_cert_subject = socket.peer_subject();
+        request.peer_ip = socket.tcp().peer_addr().ok().map(|addr| addr.ip());
+
+        Ok(Some(request))
     }
 
     pub async fn respond(&mut self, response: Response) -> Result<(), error::Error> {
-        self.socket.write_all(format!("HTTP/1.1 {} {}\r\n", response.status.code, response.status.message).as_bytes()).await?;
+        if let Some(fault) = &response.fault {
+            return fault.inject(self, &response).await;
+        }
+
+        self.write_head(&response).await?;
+        let mut throttle = response.throttle_kbps.map(Throttle::new);
+        let chunk_size = throttle.as_ref().map(|throttle| throttle.chunk_size()).unwrap_or(CHUNK_SIZE);
+        for chunk in response.body.chunks(chunk_size) {
+            self.write_chunk(chunk, &mut throttle).await?;
+        }
+        if let Some(file) = &response.file {
+            self.write_file(file, chunk_size, &mut throttle).await?;
+        }
+        if let Some(events) = response.events {
+            self.write_events(events).await?;
+        }
+        if let Some(session) = response.websocket {
+            session.run(self).await?;
+        }
+
+        Ok(())
+    }
+
+    /// send the events one by one as they're due.
+    async fn write_events(&mut self, mut events: EventStream) -> Result<(), error::Error> {
+        if let Some(head) = events.head() {
+            self.write(head.as_bytes()).await?;
+        }
+        self.socket.flush().await?;
+        while let Some(event) = events.next_event() {
+            let (delay, event) = event?;
+            tokio::time::sleep(delay).await;
+            self.write(event.as_bytes()).await?;
+            self.socket.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// copy the part of the file to the socket with a bounded buffer.
+    async fn write_file(&mut self, file: &FileBody, chunk_size: usize, throttle: &mut Option<Throttle>) -> Result<(), error::Error> {
+        let mut reader = tokio::fs::File::open(&file.path).await?;
+        reader.seek(SeekFrom::Start(file.start)).await?;
+        let mut reader = reader.take(file.len);
+        let mut buffer = vec![0; chunk_size];
+        let mut written = 0;
+        loop {
+            let read = reader.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            self.write_chunk(&buffer[..read], throttle).await?;
+            written += read as u64;
+        }
+        // the `Content-Length` is already sent, a shorter body can't be fixed
+        if written < file.len {
+            return Err(error::Error::IoError(format!("`{}` is shorter than its size", file.path.display())));
+        }
+        Ok(())
+    }
+
+    /// a throttled chunk waits for its turn and is sent right away.
+    async fn write_chunk(&mut self, chunk: &[u8], throttle: &mut Option<Throttle>) -> Result<(), error::Error> {
+        match throttle {
+            Some(throttle) => {
+                throttle.wait(chunk.len()).await;
+                self.write(chunk).await?;
+                self.socket.flush().await?;
+            },
+            None => self.write(chunk).await?,
+        }
+        Ok(())
+    }
+
+    /// write all of the bytes, the client has `write_timeout` to take them.
+    pub async fn write(&mut self, bytes: &[u8]) -> Result<(), error::Error> {
+        match self.write_timeout {
+            Some(write_timeout) => match tokio::time::timeout(write_timeout, self.socket.write_all(bytes)).await {
+                Ok(result) => Ok(result?),
+                Err(_) => Err(error::Error::IoError("the client didn't read the response in time".to_string())),
+            },
+            None => Ok(self.socket.write_all(bytes).await?),
+        }
+    }
+
+    /// write the status line and the headers in one go.
+    pub async fn write_head(&mut self, response: &Response) -> Result<(), error::Error> {
+        let mut head = format!("HTTP/1.1 {} {}\r\n", response.status.code, response.status.message);
         for (k, v) in response.headers.iter() {
-            self.socket.write_all(format!("{}: {}\r\n", k, v).as_bytes()).await?;
+            head.push_str(&format!("{}: {}\r\n", k, v));
         }
-        self.socket.write_all(b"\r\n").await?;
-        self.socket.write_all(response.body.as_slice()).await?;
+        for cookie in response.cookies.iter() {
+            head.push_str(&format!("Set-Cookie: {}\r\n", cookie));
+        }
+        head.push_str("\r\n");
+        self.write(head.as_bytes()).await?;
 
         Ok(())
     }
 
-}
\ No newline at end of file
+}
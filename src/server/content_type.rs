@@ -1,13 +1,29 @@
 
+#[allow(clippy::upper_case_acronyms)]
 pub enum ContentType {
     // documents
     TXT,
     PDF,
+    HTML,
+    CSS,
+    JS,
+    JSON,
+    XML,
+    CSV,
+    WASM,
 
     // images
     PNG,
     JPG,
     JPEG,
+    GIF,
+    SVG,
+    WEBP,
+    ICO,
+
+    // fonts
+    WOFF,
+    WOFF2,
 
     // videos
     MP4,
@@ -46,9 +62,22 @@ impl From<&str> for ContentType {
         match s {
             "txt"  => ContentType::TXT,
             "pdf"  => ContentType::PDF ,
+            "html" | "htm" => ContentType::HTML,
+            "css"  => ContentType::CSS,
+            "js" | "mjs" => ContentType::JS,
+            "json" | "map" => ContentType::JSON,
+            "xml"  => ContentType::XML,
+            "csv"  => ContentType::CSV,
+            "wasm" => ContentType::WASM,
             "png"  => ContentType::PNG,
             "jpg"  => ContentType::JPG,
             "jpeg" => ContentType::JPEG,
+            "gif"  => ContentType::GIF,
+            "svg"  => ContentType::SVG,
+            "webp" => ContentType::WEBP,
+            "ico"  => ContentType::ICO,
+            "woff" => ContentType::WOFF,
+            "woff2" => ContentType::WOFF2,
             "mp4"  => ContentType::MP4,
             "mkv"  => ContentType::MKV,
             "mpeg" => ContentType::MPEG,
@@ -73,9 +102,22 @@ impl From<ContentType> for String {
         match content_type {
             ContentType::TXT =>  "text/plain".to_string(),
             ContentType::PDF =>  "application/pdf".to_string(),
+            ContentType::HTML => "text/html".to_string(),
+            ContentType::CSS =>  "text/css".to_string(),
+            ContentType::JS =>   "text/javascript".to_string(),
+            ContentType::JSON => "application/json".to_string(),
+            ContentType::XML =>  "application/xml".to_string(),
+            ContentType::CSV =>  "text/csv".to_string(),
+            ContentType::WASM => "application/wasm".to_string(),
             ContentType::PNG =>  "image/png".to_string(),
             ContentType::JPG | ContentType::JPEG 
                 =>  "image/jpeg".to_string(),
+            ContentType::GIF =>  "image/gif".to_string(),
+            ContentType::SVG =>  "image/svg+xml".to_string(),
+            ContentType::WEBP => "image/webp".to_string(),
+            ContentType::ICO =>  "image/x-icon".to_string(),
+            ContentType::WOFF => "font/woff".to_string(),
+            ContentType::WOFF2 => "font/woff2".to_string(),
             ContentType::MP4 =>  "video/mp4".to_string(),
             ContentType::MKV =>  "video/x-matroska".to_string(),
             ContentType::MPEG => "video/mpeg".to_string(),
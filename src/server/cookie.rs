@@ -0,0 +1,62 @@
+use serde::Deserialize;
+
+/// a cookie to set on the client with a `Set-Cookie` header.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SetCookie {
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+    pub path: Option<String>,
+    pub domain: Option<String>,
+    /// seconds until the cookie expires, `0` deletes it
+    pub max_age: Option<i64>,
+    /// an http date like `Wed, 21 Oct 2015 07:28:00 GMT`
+    pub expires: Option<String>,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(default)]
+    pub http_only: bool,
+    pub same_site: Option<SameSite>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl SetCookie {
+
+    /// the value of the `Set-Cookie` header, like `session=abc; Path=/; HttpOnly`.
+    pub fn to_header(&self) -> String {
+        let mut header = format!("{}={}", self.name, self.value);
+        if let Some(path) = &self.path {
+            header.push_str(&format!("; Path={}", path));
+        }
+        if let Some(domain) = &self.domain {
+            header.push_str(&format!("; Domain={}", domain));
+        }
+        if let Some(max_age) = self.max_age {
+            header.push_str(&format!("; Max-Age={}", max_age));
+        }
+        if let Some(expires) = &self.expires {
+            header.push_str(&format!("; Expires={}", expires));
+        }
+        if self.secure {
+            header.push_str("; Secure");
+        }
+        if self.http_only {
+            header.push_str("; HttpOnly");
+        }
+        match self.same_site {
+            Some(SameSite::Strict) => header.push_str("; SameSite=Strict"),
+            Some(SameSite::Lax) => header.push_str("; SameSite=Lax"),
+            Some(SameSite::None) => header.push_str("; SameSite=None"),
+            None => {},
+        }
+        header
+    }
+
+}
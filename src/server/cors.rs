@@ -0,0 +1,169 @@
+use crate::fake_rest::server_config::{CorsConfig, Server};
+use super::{request::{Method, Request}, response::Response, router, status::Status};
+
+/// answer the `OPTIONS` preflight requests of the browsers, it's `None` for the other requests.
+pub fn preflight(cors: &CorsConfig, request: &Request, server: &Server) -> Option<Response> {
+    if request.method != Method::OPTIONS {
+        return None;
+    }
+    let origin = request.header("Origin")?;
+    let requested_method = request.header("Access-Control-Request-Method")?;
+
+    let mut response = Response::text(Status::no_content(), "");
+    response.headers.remove("Content-Length");
+    response.headers.append("Vary", "Origin");
+    if !is_allowed_origin(cors, origin) {
+        return Some(response);
+    }
+
+    allow_origin(cors, origin, &mut response);
+    let methods = match &cors.allowed_methods {
+        Some(methods) => methods.join(", "),
+        None => {
+            let methods: Vec<String> = router::allowed_methods(&server.data, request)
+                .iter()
+                .map(|method| method.to_string())
+                .collect();
+            if methods.len() > 1 { methods.join(", ") } else { requested_method.clone() }
+        },
+    };
+    response.headers.insert("Access-Control-Allow-Methods".to_string(), methods);
+
+    // allow the requested headers when the config doesn't limit them
+    let headers = match &cors.allowed_headers {
+        Some(headers) => Some(headers.join(", ")),
+        None => request.header("Access-Control-Request-Headers").cloned(),
+    };
+    if let Some(headers) = headers {
+        response.headers.insert("Access-Control-Allow-Headers".to_string(), headers);
+    }
+    if let Some(max_age) = cors.max_age {
+        response.headers.insert("Access-Control-Max-Age".to_string(), max_age.to_string());
+    }
+    Some(response)
+}
+
+/// add the `Access-Control-*` headers to the response of a cross origin request.
+pub fn apply(cors: &CorsConfig, request: &Request, response: &mut Response) {
+    let origin = match request.header("Origin") {
+        Some(origin) => origin,
+        None => return,
+    };
+    response.headers.append("Vary", "Origin");
+    if !is_allowed_origin(cors, origin) {
+        return;
+    }
+
+    allow_origin(cors, origin, response);
+    if let Some(exposed_headers) = &cors.exposed_headers {
+        response.headers.insert("Access-Control-Expose-Headers".to_string(), exposed_headers.join(", "));
+    }
+}
+
+fn is_allowed_origin(cors: &CorsConfig, origin: &str) -> bool {
+    match &cors.allowed_origins {
+        Some(origins) => origins.iter().any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin)),
+        None => true,
+    }
+}
+
+/// the browsers don't accept `*` with credentials, so the origin is echoed back then.
+fn allow_origin(cors: &CorsConfig, origin: &str, response: &mut Response) {
+    let any_origin = cors.allowed_origins.as_ref().is_none_or(|origins| origins.iter().any(|allowed| allowed == "*"));
+    let allowed_origin = if any_origin && !cors.allow_credentials { "*" } else { origin };
+    response.headers.insert("Access-Control-Allow-Origin".to_string(), allowed_origin.to_string());
+    if cors.allow_credentials {
+        response.headers.insert("Access-Control-Allow-Credentials".to_string(), "true".to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::fake_rest::server_config::{ConfigFormat, ConfigSource, CorsConfig, Server};
+    use crate::server::{request::{Request, RequestLimits}, response::Response, status::Status};
+    use super::{apply, preflight};
+
+    fn cors(config: serde_json::Value) -> CorsConfig {
+        serde_json::from_value(config).unwrap()
+    }
+
+    async fn request(method: &str, headers: &[&str]) -> Request {
+        let raw = format!("{} /users HTTP/1.1\r\nHost: localhost\r\n{}\r\n", method, headers.iter().map(|header| format!("{}\r\n", header)).collect::<String>());
+        Request::new(&mut raw.as_bytes(), RequestLimits::default()).await.unwrap()
+    }
+
+    async fn server() -> Server {
+        let config = r#"
+            [config]
+            host = "127.0.0.1"
+            port = 0
+            [[data]]
+            method = "GET"
+            path = "/users"
+            result_type = "direct"
+            result = "[]"
+            [[data]]
+            method = "POST"
+            path = "/users"
+            result_type = "direct"
+            result = "{}"
+        "#;
+        ConfigSource::Text(config.to_string(), ConfigFormat::Toml).parse().await.unwrap().remove(0)
+    }
+
+    fn header<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+        response.headers.get(name).map(String::as_str)
+    }
+
+    #[tokio::test]
+    async fn allows_any_origin_by_default() {
+        let mut response = Response::text(Status::ok(), "");
+        apply(&cors(json!({})), &request("GET", &["Origin: https://app.test"]).await, &mut response);
+        assert_eq!(header(&response, "Access-Control-Allow-Origin"), Some("*"));
+        assert_eq!(header(&response, "Vary"), Some("Origin"));
+    }
+
+    #[tokio::test]
+    async fn echoes_the_origin_with_credentials() {
+        let mut response = Response::text(Status::ok(), "");
+        let config = cors(json!({ "allow_credentials": true, "exposed_headers": ["X-Total"] }));
+        apply(&config, &request("GET", &["Origin: https://app.test"]).await, &mut response);
+        assert_eq!(header(&response, "Access-Control-Allow-Origin"), Some("https://app.test"));
+        assert_eq!(header(&response, "Access-Control-Allow-Credentials"), Some("true"));
+        assert_eq!(header(&response, "Access-Control-Expose-Headers"), Some("X-Total"));
+    }
+
+    #[tokio::test]
+    async fn skips_the_other_origins() {
+        let config = cors(json!({ "allowed_origins": ["https://app.test"] }));
+        let mut response = Response::text(Status::ok(), "");
+        apply(&config, &request("GET", &["Origin: https://evil.test"]).await, &mut response);
+        assert_eq!(header(&response, "Access-Control-Allow-Origin"), None);
+
+        let mut response = Response::text(Status::ok(), "");
+        apply(&config, &request("GET", &["Origin: HTTPS://APP.TEST"]).await, &mut response);
+        assert_eq!(header(&response, "Access-Control-Allow-Origin"), Some("HTTPS://APP.TEST"));
+    }
+
+    #[tokio::test]
+    async fn answers_the_preflight_with_the_methods_of_the_path() {
+        let request = request("OPTIONS", &[
+            "Origin: https://app.test",
+            "Access-Control-Request-Method: POST",
+            "Access-Control-Request-Headers: X-Token",
+        ]).await;
+        let response = preflight(&cors(json!({ "max_age": 600 })), &request, &server().await).unwrap();
+        assert_eq!(response.status.code, 204);
+        assert_eq!(header(&response, "Access-Control-Allow-Methods"), Some("GET, POST, HEAD, OPTIONS"));
+        assert_eq!(header(&response, "Access-Control-Allow-Headers"), Some("X-Token"));
+        assert_eq!(header(&response, "Access-Control-Max-Age"), Some("600"));
+    }
+
+    #[tokio::test]
+    async fn ignores_the_requests_that_are_not_preflights() {
+        let server = server().await;
+        assert!(preflight(&cors(json!({})), &request("GET", &["Origin: https://app.test"]).await, &server).is_none());
+        assert!(preflight(&cors(json!({})), &request("OPTIONS", &["Origin: https://app.test"]).await, &server).is_none());
+    }
+}
@@ -0,0 +1,210 @@
+use std::{cmp::Ordering, path::Path};
+use serde_json::{json, Map, Value};
+use crate::{error::Error, fake_rest::server_config::ServerDataSchema};
+use super::{request::Request, resource::RESOURCE_ID, status::Status};
+
+/// read the records of a `dataset` route from a json array or a csv file with a header row.
+///
+/// the csv values are numbers, `true` and `false` when they look like them, the empty ones are `null`.
+pub async fn load(path: &str) -> Result<Vec<Value>, Error> {
+    let content = tokio::fs::read_to_string(path).await.map_err(|e| Error::ConfigFileOpenError(
+        format!("can't read the dataset file `{}`: {}", path, e)
+    ))?;
+    let is_csv = Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    if !is_csv {
+        return serde_json::from_str(&content).map_err(|e| Error::ConfigValidationError(
+            format!("the dataset file `{}` must be a json array: {}", path, e)
+        ));
+    }
+
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+    let invalid = |e: csv::Error| Error::ConfigValidationError(format!("the dataset file `{}` is not a valid csv: {}", path, e));
+    let headers = reader.headers().map_err(invalid)?.clone();
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row.map_err(invalid)?;
+        let record: Map<String, Value> = headers.iter().zip(row.iter()).map(|(name, value)| (name.to_string(), csv_value(value))).collect();
+        records.push(Value::Object(record));
+    }
+    Ok(records)
+}
+
+fn csv_value(value: &str) -> Value {
+    if value.is_empty() {
+        return Value::Null;
+    }
+    if let Ok(number) = value.parse::<i64>() {
+        return Value::from(number);
+    }
+    match value {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => value.parse::<f64>().ok().filter(|number| number.is_finite()).map(Value::from).unwrap_or_else(|| Value::String(value.to_string())),
+    }
+}
+
+/// serve the records of a `dataset` route, `GET /path` lists them and `GET /path/{id}` finds the one with
+/// the `id_field` (default is `id`).
+///
+/// the query strings of the list that are fields of the records filter them, `?color=red` keeps the records
+/// with that value and `?name_like=shirt` the ones that contain it in any case. the nested fields are like
+/// `address.city`, and the other query strings are ignored.
+///
+/// `?sort=-price,name` sorts the list by the fields, descending with a `-`, and `?fields=sku,name` keeps
+/// only these fields of the records, of the list or the one record.
+pub fn handle(item: &ServerDataSchema, request: &Request) -> (Status, Vec<u8>) {
+    let records = item.records.as_slice();
+    let key = item.id_field.as_deref().unwrap_or(RESOURCE_ID);
+    let fields: Option<Vec<&str>> = request.query_strings.get("fields").map(|fields| {
+        fields.split(',').map(str::trim).filter(|field| !field.is_empty()).collect()
+    });
+    let (status, result) = match request.path_params.get(RESOURCE_ID) {
+        None => {
+            let filters = filters(records, request);
+            let mut records: Vec<&Value> = records.iter().filter(|record| filters.iter().all(|filter| filter.matches(record))).collect();
+            if let Some(sort) = request.query_strings.get("sort") {
+                sort_records(&mut records, sort);
+            }
+            let records: Vec<Value> = records.into_iter().map(|record| select(record, fields.as_deref())).collect();
+            (Status::ok(), Value::from(records))
+        },
+        Some(id) => match records.iter().find(|record| record.get(key).is_some_and(|value| same_value(value, id))) {
+            Some(record) => (Status::ok(), select(record, fields.as_deref())),
+            None => (Status::not_found(), json!({ "error": "Not Found" })),
+        },
+    };
+    (status, result.to_string().into_bytes())
+}
+
+/// the values are compared with the text of the request, like `42` with the number 42.
+fn same_value(value: &Value, text: &str) -> bool {
+    match value {
+        Value::String(value) => value == text,
+        Value::Null => false,
+        value => serde_json::from_str::<Value>(text).is_ok_and(|text| text == *value),
+    }
+}
+
+/// a query string that filters the records.
+struct Filter<'a> {
+    field: &'a str,
+    value: &'a str,
+    substring: bool,
+}
+
+impl Filter<'_> {
+
+    fn matches(&self, record: &Value) -> bool {
+        match field(record, self.field) {
+            Some(value) if self.substring => {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    Value::Null => return false,
+                    value => value.to_string(),
+                };
+                value.to_lowercase().contains(&self.value.to_lowercase())
+            },
+            Some(value) => same_value(value, self.value),
+            None => false,
+        }
+    }
+
+}
+
+/// the query strings of the fields of the records, the exact ones and the `_like` ones.
+/// `sort` and `fields` are never filters.
+fn filters<'a>(records: &[Value], request: &'a Request) -> Vec<Filter<'a>> {
+    let is_field = |name: &str| records.iter().any(|record| field(record, name).is_some());
+    let mut filters = Vec::new();
+    for (name, value) in request.query_strings.iter() {
+        if matches!(name.as_str(), "sort" | "fields") {
+            continue;
+        }
+        if is_field(name) {
+            filters.push(Filter { field: name, value, substring: false });
+        }else if let Some(name) = name.strip_suffix("_like").filter(|name| is_field(name)) {
+            filters.push(Filter { field: name, value, substring: true });
+        }
+    }
+    filters
+}
+
+/// the value of a field of the record, the nested ones are like `address.city`.
+fn field<'a>(record: &'a Value, name: &str) -> Option<&'a Value> {
+    name.split('.').try_fold(record, |value, key| value.get(key))
+}
+
+/// sort the records by the fields of `sort` one after another, the records without a field go last.
+fn sort_records(records: &mut [&Value], sort: &str) {
+    let keys: Vec<(&str, bool)> = sort
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(|key| match key.strip_prefix('-') {
+            Some(key) => (key, true),
+            None => (key.strip_prefix('+').unwrap_or(key), false),
+        })
+        .collect();
+    records.sort_by(|a, b| {
+        keys.iter().fold(Ordering::Equal, |ordering, (key, descending)| {
+            ordering.then_with(|| {
+                let (a, b) = (field(a, key).filter(|value| !value.is_null()), field(b, key).filter(|value| !value.is_null()));
+                match (a, b) {
+                    (Some(a), Some(b)) if *descending => compare(b, a),
+                    (Some(a), Some(b)) => compare(a, b),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            })
+        })
+    });
+}
+
+/// the numbers are compared by their value and the strings by their text, the booleans go before the
+/// numbers and the numbers before the strings.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+/// the record with only the `fields`, the nested ones like `address.city` keep their parents.
+fn select(record: &Value, fields: Option<&[&str]>) -> Value {
+    let Some(fields) = fields else {
+        return record.clone();
+    };
+    let mut selected = Value::Object(Map::new());
+    for name in fields {
+        let Some(value) = field(record, name) else {
+            continue;
+        };
+        let mut target = &mut selected;
+        let mut keys = name.split('.').peekable();
+        while let Some(key) = keys.next() {
+            let Value::Object(object) = target else {
+                break;
+            };
+            if keys.peek().is_none() {
+                object.insert(key.to_string(), value.clone());
+                break;
+            }
+            target = object.entry(key.to_string()).or_insert_with(|| Value::Object(Map::new()));
+        }
+    }
+    selected
+}
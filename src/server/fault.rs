@@ -0,0 +1,46 @@
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use crate::error::Error;
+use super::{connection::Connection, response::Response};
+
+/// broken server behaviours to test the resilience of the clients.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Fault {
+    /// close the connection with a tcp reset
+    ConnectionReset,
+    /// close the connection without writing anything
+    EmptyResponse,
+    /// write something that is not http
+    MalformedResponse,
+    /// write the head and half of the body, then close the connection
+    HalfBodyThenClose,
+}
+
+impl Fault {
+
+    pub async fn inject(&self, connection: &mut Connection, response: &Response) -> Result<(), Error> {
+        match self {
+            Fault::ConnectionReset => {
+                // a zero linger makes dropping the socket send a RST instead of a FIN
+                connection.socket.get_ref().tcp().set_zero_linger()?;
+                return Ok(());
+            },
+            Fault::EmptyResponse => {},
+            Fault::MalformedResponse => {
+                connection.socket.write_all(b"HTTP/1.1 ??? Malformed\r\nthis is not: a valid\r\nheader\r\n\r\n").await?;
+                connection.socket.write_all(b"\x00\xff\xfe garbage \x00").await?;
+            },
+            Fault::HalfBodyThenClose => {
+                connection.write_head(response).await?;
+                let half = response.body.len() / 2;
+                connection.socket.write_all(&response.body[..half]).await?;
+                connection.socket.flush().await?;
+            },
+        }
+
+        connection.socket.shutdown().await.ok();
+        Ok(())
+    }
+
+}
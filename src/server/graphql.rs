@@ -0,0 +1,186 @@
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use crate::{error::Error, fake_rest::server_config::ResultValue};
+use super::{request::Request, status::Status, template};
+
+/// the canned responses of a `graphql` route.
+#[derive(Debug, Deserialize, Clone)]
+pub struct GraphQl {
+    pub operations: Vec<Operation>,
+}
+
+/// the response of the operations with the `name`, the first one that matches is used.
+///
+/// an operation without a `name` matches all of them, like the anonymous `{ users { id } }` queries.
+/// the `variables` that are set must be equal to the ones of the request, the others can be anything.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Operation {
+    pub name: Option<String>,
+    pub variables: Option<Map<String, Value>>,
+    /// a template of the request with `{{graphql.operation}}`, `{{graphql.type}}` and `{{graphql.variables.*}}`.
+    /// it's served as `{"data": result}` unless it has `data` or `errors` itself
+    pub result: ResultValue,
+}
+
+/// an operation of the request.
+struct Query {
+    name: Option<String>,
+    kind: String,
+    variables: Value,
+}
+
+/// answer the queries and mutations of the request, a json array of them is answered with an array.
+///
+/// the request is the json of `query`, `operationName` and `variables` in the body or the query string of
+/// a `GET`, or the query itself with `Content-Type: application/graphql`. the operations without a response
+/// are answered with an error like a real server, with 200.
+pub fn handle(graphql: &GraphQl, request: &Request) -> Result<(Status, Vec<u8>), Error> {
+    let body = match request.json() {
+        Some(Value::Array(batch)) => {
+            let mut results = Vec::new();
+            for item in batch {
+                match parse(item) {
+                    Some(query) => results.push(resolve(graphql, &query, request)?),
+                    None => return Ok(bad_request()),
+                }
+            }
+            Value::Array(results)
+        },
+        Some(item) => match parse(item) {
+            Some(query) => resolve(graphql, &query, request)?,
+            None => return Ok(bad_request()),
+        },
+        None => match raw_query(request) {
+            Some(query) => resolve(graphql, &query, request)?,
+            None => return Ok(bad_request()),
+        },
+    };
+    Ok((Status::ok(), body.to_string().into_bytes()))
+}
+
+/// the query of a json request.
+fn parse(item: &Value) -> Option<Query> {
+    let text = item.get("query")?.as_str()?;
+    let variables = match item.get("variables") {
+        Some(Value::Null) | None => json!({}),
+        Some(variables) => variables.clone(),
+    };
+    let name = item.get("operationName").and_then(|name| name.as_str()).map(|name| name.to_string());
+    Some(query(text, name, variables))
+}
+
+/// the query of a `GET` or an `application/graphql` body.
+fn raw_query(request: &Request) -> Option<Query> {
+    if let Some(text) = request.query_strings.get("query") {
+        let variables = request.query_strings.get("variables")
+            .and_then(|variables| serde_json::from_str(variables).ok())
+            .unwrap_or_else(|| json!({}));
+        return Some(query(text, request.query_strings.get("operationName").cloned(), variables));
+    }
+    let text = std::str::from_utf8(&request.body).ok().filter(|text| !text.trim().is_empty())?;
+    Some(query(text, None, json!({})))
+}
+
+fn query(text: &str, name: Option<String>, variables: Value) -> Query {
+    let (kind, parsed_name) = operation(text, name.as_deref());
+    Query { name: name.or(parsed_name), kind, variables }
+}
+
+fn resolve(graphql: &GraphQl, query: &Query, request: &Request) -> Result<Value, Error> {
+    let found = graphql.operations.iter().find(|operation| {
+        let named = operation.name.is_none() || operation.name == query.name;
+        let variables = operation.variables.iter().flatten().all(|(name, value)| query.variables.get(name) == Some(value));
+        named && variables
+    });
+    let Some(operation) = found else {
+        let name = query.name.as_deref().unwrap_or("anonymous");
+        return Ok(errors(&format!("there's no response for the operation `{}`", name)));
+    };
+
+    let mut context = template::context(request);
+    context["graphql"] = json!({ "operation": query.name, "type": query.kind, "variables": query.variables });
+    let result = template::render_context(&operation.result, &context)?;
+    Ok(match serde_json::from_str::<Value>(&result) {
+        Ok(Value::Object(result)) if result.contains_key("data") || result.contains_key("errors") => Value::Object(result),
+        Ok(result) => json!({ "data": result }),
+        Err(_) => errors(&format!("the result of the operation is not a json: {}", result)),
+    })
+}
+
+fn errors(message: &str) -> Value {
+    json!({ "errors": [{ "message": message }] })
+}
+
+fn bad_request() -> (Status, Vec<u8>) {
+    (Status::bad_request(), errors("the request doesn't have a query").to_string().into_bytes())
+}
+
+/// the type and the name of the operation of the document, the one with the `name` if it's set or the first one.
+///
+/// the names are read at the top level of the document, out of the selections, the arguments, the strings
+/// and the comments. the anonymous operations are queries without a name.
+fn operation(document: &str, name: Option<&str>) -> (String, Option<String>) {
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut chars = document.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '#' => while chars.next_if(|(_, c)| *c != '\n').is_some() {},
+            '"' => {
+                let block = document[start..].starts_with("\"\"\"");
+                if block {
+                    chars.nth(1);
+                }
+                while let Some((index, c)) = chars.next() {
+                    match c {
+                        '\\' => { chars.next(); },
+                        '"' if !block => break,
+                        '"' if document[index..].starts_with("\"\"\"") => {
+                            chars.nth(1);
+                            break;
+                        },
+                        _ => {},
+                    }
+                }
+            },
+            '{' | '(' => {
+                if depth == 0 && c == '{' {
+                    tokens.push("{".to_string());
+                }
+                depth += 1;
+            },
+            '}' | ')' => depth -= 1,
+            c if depth == 0 && (c.is_ascii_alphabetic() || c == '_') => {
+                let mut token = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_') {
+                    token.push(c);
+                }
+                tokens.push(token);
+            },
+            _ => {},
+        }
+    }
+
+    let mut operations = Vec::new();
+    // a definition that waits for its selections, the `{` after it is not an anonymous query
+    let mut pending = false;
+    let mut tokens = tokens.iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token.as_str() {
+            "query" | "mutation" | "subscription" if !pending => {
+                let operation_name = tokens.next_if(|next| next.as_str() != "{").cloned();
+                operations.push((token.clone(), operation_name));
+                pending = true;
+            },
+            "fragment" if !pending => pending = true,
+            "{" if pending => pending = false,
+            "{" => operations.push(("query".to_string(), None)),
+            _ => {},
+        }
+    }
+    let found = match name {
+        Some(name) => operations.iter().find(|(_, operation_name)| operation_name.as_deref() == Some(name)),
+        None => operations.first(),
+    };
+    found.cloned().unwrap_or_else(|| ("query".to_string(), None))
+}
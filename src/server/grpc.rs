@@ -0,0 +1,137 @@
+use std::path::Path;
+use base64::Engine;
+use prost_reflect::{prost::Message, DeserializeOptions, DynamicMessage, MethodDescriptor, SerializeOptions};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use crate::error::Error;
+use super::{helpers, request::Request, template};
+
+/// the grpc status codes of the failures of the server itself.
+const INVALID_ARGUMENT: u32 = 3;
+const UNIMPLEMENTED: u32 = 12;
+const INTERNAL: u32 = 13;
+
+/// the method of a `grpc` route, the path of the route is the grpc path like `/helloworld.Greeter/SayHello`.
+///
+/// the requests are grpc-web, the http/2 of the native grpc is not served.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Grpc {
+    /// the `.proto` file of the service, its imports are read from its directory
+    pub proto: String,
+    /// the grpc status of the response, 0 (OK) by default. the other ones are sent without a message
+    pub status: Option<u32>,
+    /// the `grpc-message` of the status
+    pub message: Option<String>,
+    #[serde(skip)]
+    pub method: Option<MethodDescriptor>,
+}
+
+impl Grpc {
+
+    /// compile the `proto` file and find the method of the route `path`.
+    pub fn load(&mut self, path: &str) -> Result<(), Error> {
+        let proto = Path::new(&self.proto);
+        let include = proto.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let pool = protox::Compiler::new([include])
+            .and_then(|mut compiler| compiler.include_imports(true).open_file(proto).map(|compiler| compiler.descriptor_pool()))
+            .map_err(|e| Error::ConfigValidationError(format!("can't compile the proto file `{}`: {}", self.proto, e)))?;
+
+        let (service, method) = path.trim_start_matches('/').split_once('/').ok_or_else(|| Error::ConfigValidationError(
+            format!("the path of the grpc route `{}` must be like `/package.Service/Method`", path)
+        ))?;
+        let service = pool.get_service_by_name(service).ok_or_else(|| Error::ConfigValidationError(
+            format!("the service `{}` is not in `{}`", service, self.proto)
+        ))?;
+        let method = service.methods().find(|item| item.name() == method).ok_or_else(|| Error::ConfigValidationError(
+            format!("the service `{}` doesn't have the method `{}`", service.full_name(), method)
+        ))?;
+        self.method = Some(method);
+        Ok(())
+    }
+
+}
+
+/// answer a grpc-web request with the `result`, a template of the json of the response message.
+///
+/// the template gets the request message as `{{grpc.request.*}}` with the field names of the proto file.
+/// the server streaming methods send every item of a json array as a message. the request and the response
+/// are base64 with `application/grpc-web-text`. it's the content type and the body of the response,
+/// the failures are sent as grpc statuses.
+pub fn handle(grpc: &Grpc, result: &str, request: &Request) -> (String, Vec<u8>) {
+    let content_type = request.header("Content-Type").map(|value| value.to_ascii_lowercase()).unwrap_or_default();
+    let text = content_type.starts_with("application/grpc-web-text");
+    let content_type = if text { "application/grpc-web-text+proto" } else { "application/grpc-web+proto" };
+
+    let body = match messages(grpc, result, request, text) {
+        Ok(messages) => {
+            let mut body = Vec::new();
+            for message in messages {
+                body.extend(frame(0x00, &message));
+            }
+            body.extend(trailers(grpc.status.unwrap_or(0), grpc.message.as_deref().unwrap_or_default()));
+            body
+        },
+        Err((status, message)) => trailers(status, &message),
+    };
+    let body = if text { base64::engine::general_purpose::STANDARD.encode(body).into_bytes() } else { body };
+    (content_type.to_string(), body)
+}
+
+/// the encoded response messages, none of them for the failed statuses.
+fn messages(grpc: &Grpc, result: &str, request: &Request, text: bool) -> Result<Vec<Vec<u8>>, (u32, String)> {
+    let method = grpc.method.as_ref().ok_or((UNIMPLEMENTED, "the method is not loaded".to_string()))?;
+    let body = if text {
+        let body: Vec<u8> = request.body.iter().copied().filter(|byte| !byte.is_ascii_whitespace()).collect();
+        base64::engine::general_purpose::STANDARD.decode(body).map_err(|e| (INVALID_ARGUMENT, format!("the body is not base64: {}", e)))?
+    }else {
+        request.body.clone()
+    };
+
+    // a frame is a flag of compression, the length of the message as a big endian u32 and the message
+    let message = match body.get(..5) {
+        Some([0x00, len @ ..]) => {
+            let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+            body.get(5..5 + len).ok_or((INVALID_ARGUMENT, "the request message is incomplete".to_string()))?
+        },
+        Some(_) => return Err((UNIMPLEMENTED, "the compressed messages are not supported".to_string())),
+        None => &[][..],
+    };
+    let input = DynamicMessage::decode(method.input(), message).map_err(|e| (INVALID_ARGUMENT, e.to_string()))?;
+    let input = input
+        .serialize_with_options(serde_json::value::Serializer, &SerializeOptions::new().use_proto_field_name(true))
+        .map_err(|e| (INTERNAL, e.to_string()))?;
+
+    if grpc.status.is_some_and(|status| status != 0) {
+        return Ok(Vec::new());
+    }
+    let mut context = template::context(request);
+    context["grpc"] = json!({ "request": input });
+    let output = template::render_context(result, &context).map_err(|e| (INTERNAL, e.to_string()))?;
+    let output: Value = serde_json::from_str(&output).map_err(|e| (INTERNAL, format!("the result is not a json: {}", e)))?;
+    let outputs = match output {
+        Value::Array(outputs) if method.is_server_streaming() => outputs,
+        output => vec![output],
+    };
+    outputs.into_iter().map(|output| {
+        DynamicMessage::deserialize_with_options(method.output(), output, &DeserializeOptions::new())
+            .map(|message| message.encode_to_vec())
+            .map_err(|e| (INTERNAL, format!("the result is not a `{}`: {}", method.output().full_name(), e)))
+    }).collect()
+}
+
+fn frame(flag: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![flag];
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// the trailers are sent in the body as a frame with the flag `0x80`.
+fn trailers(status: u32, message: &str) -> Vec<u8> {
+    let mut trailers = format!("grpc-status:{}\r\n", status);
+    if !message.is_empty() {
+        trailers.push_str(&format!("grpc-message:{}\r\n", helpers::percent_encode(message)));
+    }
+    frame(0x80, trailers.as_bytes())
+}
+
@@ -0,0 +1,73 @@
+use serde::{Serialize, Serializer, ser::SerializeMap};
+
+/// http headers with case-insensitive names, they keep the order and the case they're inserted with.
+#[derive(Debug, Clone, Default)]
+pub struct Headers {
+    entries: Vec<(String, String)>,
+}
+
+impl Headers {
+
+    pub fn new() -> Headers {
+        Headers { entries: Vec::new() }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut String> {
+        self.entries
+            .iter_mut()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// set the header, it replaces the value of the same header in any case.
+    pub fn insert(&mut self, name: String, value: String) {
+        match self.entries.iter_mut().find(|(key, _)| key.eq_ignore_ascii_case(&name)) {
+            Some(entry) => *entry = (name, value),
+            None => self.entries.push((name, value)),
+        }
+    }
+
+    /// add a value to a list header like `Vary: Origin, Accept-Encoding`.
+    pub fn append(&mut self, name: &str, value: &str) {
+        match self.get_mut(name) {
+            Some(values) if values.split(',').any(|item| item.trim().eq_ignore_ascii_case(value)) => {},
+            Some(values) => {
+                values.push_str(", ");
+                values.push_str(value);
+            },
+            None => self.insert(name.to_string(), value.to_string()),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let index = self.entries.iter().position(|(key, _)| key.eq_ignore_ascii_case(name))?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+}
+
+/// serialize as a map of the names as they're sent, the templates add their lowercase names too.
+impl Serialize for Headers {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (key, value) in self.entries.iter() {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
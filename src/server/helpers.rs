@@ -1,7 +1,7 @@
 use crate::error::Error;
 
 pub fn get_key_value(content: &str, delimiter: char) -> Result<(String, String), Error> {
-    let mut split = content.split(delimiter);
+    let mut split = content.splitn(2, delimiter);
     let key = match split.next() {
         Some(key) => key.trim().to_string(),
         None => return Err(Error::ParsingError(
@@ -19,7 +19,7 @@ pub fn get_key_value(content: &str, delimiter: char) -> Result<(String, String),
 }
 
 pub fn get_key_optional_value(content: &str, delimiter: char) -> Result<(String, String), Error> {
-    let mut split = content.split(delimiter);
+    let mut split = content.splitn(2, delimiter);
     let key = match split.next() {
         Some(key) => key.trim().to_string(),
         None => return Err(Error::ParsingError(
@@ -34,10 +34,90 @@ pub fn get_key_optional_value(content: &str, delimiter: char) -> Result<(String,
     Ok((key, value))
 }
 
+/// decode the `%XX` escapes and, for the form and query values, `+` as a space.
+/// invalid escapes are kept as they are.
+pub fn percent_decode(content: &str, plus_as_space: bool) -> String {
+    let bytes = content.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' => match bytes.get(index + 1..index + 3).and_then(decode_hex) {
+                Some(byte) => {
+                    decoded.push(byte);
+                    index += 3;
+                    continue;
+                },
+                None => decoded.push(b'%'),
+            },
+            b'+' if plus_as_space => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// escape the bytes of a path segment that aren't unreserved as `%XX`.
+pub fn percent_encode(content: &str) -> String {
+    let mut encoded = String::with_capacity(content.len());
+    for byte in content.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        }else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn decode_hex(pair: &[u8]) -> Option<u8> {
+    // `from_str_radix` takes a sign too, like `+1`
+    if !pair.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()
+}
+
 fn get_key_value_type<'a>(delimiter: char) -> &'a str {
     if delimiter == ':' {
         "header"
+    }else if delimiter == '~' {
+        "regex"
     }else {
         "query string"
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::percent_decode;
+
+    #[test]
+    fn decodes_spaces() {
+        assert_eq!(percent_decode("a%20b", false), "a b");
+        assert_eq!(percent_decode("a%20b", true), "a b");
+    }
+
+    #[test]
+    fn decodes_plus_as_space_only_in_queries() {
+        assert_eq!(percent_decode("a+b", false), "a+b");
+        assert_eq!(percent_decode("a+b", true), "a b");
+        assert_eq!(percent_decode("a%2Bb", true), "a+b");
+    }
+
+    #[test]
+    fn decodes_multi_byte_utf8() {
+        assert_eq!(percent_decode("caf%C3%A9", false), "café");
+        assert_eq!(percent_decode("%E2%9C%93%F0%9F%98%80", false), "✓😀");
+    }
+
+    #[test]
+    fn keeps_invalid_escapes() {
+        assert_eq!(percent_decode("100%", false), "100%");
+        assert_eq!(percent_decode("%4", false), "%4");
+        assert_eq!(percent_decode("%zz%41", false), "%zzA");
+        assert_eq!(percent_decode("%+1", true), "% 1");
+        assert_eq!(percent_decode("%C3", false), "\u{FFFD}");
+    }
+}
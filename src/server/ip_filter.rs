@@ -0,0 +1,113 @@
+use std::net::IpAddr;
+use ipnet::IpNet;
+use serde::Deserialize;
+use crate::error::Error;
+
+/// the client addresses that can use the server or a route, like `10.0.0.0/8` or `127.0.0.1`.
+///
+/// the `deny` list wins over the `allow` list, and any address is allowed without an `allow` list.
+#[derive(Debug, Deserialize, Clone)]
+pub struct IpFilter {
+    pub allow: Option<Vec<String>>,
+    pub deny: Option<Vec<String>>,
+    #[serde(skip)]
+    compiled_allow: Option<Vec<IpNet>>,
+    #[serde(skip)]
+    compiled_deny: Vec<IpNet>,
+}
+
+impl IpFilter {
+
+    pub fn compile(&mut self) -> Result<(), Error> {
+        if let Some(allow) = &self.allow {
+            self.compiled_allow = Some(allow.iter().map(|net| parse_net(net)).collect::<Result<_, _>>()?);
+        }
+        self.compiled_deny = self.deny.iter().flatten().map(|net| parse_net(net)).collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    pub fn is_allowed(&self, ip: Option<IpAddr>) -> bool {
+        let ip = match ip {
+            // an ipv4 client of a dual stack listener shows up as `::ffff:a.b.c.d`
+            Some(IpAddr::V6(ip)) => ip.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(IpAddr::V6(ip)),
+            Some(ip) => ip,
+            None => return self.compiled_allow.is_none(),
+        };
+        if self.compiled_deny.iter().any(|net| net.contains(&ip)) {
+            return false;
+        }
+        match &self.compiled_allow {
+            Some(allow) => allow.iter().any(|net| net.contains(&ip)),
+            None => true,
+        }
+    }
+
+}
+
+/// parse a cidr like `10.0.0.0/8`, or a single address.
+fn parse_net(net: &str) -> Result<IpNet, Error> {
+    let net = net.trim();
+    net.parse::<IpNet>()
+        .or_else(|_| net.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| Error::ConfigValidationError(
+            format!("invalid ip address or cidr `{}`", net)
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+    use super::IpFilter;
+
+    fn filter(allow: Option<&[&str]>, deny: &[&str]) -> IpFilter {
+        let mut filter = IpFilter {
+            allow: allow.map(|allow| allow.iter().map(|net| net.to_string()).collect()),
+            deny: Some(deny.iter().map(|net| net.to_string()).collect()),
+            compiled_allow: None,
+            compiled_deny: Vec::new(),
+        };
+        filter.compile().unwrap();
+        filter
+    }
+
+    fn ip(ip: &str) -> Option<IpAddr> {
+        Some(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn allows_the_listed_networks_and_addresses() {
+        let filter = filter(Some(&["10.0.0.0/8", " 192.168.1.5 ", "fd00::/8"]), &[]);
+        assert!(filter.is_allowed(ip("10.1.2.3")));
+        assert!(filter.is_allowed(ip("192.168.1.5")));
+        assert!(filter.is_allowed(ip("fd00::1")));
+        assert!(!filter.is_allowed(ip("192.168.1.6")));
+        assert!(!filter.is_allowed(None));
+    }
+
+    #[test]
+    fn denies_over_the_allow_list() {
+        let filter = filter(Some(&["10.0.0.0/8"]), &["10.0.0.1"]);
+        assert!(!filter.is_allowed(ip("10.0.0.1")));
+        assert!(filter.is_allowed(ip("10.0.0.2")));
+    }
+
+    #[test]
+    fn allows_everyone_without_an_allow_list() {
+        let filter = filter(None, &["127.0.0.1"]);
+        assert!(filter.is_allowed(ip("8.8.8.8")));
+        assert!(filter.is_allowed(None));
+        assert!(!filter.is_allowed(ip("127.0.0.1")));
+    }
+
+    #[test]
+    fn matches_the_ipv4_mapped_addresses() {
+        let filter = filter(Some(&["127.0.0.1"]), &[]);
+        assert!(filter.is_allowed(ip("::ffff:127.0.0.1")));
+    }
+
+    #[test]
+    fn rejects_an_invalid_network() {
+        let mut filter = IpFilter { allow: Some(vec!["10.0.0.0/33".to_string()]), deny: None, compiled_allow: None, compiled_deny: Vec::new() };
+        assert!(filter.compile().is_err());
+    }
+}
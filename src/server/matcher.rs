@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use regex::Regex;
+use serde_json::Value;
+use crate::{fake_rest::server_config::{ServerDataSchema, When}, error::Error};
+use super::{request::Request, helpers, xml};
+
+#[derive(Debug, Clone)]
+pub enum ValueMatcher {
+    Any,
+    Equals(String),
+    Regex(Regex),
+}
+
+impl ValueMatcher {
+    pub fn is_match(&self, value: &str) -> bool {
+        match self {
+            ValueMatcher::Any => true,
+            ValueMatcher::Equals(expected) => value == expected,
+            ValueMatcher::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
+
+/// a required header, query string, cookie or form field of a route.
+#[derive(Debug, Clone)]
+pub struct Requirement {
+    pub name: String,
+    pub matcher: ValueMatcher,
+}
+
+impl Requirement {
+
+    /// parse `name` (just check the existence), `name<delimiter>value` (compare the value)
+    /// or `name ~ regex` (match the value against the regex).
+    pub fn parse(content: &str, delimiter: char) -> Result<Requirement, Error> {
+        let regex_position = content.find('~');
+        let is_regex = match (regex_position, content.find(delimiter)) {
+            (Some(regex_position), Some(delimiter_position)) => regex_position < delimiter_position,
+            (Some(_), None) => true,
+            _ => false,
+        };
+
+        if is_regex {
+            let (name, pattern) = helpers::get_key_value(content, '~')?;
+            let regex = Regex::new(&pattern).map_err(|e| Error::ConfigValidationError(
+                format!("invalid regex in `{}`: {}", content, e)
+            ))?;
+            return Ok(Requirement { name, matcher: ValueMatcher::Regex(regex) });
+        }
+
+        let (name, value) = helpers::get_key_optional_value(content, delimiter)?;
+        let matcher = if value.is_empty() {
+            ValueMatcher::Any
+        }else {
+            ValueMatcher::Equals(value)
+        };
+        Ok(Requirement { name, matcher })
+    }
+
+}
+
+/// check the matchers of the route which select between routes of the same path and method.
+pub fn matches(item: &ServerDataSchema, request: &Request) -> bool {
+    matches_queries(item, request)
+        && matches_all(&item.compiled_cookies, |name| request.cookies.get(name))
+        && matches_form(item, request)
+        && matches_client_cert(item, request)
+        && matches_body(item, request)
+        && matches_soap_action(item, request)
+}
+
+fn matches_soap_action(item: &ServerDataSchema, request: &Request) -> bool {
+    match &item.soap_action {
+        Some(expected) => xml::soap_action(request).as_ref() == Some(expected),
+        None => true,
+    }
+}
+
+fn matches_client_cert(item: &ServerDataSchema, request: &Request) -> bool {
+    match (&item.client_cert_subject, &request.client_cert_subject) {
+        (Some(expected), Some(subject)) => subject.contains(expected.as_str()),
+        (Some(_), None) => false,
+        (None, _) => true,
+    }
+}
+
+fn matches_form(item: &ServerDataSchema, request: &Request) -> bool {
+    if item.compiled_form.is_empty() && item.compiled_files.is_empty() {
+        return true;
+    }
+    if !matches_all(&item.compiled_form, |name| request.form().get(name)) {
+        return false;
+    }
+
+    let files: HashMap<String, String> = request.multipart()
+        .map(|multipart| multipart.files.iter().map(|file| (file.name.clone(), file.filename.clone())).collect())
+        .unwrap_or_default();
+    matches_all(&item.compiled_files, |name| files.get(name))
+}
+
+fn matches_queries(item: &ServerDataSchema, request: &Request) -> bool {
+    matches_all(&item.compiled_queries, |name| request.query_strings.get(name))
+}
+
+/// check the `when` block of a response variant.
+pub fn matches_when(when: &When, request: &Request) -> bool {
+    if !matches_all(&when.compiled_headers, |name| request.header(name)) {
+        return false;
+    }
+
+    if !matches_all(&when.compiled_queries, |name| request.query_strings.get(name)) {
+        return false;
+    }
+
+    if !matches_all(&when.compiled_cookies, |name| request.cookies.get(name)) {
+        return false;
+    }
+
+    if when.tenant.is_some() && when.tenant != request.tenant {
+        return false;
+    }
+
+    if let Some(json_paths) = &when.json_path {
+        let json = match request.json() {
+            Some(json) => json,
+            None => return false,
+        };
+        for (path, expected) in json_paths.iter() {
+            match get_json_path(json, path) {
+                Some(actual) if actual == expected => {},
+                _ => return false,
+            }
+        }
+    }
+
+    true
+}
+
+/// check the requirements against the values that `get` finds by name.
+pub fn matches_all<'a>(requirements: &[Requirement], get: impl Fn(&str) -> Option<&'a String>) -> bool {
+    requirements.iter().all(|requirement| {
+        match get(&requirement.name) {
+            Some(value) => requirement.matcher.is_match(value),
+            None => false,
+        }
+    })
+}
+
+/// get a value by a path like `user.name`, `$.user.name` or `items.0.id`.
+pub fn get_json_path<'a>(json: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.strip_prefix("$.").unwrap_or(path);
+    let mut current = json;
+    for key in path.split('.').filter(|key| !key.is_empty() && *key != "$") {
+        current = match current {
+            Value::Object(object) => object.get(key)?,
+            Value::Array(array) => array.get(key.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+/// check the `body_equals`, `body_contains` and `body_json` matchers of the route.
+fn matches_body(item: &ServerDataSchema, request: &Request) -> bool {
+    let body = String::from_utf8_lossy(&request.body);
+
+    if let Some(expected) = &item.body_equals {
+        if body.trim() != expected.trim() {
+            return false;
+        }
+    }
+
+    if let Some(expected) = &item.body_contains {
+        if !body.contains(expected.as_str()) {
+            return false;
+        }
+    }
+
+    if let Some(xpaths) = &item.body_xpath {
+        if !xml::matches_xpaths(&request.body, xpaths, item.xml_namespaces.as_ref()) {
+            return false;
+        }
+    }
+
+    if let Some(expected) = &item.body_json {
+        match request.json() {
+            Some(actual) => {
+                if !json_contains(actual, expected) {
+                    return false;
+                }
+            },
+            None => return false,
+        }
+    }
+
+    true
+}
+
+/// objects only need to have the expected fields, everything else must be equal.
+fn json_contains(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => {
+            expected.iter().all(|(key, expected_value)| {
+                match actual.get(key) {
+                    Some(actual_value) => json_contains(actual_value, expected_value),
+                    None => false,
+                }
+            })
+        },
+        _ => actual == expected,
+    }
+}
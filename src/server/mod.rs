@@ -3,4 +3,37 @@ pub mod response;
 pub mod connection;
 pub mod content_type;
 pub mod status;
-pub mod helpers;
\ No newline at end of file
+pub mod helpers;
+pub mod router;
+pub mod matcher;
+pub mod template;
+pub mod fault;
+pub mod variant;
+pub mod resource;
+pub mod proxy;
+pub mod stream;
+pub mod tls;
+pub mod compression;
+pub mod negotiation;
+pub mod range;
+pub mod cache;
+pub mod cookie;
+pub mod multipart;
+pub mod headers;
+pub mod cors;
+pub mod auth;
+pub mod oauth2;
+pub mod ip_filter;
+pub mod admin;
+pub mod throttle;
+pub mod static_dir;
+pub mod command;
+pub mod script;
+pub mod plugin;
+pub mod callback;
+pub mod sse;
+pub mod websocket;
+pub mod graphql;
+pub mod grpc;
+pub mod xml;
+pub mod dataset;
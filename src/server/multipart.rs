@@ -0,0 +1,204 @@
+use std::{collections::HashMap, path::Path};
+use serde::Serialize;
+use crate::error::Error;
+
+/// the parts of a `multipart/form-data` body.
+#[derive(Debug, Default)]
+pub struct Multipart {
+    /// the parts without a file name
+    pub fields: HashMap<String, String>,
+    pub files: Vec<FilePart>,
+}
+
+/// an uploaded file of a `multipart/form-data` body.
+#[derive(Debug, Serialize)]
+pub struct FilePart {
+    /// the name of the form field
+    pub name: String,
+    pub filename: String,
+    pub content_type: String,
+    pub size: usize,
+    #[serde(skip)]
+    pub content: Vec<u8>,
+}
+
+/// the boundary of a `multipart/form-data; boundary=...` content type.
+pub fn get_boundary(content_type: &str) -> Option<String> {
+    let mut params = content_type.split(';');
+    if !params.next()?.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    params
+        .filter_map(|param| param.trim().split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+}
+
+/// split the body on the `--boundary` lines, the parts that can't be parsed are skipped.
+pub fn parse(body: &[u8], boundary: &str) -> Multipart {
+    let mut multipart = Multipart::default();
+    let delimiter = format!("--{}", boundary).into_bytes();
+
+    for section in split(body, &delimiter).into_iter().skip(1) {
+        // the last delimiter is `--boundary--`
+        if section.starts_with(b"--") {
+            break;
+        }
+        let section = section.strip_prefix(b"\r\n").unwrap_or(section);
+        let section = section.strip_suffix(b"\r\n").unwrap_or(section);
+        let (head, content) = match find(section, b"\r\n\r\n") {
+            Some(position) => (&section[..position], &section[position + 4..]),
+            None => continue,
+        };
+
+        let head = String::from_utf8_lossy(head);
+        let mut name = None;
+        let mut filename = None;
+        let mut content_type = String::from("application/octet-stream");
+        for line in head.lines() {
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            if key.eq_ignore_ascii_case("Content-Disposition") {
+                name = get_param(value, "name");
+                filename = get_param(value, "filename");
+            }else if key.eq_ignore_ascii_case("Content-Type") {
+                content_type = value.to_string();
+            }
+        }
+
+        let name = match name {
+            Some(name) => name,
+            None => continue,
+        };
+        match filename {
+            Some(filename) => multipart.files.push(FilePart {
+                name,
+                filename,
+                content_type,
+                size: content.len(),
+                content: content.to_vec(),
+            }),
+            None => {
+                multipart.fields.insert(name, String::from_utf8_lossy(content).to_string());
+            },
+        }
+    }
+    multipart
+}
+
+/// write the uploaded files in the directory with their own file names, or the names of their fields
+/// when they don't have one. the directory parts of the names are dropped, so they can't be written
+/// anywhere else, and the files without a usable name like `..` are skipped.
+pub async fn save_files(multipart: &Multipart, dir: &str) -> Result<(), Error> {
+    if multipart.files.is_empty() {
+        return Ok(());
+    }
+    tokio::fs::create_dir_all(dir).await?;
+    for file in multipart.files.iter() {
+        let Some(file_name) = safe_file_name(&file.filename).or_else(|| safe_file_name(&file.name)) else {
+            continue;
+        };
+        tokio::fs::write(Path::new(dir).join(file_name), &file.content).await?;
+    }
+    Ok(())
+}
+
+/// the last part of a name of the client, `None` for the empty names and the ones without it like `..` and `/`.
+fn safe_file_name(name: &str) -> Option<String> {
+    Path::new(name).file_name().map(|name| name.to_string_lossy().to_string())
+}
+
+/// a `key="value"` parameter of a header value like `form-data; name="avatar"; filename="me.png"`.
+fn get_param(header_value: &str, key: &str) -> Option<String> {
+    header_value
+        .split(';')
+        .filter_map(|param| param.trim().split_once('='))
+        .find(|(param_key, _)| param_key.trim().eq_ignore_ascii_case(key))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+}
+
+fn split<'a>(content: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+    let mut sections = Vec::new();
+    let mut rest = content;
+    while let Some(position) = find(rest, delimiter) {
+        sections.push(&rest[..position]);
+        rest = &rest[position + delimiter.len()..];
+    }
+    sections.push(rest);
+    sections
+}
+
+fn find(content: &[u8], pattern: &[u8]) -> Option<usize> {
+    content.windows(pattern.len()).position(|window| window == pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_boundary, parse, save_files};
+
+    fn body(parts: &[(&str, &str)]) -> Vec<u8> {
+        let mut body = String::new();
+        for (name, filename) in parts {
+            body.push_str(&format!(
+                "--b\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\r\ncontent\r\n",
+                name, filename
+            ));
+        }
+        body.push_str("--b--\r\n");
+        body.into_bytes()
+    }
+
+    #[test]
+    fn finds_the_boundary() {
+        assert_eq!(get_boundary("multipart/form-data; boundary=abc").as_deref(), Some("abc"));
+        assert_eq!(get_boundary("Multipart/Form-Data; charset=utf-8; Boundary=\"a b\"").as_deref(), Some("a b"));
+        assert_eq!(get_boundary("multipart/mixed; boundary=abc"), None);
+        assert_eq!(get_boundary("multipart/form-data"), None);
+    }
+
+    #[test]
+    fn parses_the_fields_and_the_files() {
+        let body = b"preamble\r\n--b\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nhello\r\n\
+            --b\r\nContent-Disposition: form-data; name=\"avatar\"; filename=\"me.png\"\r\nContent-Type: image/png\r\n\r\n\x89PNG\r\n\
+            --b\r\nContent-Disposition: form-data; filename=\"nameless.txt\"\r\n\r\nskipped\r\n\
+            --b--\r\n";
+        let multipart = parse(body, "b");
+        assert_eq!(multipart.fields.len(), 1);
+        assert_eq!(multipart.fields["title"], "hello");
+        assert_eq!(multipart.files.len(), 1);
+        let file = &multipart.files[0];
+        assert_eq!((file.name.as_str(), file.filename.as_str(), file.content_type.as_str()), ("avatar", "me.png", "image/png"));
+        assert_eq!(file.content, b"\x89PNG");
+        assert_eq!(file.size, 4);
+    }
+
+    #[test]
+    fn defaults_the_content_type_of_the_files() {
+        let multipart = parse(&body(&[("doc", "a.bin")]), "b");
+        assert_eq!(multipart.files[0].content_type, "application/octet-stream");
+    }
+
+    #[tokio::test]
+    async fn saves_files_only_in_the_directory() {
+        let root = std::env::temp_dir().join(format!("fake-rest-upload-{}", std::process::id()));
+        let dir = root.join("uploads");
+        let multipart = parse(&body(&[
+            ("../../escaped", ""),
+            ("../../escaped_dots", ".."),
+            ("..", ".."),
+            ("avatar", "../me.png"),
+        ]), "b");
+        save_files(&multipart, dir.to_str().unwrap()).await.unwrap();
+
+        let mut saved: Vec<String> = std::fs::read_dir(&dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        saved.sort();
+        let outside = root.join("escaped").exists() || root.join("escaped_dots").exists() || root.join("me.png").exists();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(saved, ["escaped", "escaped_dots", "me.png"]);
+        assert!(!outside);
+    }
+}
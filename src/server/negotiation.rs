@@ -0,0 +1,127 @@
+use crate::fake_rest::server_config::{Representation, ServerDataSchema};
+use super::request::Request;
+
+/// pick the representation of the route that the `Accept` header of the request prefers
+/// and apply it to the route. it's `false` when the client accepts none of them.
+///
+/// the representations are checked in order, so the first one wins on equal quality
+/// and when the request has no `Accept` header.
+pub fn negotiate(item: &mut ServerDataSchema, request: &Request) -> bool {
+    let representations = match &item.representations {
+        Some(representations) if !representations.is_empty() => representations,
+        _ => return true,
+    };
+
+    let ranges = match request.header("Accept") {
+        Some(accept) => parse_accept(accept),
+        None => Vec::new(),
+    };
+
+    let mut best: Option<(&Representation, f32)> = None;
+    for representation in representations.iter() {
+        let quality = if ranges.is_empty() { 1.0 } else { quality_of(&representation.content_type, &ranges) };
+        if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+            best = Some((representation, quality));
+        }
+    }
+
+    match best {
+        Some((representation, _)) => {
+            let representation = representation.clone();
+            apply(item, &representation);
+            true
+        },
+        None => false,
+    }
+}
+
+/// the `Accept` media ranges like `application/json;q=0.9` as `(type, subtype, q)`.
+fn parse_accept(accept: &str) -> Vec<(String, String, f32)> {
+    accept
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let media_type = parts.next()?.trim().to_lowercase();
+            let (main_type, sub_type) = media_type.split_once('/')?;
+            let quality = parts
+                .find_map(|part| part.trim().strip_prefix("q="))
+                .and_then(|quality| quality.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some((main_type.trim().to_string(), sub_type.trim().to_string(), quality))
+        })
+        .collect()
+}
+
+/// the quality of the most specific media range matching the content type.
+fn quality_of(content_type: &str, ranges: &[(String, String, f32)]) -> f32 {
+    let media_type = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+    let (main_type, sub_type) = media_type.split_once('/').unwrap_or((media_type.as_str(), ""));
+
+    let mut best: Option<(usize, f32)> = None;
+    for (range_type, range_sub_type, quality) in ranges.iter() {
+        let specificity = if range_type == main_type && range_sub_type == sub_type {
+            2
+        }else if range_type == main_type && range_sub_type == "*" {
+            1
+        }else if range_type == "*" && range_sub_type == "*" {
+            0
+        }else {
+            continue;
+        };
+        if best.is_none_or(|(best_specificity, _)| specificity > best_specificity) {
+            best = Some((specificity, *quality));
+        }
+    }
+    best.map(|(_, quality)| quality).unwrap_or(0.0)
+}
+
+fn apply(item: &mut ServerDataSchema, representation: &Representation) {
+    if let Some(result_type) = &representation.result_type {
+        item.result_type = result_type.clone();
+    }
+    if let Some(result) = &representation.result {
+        item.result = result.clone();
+    }
+    if representation.status_code.is_some() {
+        item.status_code = representation.status_code;
+    }
+
+    let result_headers = item.result_headers.get_or_insert_with(Vec::new);
+    result_headers.push(format!("Content-Type: {}", representation.content_type));
+    result_headers.push("Vary: Accept".to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_accept, quality_of};
+
+    #[test]
+    fn parses_the_media_ranges() {
+        let ranges = parse_accept("text/HTML, application/json;q=0.9 , */*; q=0.1, invalid");
+        assert_eq!(ranges, [
+            ("text".to_string(), "html".to_string(), 1.0),
+            ("application".to_string(), "json".to_string(), 0.9),
+            ("*".to_string(), "*".to_string(), 0.1),
+        ]);
+    }
+
+    #[test]
+    fn ignores_an_invalid_quality() {
+        assert_eq!(parse_accept("text/plain;q=high"), [("text".to_string(), "plain".to_string(), 1.0)]);
+    }
+
+    #[test]
+    fn uses_the_most_specific_range() {
+        let ranges = parse_accept("*/*;q=0.1, application/*;q=0.5, application/json;q=0.8");
+        assert_eq!(quality_of("application/json; charset=utf-8", &ranges), 0.8);
+        assert_eq!(quality_of("application/xml", &ranges), 0.5);
+        assert_eq!(quality_of("text/csv", &ranges), 0.1);
+    }
+
+    #[test]
+    fn rejects_the_content_types_out_of_the_ranges() {
+        let ranges = parse_accept("application/json, text/*;q=0");
+        assert_eq!(quality_of("text/html", &ranges), 0.0);
+        assert_eq!(quality_of("image/png", &ranges), 0.0);
+    }
+}
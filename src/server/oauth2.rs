@@ -0,0 +1,140 @@
+use std::{collections::HashMap, str::FromStr, time::{SystemTime, UNIX_EPOCH}};
+use jsonwebtoken::{errors::ErrorKind, Algorithm, EncodingKey, Header};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use crate::error::Error;
+use super::{auth, request::Request, status::Status};
+
+/// an `oauth2_token` route that issues signed json web tokens.
+#[derive(Debug, Deserialize, Clone)]
+pub struct OAuth2 {
+    /// the accepted `client_id:client_secret` pairs, any client is accepted when it's not set
+    pub clients: Option<Vec<String>>,
+    /// the accepted `username:password` pairs of the `password` grant
+    #[serde(default)]
+    pub users: Vec<String>,
+    /// `HS256` (default), `HS384`, `HS512`, `RS256`, `RS384`, `RS512`, `PS256`, `PS384`, `PS512`, `ES256` or `ES384`
+    pub algorithm: Option<String>,
+    /// the secret of the `HS` algorithms, it's required for them
+    pub secret: Option<String>,
+    /// the pem file of the private key of the other algorithms
+    pub private_key: Option<String>,
+    #[serde(skip)]
+    pub private_key_pem: Vec<u8>,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    /// seconds until the tokens expire, default is 3600
+    pub expires_in: Option<u64>,
+    /// more claims of the tokens, like `{ role = "admin" }`
+    pub claims: Option<HashMap<String, Value>>,
+}
+
+impl OAuth2 {
+
+    /// read the private key and check that the tokens can be signed.
+    pub async fn load(&mut self) -> Result<(), Error> {
+        if let Some(private_key) = &self.private_key {
+            self.private_key_pem = tokio::fs::read(private_key).await.map_err(|e| Error::ConfigFileOpenError(
+                format!("can't read the oauth2 private_key `{}`: {}", private_key, e)
+            ))?;
+        }
+        if auth::is_hmac(self.algorithm.as_deref()) && self.secret.as_deref().is_none_or(str::is_empty) {
+            return Err(Error::ConfigValidationError(
+                "the oauth2 `secret` is required for the `HS` algorithms".to_string()
+            ));
+        }
+        self.encoding_key().map(|_| ()).map_err(|e| Error::ConfigValidationError(
+            format!("invalid oauth2 config: {}", e)
+        ))
+    }
+
+    fn encoding_key(&self) -> Result<(Algorithm, EncodingKey), jsonwebtoken::errors::Error> {
+        let algorithm = Algorithm::from_str(self.algorithm.as_deref().unwrap_or("HS256"))?;
+        let key = match algorithm {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                // an empty key would let anyone sign the same tokens
+                let secret = self.secret.as_deref().filter(|secret| !secret.is_empty()).ok_or(ErrorKind::InvalidKeyFormat)?;
+                EncodingKey::from_secret(secret.as_bytes())
+            },
+            Algorithm::ES256 | Algorithm::ES384 => EncodingKey::from_ec_pem(&self.private_key_pem)?,
+            Algorithm::EdDSA => EncodingKey::from_ed_pem(&self.private_key_pem)?,
+            _ => EncodingKey::from_rsa_pem(&self.private_key_pem)?,
+        };
+        Ok((algorithm, key))
+    }
+
+}
+
+/// issue a token for the `client_credentials` and `password` grants of the form body.
+///
+/// the client authenticates with basic auth or the `client_id` and `client_secret` fields.
+pub fn handle(config: &OAuth2, request: &Request) -> (Status, Vec<u8>) {
+    let form = request.form();
+    let client = match auth::basic_credentials(request) {
+        Some(credentials) => credentials,
+        None => format!(
+            "{}:{}",
+            form.get("client_id").map(String::as_str).unwrap_or(""),
+            form.get("client_secret").map(String::as_str).unwrap_or("")
+        ),
+    };
+    let client_id = client.split(':').next().unwrap_or("").to_string();
+    if config.clients.as_ref().is_some_and(|clients| !clients.contains(&client)) {
+        return error(Status::un_athorized(), "invalid_client");
+    }
+
+    let subject = match form.get("grant_type").map(String::as_str) {
+        Some("client_credentials") => client_id.clone(),
+        Some("password") => {
+            let username = form.get("username").map(String::as_str).unwrap_or("");
+            let password = form.get("password").map(String::as_str).unwrap_or("");
+            if !config.users.contains(&format!("{}:{}", username, password)) {
+                return error(Status::bad_request(), "invalid_grant");
+            }
+            username.to_string()
+        },
+        Some(_) => return error(Status::bad_request(), "unsupported_grant_type"),
+        None => return error(Status::bad_request(), "invalid_request"),
+    };
+
+    let expires_in = config.expires_in.unwrap_or(3600);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or(0);
+    let mut claims = json!({
+        "sub": subject,
+        "client_id": client_id,
+        "iat": now,
+        "exp": now + expires_in,
+    });
+    if let Some(issuer) = &config.issuer {
+        claims["iss"] = json!(issuer);
+    }
+    if let Some(audience) = &config.audience {
+        claims["aud"] = json!(audience);
+    }
+    let scope = form.get("scope");
+    if let Some(scope) = scope {
+        claims["scope"] = json!(scope);
+    }
+    for (name, value) in config.claims.iter().flatten() {
+        claims[name] = value.clone();
+    }
+
+    let token = config.encoding_key().and_then(|(algorithm, key)| jsonwebtoken::encode(&Header::new(algorithm), &claims, &key));
+    let access_token = match token {
+        Ok(token) => token,
+        Err(_) => return error(Status::internal_server_error(), "server_error"),
+    };
+    let mut body = json!({
+        "access_token": access_token,
+        "token_type": "Bearer",
+        "expires_in": expires_in,
+    });
+    if let Some(scope) = scope {
+        body["scope"] = json!(scope);
+    }
+    (Status::ok(), body.to_string().into_bytes())
+}
+
+fn error(status: Status, error: &str) -> (Status, Vec<u8>) {
+    (status, json!({ "error": error }).to_string().into_bytes())
+}
@@ -0,0 +1,83 @@
+use std::sync::OnceLock;
+use serde_json::Value;
+use wasmi::{Config, Engine, Linker, Module, Store};
+use crate::fake_rest::server_config::ServerDataSchema;
+use super::{request::Request, script::{self, ScriptOutput}, template};
+
+/// the instructions a plugin can run for one request, so an endless loop can't hang the connection.
+const MAX_FUEL: u64 = 10_000_000;
+
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        Engine::new(&config)
+    })
+}
+
+/// compile a plugin to check it before it's served.
+pub fn compile(wasm: &[u8]) -> Result<Module, String> {
+    Module::new(engine(), wasm).map_err(|e| e.to_string())
+}
+
+/// run the webassembly module of a `wasm` route, the `result` is the path of its `.wasm` (or `.wat`) file.
+///
+/// the module exports its `memory`, `alloc(len: i32) -> i32` that returns a place for the request and
+/// `handle(ptr: i32, len: i32) -> i64` that gets the request and returns the place of the response as
+/// `ptr << 32 | len`. the request is the json of the request values of the templates and the response is
+/// a json like `{"status": 201, "headers": {"X-Id": "1"}, "body": {"id": 1}}`, the body is served as json
+/// unless it's a string.
+///
+/// every request gets a new instance of the module, and the modules can't import anything, so they can't
+/// reach the files or the network. the error of a failed plugin is answered with 500.
+pub fn run(item: &ServerDataSchema, wasm: &[u8], request: &Request) -> Result<ScriptOutput, String> {
+    let failure = |e: String| format!("the plugin `{}` failed: {}", item.result, e);
+    let module = compile(wasm).map_err(failure)?;
+    let mut store = Store::new(engine(), ());
+    store.set_fuel(MAX_FUEL).map_err(|e| failure(e.to_string()))?;
+    let instance = Linker::<()>::new(engine()).instantiate_and_start(&mut store, &module).map_err(|e| failure(e.to_string()))?;
+
+    let memory = instance.get_memory(&store, "memory").ok_or_else(|| failure("it doesn't export `memory`".to_string()))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc").map_err(|e| failure(format!("`alloc`: {}", e)))?;
+    let handle = instance.get_typed_func::<(i32, i32), i64>(&store, "handle").map_err(|e| failure(format!("`handle`: {}", e)))?;
+
+    let input = template::context(request).to_string();
+    let ptr = alloc.call(&mut store, input.len() as i32).map_err(|e| failure(e.to_string()))?;
+    memory.write(&mut store, ptr as u32 as usize, input.as_bytes()).map_err(|e| failure(e.to_string()))?;
+    let result = handle.call(&mut store, (ptr, input.len() as i32)).map_err(|e| failure(e.to_string()))? as u64;
+
+    let (start, len) = ((result >> 32) as usize, (result & 0xffff_ffff) as usize);
+    let output = memory.data(&store).get(start..start + len).ok_or_else(|| failure("the response is out of the memory".to_string()))?;
+    let output: Value = serde_json::from_slice(output).map_err(|e| failure(format!("the response is not a json: {}", e)))?;
+    response(output).map_err(failure)
+}
+
+fn response(output: Value) -> Result<ScriptOutput, String> {
+    let Value::Object(mut output) = output else {
+        return Err("the response must be a json object".to_string());
+    };
+    let status = match output.remove("status") {
+        Some(status) => Some(script::http_status(status.as_i64().ok_or("`status` must be a number")?)?),
+        None => None,
+    };
+    let mut headers = Vec::new();
+    if let Some(values) = output.remove("headers") {
+        let Value::Object(values) = values else {
+            return Err("`headers` must be an object".to_string());
+        };
+        for (name, value) in values {
+            let value = match value {
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+            headers.push((name, value));
+        }
+    }
+    let (body, json) = match output.remove("body") {
+        None | Some(Value::Null) => (Vec::new(), false),
+        Some(Value::String(body)) => (body.into_bytes(), false),
+        Some(body) => (body.to_string().into_bytes(), true),
+    };
+    Ok(ScriptOutput { status, headers, body, json })
+}
@@ -0,0 +1,81 @@
+use std::sync::OnceLock;
+use crate::error::Error;
+use super::{request::{Method, Request}, response::Response, status::Status, headers::Headers};
+
+/// headers that only make sense for a single connection, they're not forwarded.
+const HOP_BY_HOP_HEADERS: [&str; 9] = [
+    "host",
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+    "te",
+    "trailer",
+    "content-length",
+];
+
+pub fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            // relay the redirects to the client instead of following them
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("failed to build the http client")
+    })
+}
+
+/// send the request to the `upstream` and return its response.
+pub async fn forward(client: &reqwest::Client, upstream: &str, request: &Request) -> Result<Response, Error> {
+    // the raw uri keeps the encoded characters of the path and the query string
+    let url = format!("{}{}", upstream.trim_end_matches('/'), request.raw_uri);
+
+    let method = reqwest::Method::from_bytes(request.method.to_string().as_bytes())
+        .map_err(|e| Error::ProxyError(e.to_string()))?;
+    let mut upstream_request = client.request(method, url).body(request.body.clone());
+    for (key, value) in request.headers.iter() {
+        let lower_key = key.to_lowercase();
+        // ask for an uncompressed body, so it can be recorded and relayed as it is
+        if HOP_BY_HOP_HEADERS.contains(&lower_key.as_str()) || lower_key == "accept-encoding" {
+            continue;
+        }
+        // the span of the request is the parent of the upstream one
+        if lower_key == "traceparent" && request.span.is_some() {
+            continue;
+        }
+        upstream_request = upstream_request.header(key, value);
+    }
+    if let Some(span) = &request.span {
+        upstream_request = upstream_request.header("traceparent", span.traceparent());
+    }
+
+    let upstream_response = upstream_request.send().await?;
+    let status = Status {
+        code: upstream_response.status().as_u16() as usize,
+        message: upstream_response.status().canonical_reason().unwrap_or("").to_string(),
+    };
+    let mut headers = Headers::new();
+    let mut cookies = Vec::new();
+    for (key, value) in upstream_response.headers().iter() {
+        if HOP_BY_HOP_HEADERS.contains(&key.as_str()) {
+            continue;
+        }
+        if let Ok(value) = value.to_str() {
+            if key == reqwest::header::SET_COOKIE {
+                cookies.push(value.to_string());
+            }else {
+                headers.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+    // the body of a `HEAD` response is empty, but its length is the one of the `GET` body
+    let head_length = match upstream_response.headers().get(reqwest::header::CONTENT_LENGTH) {
+        Some(length) if request.method == Method::HEAD => length.to_str().ok().map(|length| length.to_string()),
+        _ => None,
+    };
+    let body = upstream_response.bytes().await?.to_vec();
+    headers.insert("Content-Length".to_string(), head_length.unwrap_or_else(|| body.len().to_string()));
+
+    Ok(Response { status, headers, body, cookies, fault: None, file: None, throttle_kbps: None, events: None, websocket: None })
+}
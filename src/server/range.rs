@@ -0,0 +1,92 @@
+/// the part of a body that the `Range` header of the request asks for.
+#[derive(Debug, PartialEq)]
+pub enum ByteRange {
+    /// no range, an unsupported or malformed one, serve the whole body
+    Full,
+    /// the inclusive `start` and `end` offsets
+    Partial(usize, usize),
+    /// the range is out of the body
+    Unsatisfiable,
+}
+
+/// parse a single `bytes=start-end`, `bytes=start-` or `bytes=-suffix` range
+/// for a body of `length` bytes. multiple ranges are answered with the whole body.
+pub fn parse(header: Option<&String>, length: usize) -> ByteRange {
+    let spec = match header.and_then(|value| value.trim().strip_prefix("bytes=")) {
+        Some(spec) if !spec.contains(',') => spec.trim(),
+        _ => return ByteRange::Full,
+    };
+    let (start, end) = match spec.split_once('-') {
+        Some((start, end)) => (start.trim(), end.trim()),
+        None => return ByteRange::Full,
+    };
+
+    if start.is_empty() {
+        // the last `end` bytes
+        return match end.parse::<usize>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if length == 0 => ByteRange::Unsatisfiable,
+            Ok(suffix) => ByteRange::Partial(length.saturating_sub(suffix), length - 1),
+            Err(_) => ByteRange::Full,
+        };
+    }
+
+    let start = match start.parse::<usize>() {
+        Ok(start) => start,
+        Err(_) => return ByteRange::Full,
+    };
+    let end = if end.is_empty() {
+        length.saturating_sub(1)
+    }else {
+        match end.parse::<usize>() {
+            Ok(end) if end >= start => end.min(length.saturating_sub(1)),
+            _ => return ByteRange::Full,
+        }
+    };
+
+    if start >= length {
+        ByteRange::Unsatisfiable
+    }else {
+        ByteRange::Partial(start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, ByteRange};
+
+    fn range(header: &str, length: usize) -> ByteRange {
+        parse(Some(&header.to_string()), length)
+    }
+
+    #[test]
+    fn parses_the_start_and_the_end() {
+        assert_eq!(range("bytes=0-9", 100), ByteRange::Partial(0, 9));
+        assert_eq!(range("bytes=90-", 100), ByteRange::Partial(90, 99));
+        assert_eq!(range("bytes=90-200", 100), ByteRange::Partial(90, 99));
+    }
+
+    #[test]
+    fn parses_the_suffix() {
+        assert_eq!(range("bytes=-10", 100), ByteRange::Partial(90, 99));
+        assert_eq!(range("bytes=-500", 100), ByteRange::Partial(0, 99));
+        assert_eq!(range("bytes=-0", 100), ByteRange::Unsatisfiable);
+        assert_eq!(range("bytes=-10", 0), ByteRange::Unsatisfiable);
+    }
+
+    #[test]
+    fn rejects_the_ranges_out_of_the_body() {
+        assert_eq!(range("bytes=100-", 100), ByteRange::Unsatisfiable);
+        assert_eq!(range("bytes=0-", 0), ByteRange::Unsatisfiable);
+    }
+
+    #[test]
+    fn serves_the_whole_body_for_the_other_ranges() {
+        assert_eq!(parse(None, 100), ByteRange::Full);
+        assert_eq!(range("bytes=0-1,5-6", 100), ByteRange::Full);
+        assert_eq!(range("items=0-1", 100), ByteRange::Full);
+        assert_eq!(range("bytes=9-0", 100), ByteRange::Full);
+        assert_eq!(range("bytes=a-b", 100), ByteRange::Full);
+        assert_eq!(range("bytes=5", 100), ByteRange::Full);
+    }
+}
@@ -1,30 +1,40 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{sync::OnceLock, collections::HashMap, fmt::Display, net::IpAddr};
 use serde::Deserialize;
-use tokio::{net::TcpStream, io::AsyncReadExt};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
 
 use crate::error::Error;
-use crate::server::helpers;
+use crate::fake_rest::trace::SpanContext;
+use crate::server::{helpers, headers::Headers, multipart::{self, Multipart}};
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+#[serde(from = "String")]
 pub enum Method {
+    #[default]
     GET,
+    HEAD,
     POST,
     PUT,
     PATCH,
-    OPTION,
-    DELETE
+    OPTIONS,
+    DELETE,
+    /// the methods without a variant, like `PROPFIND`, `PURGE` or `LINK`
+    Other(String),
 }
 
 impl From<String> for Method {
     fn from(s: String) -> Self {
-        return match s.as_str() {
+        let s = s.to_uppercase();
+        match s.as_str() {
             "GET" => Method::GET,
+            "HEAD" => Method::HEAD,
             "POST" => Method::POST,
             "PUT" => Method::PUT,
             "PATCH" => Method::PATCH,
-            "OPTION" => Method::OPTION,
+            // the old configs spell it `OPTION`
+            "OPTIONS" | "OPTION" => Method::OPTIONS,
             "DELETE" => Method::DELETE,
-            _=>  Method::GET
+            _=>  Method::Other(s)
         }
     }
 }
@@ -33,50 +43,87 @@ impl Display for Method {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Method::GET => write!(f, "GET"),
+            Method::HEAD => write!(f, "HEAD"),
             Method::POST => write!(f, "POST"),
             Method::PUT => write!(f, "PUT"),
             Method::PATCH => write!(f, "PATCH"),
-            Method::OPTION => write!(f, "OPTION"),
+            Method::OPTIONS => write!(f, "OPTIONS"),
             Method::DELETE => write!(f, "DELETE"),
+            Method::Other(method) => write!(f, "{}", method),
         }
     }
 }
 
+/// the biggest parts of a request that are read, the bigger ones are answered with 414, 431 or 413.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestLimits {
+    /// the bytes of the request line
+    pub request_line: usize,
+    /// the bytes of all of the header lines
+    pub headers: usize,
+    /// the bytes of the body, it's not limited when it's `None`
+    pub body: Option<usize>,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        RequestLimits { request_line: 8 * 1024, headers: 64 * 1024, body: None }
+    }
+}
+
 #[derive(Debug)]
 pub struct Request {
     pub method: Method,
+    /// the percent-decoded path
     pub uri: String,
+    /// the path and the query string as they're sent
+    pub raw_uri: String,
     pub version: String,
-    pub headers: HashMap<String, String>,
+    pub headers: Headers,
     pub query_strings: HashMap<String, String>,
+    pub path_params: HashMap<String, String>,
+    /// the `name=value` pairs of the `Cookie` header
+    pub cookies: HashMap<String, String>,
+    pub body: Vec<u8>,
+    /// the subject of the client certificate with mutual tls
+    pub client_cert_subject: Option<String>,
+    /// the tenant of the api key of the request
+    pub tenant: Option<String>,
+    /// the address of the client
+    pub peer_ip: Option<IpAddr>,
+    /// the path (or `path_regex`) of the route that serves the request
+    pub route: Option<String>,
+    /// the span of the request when the server has `tracing`
+    pub span: Option<SpanContext>,
+    json_body: OnceLock<Option<serde_json::Value>>,
+    form_body: OnceLock<HashMap<String, String>>,
+    multipart_body: OnceLock<Option<Multipart>>,
 }
 
 impl Request {
 
-    pub async fn new(reader: &mut TcpStream) -> crate::error::RequestParseResult {
-        let mut request_info = String::new();
-        let mut headers: HashMap<String, String> = HashMap::new();
-        let mut buff: Vec<u8> = vec![];
+    pub async fn new<R: AsyncBufRead + Unpin>(reader: &mut R, limits: RequestLimits) -> crate::error::RequestParseResult {
+        if reader.fill_buf().await?.is_empty() {
+            return Err(Error::ConnectionClosed);
+        }
+        let request_info = match read_line(reader, limits.request_line).await? {
+            Some(line) => line,
+            None => return Err(Error::RequestTooLarge(414, format!("the request line is longer than {} bytes", limits.request_line))),
+        };
 
+        let mut headers = Headers::new();
+        let mut header_size = 0;
         loop {
-            let byte = reader.read_u8().await?;
-            buff.push(byte);
-            if byte as char == '\n' {
-                if request_info.is_empty() {
-                    request_info = String::from_utf8(buff[..buff.len() - 2].to_vec())?;
-                    buff.clear();
-                }else {
-                    if buff.len() == 2 && buff[0] as char == '\r' {
-                        break;
-                    }
-
-                    let header_line = String::from_utf8(buff[..buff.len() - 2].to_vec())?;
-                    buff.clear();
-                    let header = helpers::get_key_value(&header_line, ':')?;
-
-                    headers.insert(header.0, header.1);
-                }
+            let header_line = match read_line(reader, limits.headers.saturating_sub(header_size)).await? {
+                Some(line) => line,
+                None => return Err(Error::RequestTooLarge(431, format!("the headers are bigger than {} bytes", limits.headers))),
+            };
+            if header_line.is_empty() {
+                break;
             }
+            header_size += header_line.len() + 2;
+            let header = helpers::get_key_value(&header_line, ':')?;
+            headers.insert(header.0, header.1);
         }
 
         let mut request_info_iter = request_info.split(' ');
@@ -84,9 +131,10 @@ impl Request {
         let uri = request_info_iter.next().unwrap_or("").to_string();
         let version = request_info_iter.next().unwrap_or("").to_string();
         
+        let raw_uri = uri.clone();
         let mut uri_iter = uri.split('?');
         let uri = match uri_iter.next() {
-            Some(uri) => uri.to_string(),
+            Some(uri) => decode_path(uri),
             None => return Err(Error::ParsingError(
                 format!("cant get uri of the request: `{}`", uri)
             ))
@@ -95,13 +143,238 @@ impl Request {
         let mut query_strings: HashMap<String, String> = HashMap::new();
 
         if let Some(queries) = uri_iter.next() {
-            for query in queries.split('&') {
-                let result = helpers::get_key_value(query, '=')?;
-                query_strings.insert(result.0, result.1);
+            // a bare `?flag` is a query string without a value, and the empty pairs of `a=1&&b=2&` are skipped
+            for query in queries.split('&').filter(|query| !query.is_empty()) {
+                let result = helpers::get_key_optional_value(query, '=')?;
+                query_strings.insert(helpers::percent_decode(&result.0, true), helpers::percent_decode(&result.1, true));
             }
         }
 
-        Ok(Request { method, uri, version, headers, query_strings })
+        let cookies = parse_cookies(&headers);
+
+        let mut body = vec![];
+        if is_chunked(&headers) {
+            body = read_chunked_body(reader, limits).await?;
+        }else if let Some(length) = get_content_length(&headers)? {
+            check_body_size(length, limits)?;
+            body.resize(length, 0);
+            reader.read_exact(&mut body).await?;
+        }
+
+        Ok(Request {
+            method,
+            uri,
+            raw_uri,
+            version,
+            headers,
+            query_strings,
+            path_params: HashMap::new(),
+            cookies,
+            body,
+            client_cert_subject: None,
+            tenant: None,
+            peer_ip: None,
+            route: None,
+            span: None,
+            json_body: OnceLock::new(),
+            form_body: OnceLock::new(),
+            multipart_body: OnceLock::new(),
+        })
+    }
+
+    /// get the value of a header, the name is case insensitive.
+    pub fn header(&self, name: &str) -> Option<&String> {
+        self.headers.get(name)
+    }
+
+    /// the host name of the `Host` header, without the port.
+    pub fn host(&self) -> Option<&str> {
+        let host = self.header("Host")?.trim();
+        match host.strip_prefix('[') {
+            // ipv6 addresses like `[::1]:7000`
+            Some(rest) => rest.split(']').next(),
+            None => host.split(':').next(),
+        }
+    }
+
+    /// check if the client wants to send more requests on this connection.
+    pub fn keep_alive(&self) -> bool {
+        match self.header("Connection") {
+            Some(value) if value.eq_ignore_ascii_case("close") => false,
+            Some(value) if value.eq_ignore_ascii_case("keep-alive") => true,
+            // keep-alive is the default since HTTP/1.1
+            _ => self.version != "HTTP/1.0",
+        }
+    }
+
+    /// the body parsed as json, it's `None` if the body is not a valid json.
+    pub fn json(&self) -> Option<&serde_json::Value> {
+        self.json_body
+            .get_or_init(|| serde_json::from_slice(&self.body).ok())
+            .as_ref()
+    }
+
+    /// the body parsed as `application/x-www-form-urlencoded` fields like `key=value&key2=a+b%21`,
+    /// or the fields without a file of a `multipart/form-data` body.
+    pub fn form(&self) -> &HashMap<String, String> {
+        self.form_body.get_or_init(|| {
+            if let Some(multipart) = self.multipart() {
+                return multipart.fields.clone();
+            }
+
+            let mut fields = HashMap::new();
+            let content = String::from_utf8_lossy(&self.body);
+            for field in content.split('&').filter(|field| !field.is_empty()) {
+                if let Ok((key, value)) = helpers::get_key_optional_value(field, '=') {
+                    fields.insert(helpers::percent_decode(&key, true), helpers::percent_decode(&value, true));
+                }
+            }
+            fields
+        })
+    }
+
+    /// the fields and files of a `multipart/form-data` body, it's `None` for the other bodies.
+    pub fn multipart(&self) -> Option<&Multipart> {
+        self.multipart_body
+            .get_or_init(|| {
+                let boundary = multipart::get_boundary(self.header("Content-Type")?)?;
+                Some(multipart::parse(&self.body, &boundary))
+            })
+            .as_ref()
     }
 
 }
+
+/// decode the segments of the path one by one, an encoded `/` in a segment stays
+/// `%2F`, so it doesn't split the segment when the routes are matched.
+fn decode_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| helpers::percent_decode(segment, false).replace('/', "%2F"))
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+/// parse the `name=value; name2=value2` pairs of the `Cookie` header.
+fn parse_cookies(headers: &Headers) -> HashMap<String, String> {
+    let mut cookies = HashMap::new();
+    if let Some(cookie_header) = headers.get("Cookie") {
+        for pair in cookie_header.split(';') {
+            if let Some((name, value)) = pair.split_once('=') {
+                let value = value.trim().trim_matches('"');
+                cookies.insert(name.trim().to_string(), value.to_string());
+            }
+        }
+    }
+    cookies
+}
+
+fn get_content_length(headers: &Headers) -> Result<Option<usize>, Error> {
+    match headers.get("Content-Length") {
+        Some(value) => match value.parse::<usize>() {
+            Ok(length) => Ok(Some(length)),
+            Err(_) => Err(Error::ParsingError(
+                format!("invalid Content-Length: `{}`", value)
+            ))
+        },
+        None => Ok(None)
+    }
+}
+
+/// `Transfer-Encoding: chunked` wins over `Content-Length` when both of them are sent.
+fn is_chunked(headers: &Headers) -> bool {
+    match headers.get("Transfer-Encoding") {
+        Some(value) => value
+            .split(',')
+            .any(|encoding| encoding.trim().eq_ignore_ascii_case("chunked")),
+        None => false,
+    }
+}
+
+/// read the `<size in hex>\r\n<data>\r\n` chunks until the zero size one,
+/// the trailer headers after it are ignored.
+async fn read_chunked_body<R: AsyncBufRead + Unpin>(reader: &mut R, limits: RequestLimits) -> Result<Vec<u8>, Error> {
+    let mut body = vec![];
+    loop {
+        let size_line = chunk_line(reader, limits).await?;
+        // drop the chunk extensions like `;name=value`
+        let size = size_line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| Error::ParsingError(
+            format!("invalid chunk size: `{}`", size_line)
+        ))?;
+
+        if size == 0 {
+            while !chunk_line(reader, limits).await?.is_empty() {}
+            break;
+        }
+
+        let start = body.len();
+        check_body_size(start.saturating_add(size), limits)?;
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..]).await?;
+        chunk_line(reader, limits).await?;
+    }
+    Ok(body)
+}
+
+async fn chunk_line<R: AsyncBufRead + Unpin>(reader: &mut R, limits: RequestLimits) -> Result<String, Error> {
+    read_line(reader, limits.headers).await?.ok_or_else(|| Error::RequestTooLarge(
+        431,
+        format!("a line of the chunked body is longer than {} bytes", limits.headers)
+    ))
+}
+
+fn check_body_size(size: usize, limits: RequestLimits) -> Result<(), Error> {
+    match limits.body {
+        Some(limit) if size > limit => Err(Error::RequestTooLarge(413, format!("the body is bigger than {} bytes", limit))),
+        _ => Ok(()),
+    }
+}
+
+/// read a line without its `\r\n`, it's `None` when the line is longer than `limit` bytes.
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R, limit: usize) -> Result<Option<String>, Error> {
+    let mut line = vec![];
+    // a byte more than the limit tells the long lines apart
+    (&mut *reader).take(limit as u64 + 1).read_until(b'\n', &mut line).await?;
+    if line.last() != Some(&b'\n') {
+        if line.len() > limit {
+            return Ok(None);
+        }
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    line.pop();
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    Ok(Some(String::from_utf8(line)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_path;
+
+    #[test]
+    fn decodes_spaces() {
+        assert_eq!(decode_path("/users/john%20doe"), "/users/john doe");
+    }
+
+    #[test]
+    fn keeps_plus_in_paths() {
+        assert_eq!(decode_path("/search/a+b"), "/search/a+b");
+    }
+
+    #[test]
+    fn decodes_multi_byte_utf8() {
+        assert_eq!(decode_path("/caf%C3%A9/%E6%97%A5"), "/café/日");
+    }
+
+    #[test]
+    fn keeps_invalid_escapes() {
+        assert_eq!(decode_path("/a%zz/b%"), "/a%zz/b%");
+    }
+
+    #[test]
+    fn keeps_encoded_slashes_in_segments() {
+        assert_eq!(decode_path("/files/a%2Fb/c"), "/files/a%2Fb/c");
+        assert_eq!(decode_path("/files/a%2fb"), "/files/a%2Fb");
+    }
+}
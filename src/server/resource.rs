@@ -0,0 +1,70 @@
+use serde_json::{json, Value};
+use crate::fake_rest::server_config::ServerDataSchema;
+use super::{request::{Method, Request}, status::Status};
+
+pub const RESOURCE_ID: &str = "id";
+
+/// serve the crud operations of a `resource` route from its store.
+///
+/// `GET /path` lists the items, `POST /path` creates one, and `GET`, `PUT`, `PATCH`
+/// and `DELETE` on `/path/{id}` read, replace, update and delete one.
+pub fn handle(item: &ServerDataSchema, request: &Request) -> (Status, Vec<u8>) {
+    let store = &item.store;
+    let id_field = item.id_field.as_deref().unwrap_or(RESOURCE_ID);
+
+    // `HEAD` is answered like `GET`, the body is dropped when it's sent
+    let method = if request.method == Method::HEAD { &Method::GET } else { &request.method };
+    let (status, result) = match (method, request.path_params.get(RESOURCE_ID)) {
+        (Method::GET, None) => (Status::ok(), Some(Value::from(store.list()))),
+        (Method::POST, None) => match request.json() {
+            Some(body) if body.is_object() => (Status::created(), Some(store.create(id_field, body.clone()))),
+            _ => return invalid_body(),
+        },
+        (Method::GET, Some(id)) => found_or_not(store.get(id_field, id)),
+        (Method::PUT, Some(id)) => match request.json() {
+            Some(body) if body.is_object() => found_or_not(store.replace(id_field, id, body.clone())),
+            _ => return invalid_body(),
+        },
+        (Method::PATCH, Some(id)) => match request.json() {
+            Some(body) if body.is_object() => found_or_not(store.update(id_field, id, body.clone())),
+            _ => return invalid_body(),
+        },
+        (Method::DELETE, Some(id)) => match store.delete(id_field, id) {
+            Some(_) => (Status::no_content(), None),
+            None => found_or_not(None),
+        },
+        _ => (Status::method_not_allowed(), Some(json!({ "error": "Method Not Allowed" }))),
+    };
+
+    let body = match result {
+        Some(result) => result.to_string().into_bytes(),
+        None => Vec::new(),
+    };
+    (status, body)
+}
+
+/// the methods of the crud operations of the collection or the item.
+pub fn methods(is_item: bool) -> Vec<Method> {
+    if is_item {
+        vec![Method::GET, Method::PUT, Method::PATCH, Method::DELETE]
+    }else {
+        vec![Method::GET, Method::POST]
+    }
+}
+
+/// check if the method is one of the crud operations of the collection or the item.
+pub fn allows_method(method: &Method, is_item: bool) -> bool {
+    methods(is_item).contains(method)
+}
+
+fn found_or_not(item: Option<Value>) -> (Status, Option<Value>) {
+    match item {
+        Some(item) => (Status::ok(), Some(item)),
+        None => (Status::not_found(), Some(json!({ "error": "Not Found" }))),
+    }
+}
+
+fn invalid_body() -> (Status, Vec<u8>) {
+    let body = json!({ "error": "the request body must be a json object" });
+    (Status::bad_request(), body.to_string().into_bytes())
+}
@@ -1,101 +1,363 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{path::{Path, PathBuf}, sync::atomic::Ordering, time::{Duration, SystemTime}};
+use base64::Engine;
 
-use crate::{fake_rest::server_config::{Server, ServerDataSchema}, error::Error};
+use crate::{fake_rest::{server_config::Server, rate_limit::{RateLimitBy, RateLimitState}, openapi}, error::Error};
 use crate::server::status::Status;
-use super::{request::Request, content_type::ContentType, helpers};
+use super::{admin, request::{Method, Request}, content_type::ContentType, router::{self, RouteLookup}, template, fault::Fault, variant, resource, proxy, compression, negotiation, range::{self, ByteRange}, cache, multipart, headers::Headers, cors, oauth2, static_dir::{self, Listing, Target}, command, script, plugin, callback, sse::EventStream, websocket::{self, Session}, graphql, grpc, dataset};
+
+/// the `dl` files of this size or bigger are streamed from the disk instead of being read in memory.
+const STREAM_MIN_SIZE: u64 = 1024 * 1024;
 
 pub struct Response {
     pub status: Status,
-    pub headers: HashMap<String, String>,
-    pub body: Vec<u8>
+    pub headers: Headers,
+    pub body: Vec<u8>,
+    /// the `Set-Cookie` headers, they're kept apart since there can be many of them
+    pub cookies: Vec<String>,
+    pub fault: Option<Fault>,
+    /// a part of a file that is streamed after the head, instead of the `body`
+    pub file: Option<FileBody>,
+    /// write the body at this many kilobits per second
+    pub throttle_kbps: Option<u64>,
+    /// the events of an `sse` route that are sent after the head, the connection is closed after them
+    pub events: Option<EventStream>,
+    /// the websocket of an upgraded connection, it runs after the head until one of the sides closes it
+    pub websocket: Option<Session>,
+}
+
+/// `len` bytes of the file from `start`.
+#[derive(Debug, Clone)]
+pub struct FileBody {
+    pub path: PathBuf,
+    pub start: u64,
+    pub len: u64,
 }
 
 impl Response {
 
-    pub async fn new(request: &Request, server: &Server) -> Result<Response, Error> {
-        let mut headers = HashMap::new();
+    pub async fn new(request: &mut Request, server: &Server) -> Result<Response, Error> {
+        let cors = match &server.config.cors {
+            Some(cors) => cors,
+            None => return Response::build(request, server).await,
+        };
+        if let Some(response) = cors::preflight(cors, request, server) {
+            return Ok(response);
+        }
+
+        let mut response = Response::build(request, server).await?;
+        cors::apply(cors, request, &mut response);
+        Ok(response)
+    }
+
+    async fn build(request: &mut Request, server: &Server) -> Result<Response, Error> {
+        if server.config.ip_filter.as_ref().is_some_and(|filter| !filter.is_allowed(request.peer_ip)) {
+            return Ok(Response::text(Status::forbidden(), "Forbidden"))
+        }
+
+        if server.config.openapi_path.as_deref() == Some(request.uri.as_str()) && matches!(request.method, Method::GET | Method::HEAD) {
+            return Ok(Response::json(Status::ok(), &openapi::export(server)))
+        }
+
+        if server.config.metrics_path.as_deref() == Some(request.uri.as_str()) && matches!(request.method, Method::GET | Method::HEAD) {
+            request.route = Some(request.uri.clone());
+            let mut response = Response::text(Status::ok(), &server.metrics.render());
+            response.headers.insert("Content-Type".to_string(), "text/plain; version=0.0.4".to_string());
+            return Ok(response)
+        }
 
-        let mut server_data: Option<ServerDataSchema> = None;
-        for item in server.data.iter() {
-            if item.path == request.uri {
-                server_data = Some(item.clone());
-                break;
+        if let Some(admin_path) = &server.config.admin_path {
+            if let Some(response) = admin::handle(admin_path, request, server) {
+                request.route = Some(admin_path.clone());
+                return Ok(response)
             }
         }
 
-        if server_data.is_none() {
-            return Ok(Response {
-                status: Status::not_found(),
-                headers: HashMap::new(),
-                body: "Path not found".as_bytes().to_vec()
-            })
+        let api_key_header = server.config.api_key_header.as_deref().unwrap_or("X-Api-Key");
+        request.tenant = request.header(api_key_header).and_then(|key| server.api_keys.get(key)).cloned();
+
+        let mut headers = Headers::new();
+        let mut last_modified = None;
+        let mut file = None;
+        let mut events = None;
+        let mut session = None;
+        // the headers of a `script` go after the `result_headers` of the route
+        let mut script_headers = Vec::new();
+
+        let mut server_data = match router::find_route(&server.data, request) {
+            RouteLookup::Found(item, path_match) => {
+                request.path_params = path_match.params;
+                request.route = Some(item.path_regex.clone().unwrap_or_else(|| item.path.clone()));
+                item.hits.fetch_add(1, Ordering::SeqCst);
+                variant::select(item, request)
+            },
+            // answer `OPTIONS` with the methods of the path when no route handles it
+            RouteLookup::MethodNotAllowed if request.method == Method::OPTIONS => {
+                let mut response = Response::text(Status::no_content(), "");
+                response.headers.remove("Content-Length");
+                response.headers.insert("Allow".to_string(), allow_header(server, request));
+                return Ok(response)
+            },
+            _ if server.config.fallback_proxy.is_some() => {
+                let upstream = server.config.fallback_proxy.as_deref().unwrap_or_default();
+                // an upstream that can't be reached is answered like a gateway does
+                return match proxy::forward(proxy::client(), upstream, request).await {
+                    Err(Error::ProxyError(e)) => Ok(Response::text(Status::bad_gateway(), &format!("the upstream `{}` failed: {}", upstream, e))),
+                    response => response,
+                }
+            },
+            RouteLookup::NotFound => return Ok(Response::text(Status::not_found(), "Path not found")),
+            RouteLookup::NotMatched => return Ok(Response::text(Status::not_found(), "Request did not match")),
+            RouteLookup::MethodNotAllowed => {
+                let mut response = Response::text(Status::method_not_allowed(), "Method Not Allowed");
+                response.headers.insert("Allow".to_string(), allow_header(server, request));
+                return Ok(response)
+            },
+        };
+
+        if !negotiation::negotiate(&mut server_data, request) {
+            return Ok(Response::text(Status::not_acceptable(), "Not Acceptable"))
         }
-        let server_data = server_data.unwrap();
 
-        // check if method is same
-        if request.method != server_data.method {
-            return Ok(Response {
-                status: Status::method_not_allowed(),
-                headers: HashMap::new(),
-                body: "Method Not Allowed".as_bytes().to_vec()
-            })
+        if server_data.ip_filter.as_ref().is_some_and(|filter| !filter.is_allowed(request.peer_ip)) {
+            return Ok(Response::text(Status::forbidden(), "Forbidden"))
         }
 
-        // check required headers
-        if let Some(required_headers) = &server_data.headers {
-            for header in required_headers.iter() {
-                let result = helpers::get_key_optional_value(header, ':')?;
-
-                match request.headers.get(&result.0) {
-                    Some(value) => {
-                        if !result.1.is_empty() && value.as_str() != result.1 {
-                            return Err(Error::ConfigRequiredHeadersError(
-                                format!("the {} header's value is not equal.", header)
-                            ));
-                        }
-                    }
-                    None => return Err(Error::ConfigRequiredHeadersError(
-                        format!("the `{}` header is not founded in the request.", header)
-                    ))
+        if let Some(response) = server_data.auth.as_ref().and_then(|auth| auth.check(request)) {
+            return Ok(response)
+        }
+
+        let rate_limit = match &server_data.rate_limit {
+            Some(limit) => {
+                let key = match limit.per {
+                    RateLimitBy::Route => String::new(),
+                    RateLimitBy::Key => request.header(api_key_header).cloned().unwrap_or_default(),
+                    RateLimitBy::Ip => request.peer_ip.map(|ip| ip.to_string()).unwrap_or_default(),
+                };
+                let state = server_data.rate_limiter.hit(limit, &key);
+                if state.exceeded {
+                    let mut response = Response::text(Status::too_many_requests(), "Too Many Requests");
+                    response.headers.insert("Retry-After".to_string(), state.reset_secs.to_string());
+                    add_rate_limit_headers(&mut response.headers, &state);
+                    return Ok(response)
                 }
+                Some(state)
+            },
+            None => None,
+        };
+
+        // check required headers
+        for requirement in server_data.compiled_headers.iter() {
+            let failure = match request.headers.get(&requirement.name) {
+                Some(value) if requirement.matcher.is_match(value) => None,
+                Some(_) => Some(format!("the `{}` header's value is not valid.", requirement.name)),
+                None => Some(format!("the `{}` header is not founded in the request.", requirement.name)),
+            };
+            if let Some(message) = failure {
+                let message = server_data.headers_message.clone().unwrap_or(message);
+                return Ok(Response::text(Status::from(server_data.headers_status.unwrap_or(400)), &message))
             }
         }
 
-        // check required query strings
-        if let Some(required_query_strings) = &server_data.queries {
-            for query in required_query_strings.iter() {
-                if !request.query_strings.contains_key(query) {
-                    return Err(Error::ConfigRequiredQueriesError(
-                        format!("the `{}` query is not founded in the request.", query)
-                    ));
-                }
+        // validate the body against the json schema
+        if let Some(validator) = &server_data.compiled_body_schema {
+            let errors: Vec<String> = match request.json() {
+                Some(json) => validator.iter_errors(json).map(|e| e.to_string()).collect(),
+                None => vec!["the request body is not a valid json".to_string()],
+            };
+            if !errors.is_empty() {
+                let status = Status::from(server_data.body_schema_status.unwrap_or(422));
+                let body = serde_json::json!({ "errors": errors }).to_string().into_bytes();
+                headers.insert("Content-Type".to_string(), "application/json".to_string());
+                headers.insert("Content-Length".to_string(), body.len().to_string());
+                return Ok(Response { status, headers, body, cookies: Vec::new(), fault: None, file: None, throttle_kbps: None, events: None, websocket: None })
             }
         }
 
-        
+        if let (Some(upload_dir), Some(multipart)) = (&server_data.upload_dir, request.multipart()) {
+            multipart::save_files(multipart, upload_dir).await?;
+        }
+
         // get status of request
-        let status = if let Some(status) = server_data.status_code {
+        let mut status = if let Some(status) = server_data.status_code {
             Status::from(status)
         }else {
             Status::ok()
         };
 
     
+        // the tables and the arrays of the config are json, the `result_headers` can still override it
+        if server_data.result.json && matches!(server_data.result_type.as_str(), "direct" | "template") {
+            headers.insert("Content-Type".to_string(), "application/json".to_string());
+        }
+
         // get body of request
         let body: Vec<u8> = match server_data.result_type.as_str() {
-            "direct" => server_data.result.into_bytes(),
+            "direct" => server_data.result.text.into_bytes(),
+            "template" => template::render(&server_data.result, request)?.into_bytes(),
+            "xml" => {
+                let body = template::render(&server_data.result, request)?;
+                match server_data.soap {
+                    Some(soap) => {
+                        headers.insert("Content-Type".to_string(), soap.content_type().to_string());
+                        soap.wrap(&body).into_bytes()
+                    },
+                    None => {
+                        headers.insert("Content-Type".to_string(), "text/xml; charset=utf-8".to_string());
+                        body.into_bytes()
+                    },
+                }
+            },
+            "resource" => {
+                let (resource_status, body) = resource::handle(&server_data, request);
+                status = resource_status;
+                headers.insert("Content-Type".to_string(), "application/json".to_string());
+                body
+            },
+            "dataset" => {
+                let (dataset_status, body) = dataset::handle(&server_data, request);
+                status = dataset_status;
+                headers.insert("Content-Type".to_string(), "application/json".to_string());
+                body
+            },
+            "oauth2_token" => {
+                let (token_status, body) = match &server_data.oauth2 {
+                    Some(oauth2) => oauth2::handle(oauth2, request),
+                    None => (Status::internal_server_error(), Vec::new()),
+                };
+                status = token_status;
+                headers.insert("Content-Type".to_string(), "application/json".to_string());
+                headers.insert("Cache-Control".to_string(), "no-store".to_string());
+                body
+            },
+            "graphql" => {
+                let graphql = server_data.graphql.as_ref().ok_or_else(|| Error::ConfigValidationError(
+                    format!("`graphql` is required for the graphql route `{}`", server_data.path)
+                ))?;
+                let (graphql_status, body) = graphql::handle(graphql, request)?;
+                status = graphql_status;
+                headers.insert("Content-Type".to_string(), "application/json".to_string());
+                body
+            },
+            "grpc" => {
+                let grpc = server_data.grpc.as_ref().ok_or_else(|| Error::ConfigValidationError(
+                    format!("`grpc` is required for the grpc route `{}`", server_data.path)
+                ))?;
+                let (content_type, body) = grpc::handle(grpc, &server_data.result, request);
+                headers.insert("Content-Type".to_string(), content_type);
+                body
+            },
+            "command" => match command::run(&server_data, request).await? {
+                Ok(output) => output,
+                Err(failure) => return Ok(failure),
+            },
+            "script" | "wasm" => {
+                let (content, _) = read_file(Path::new(server_data.result.as_str()), server).await?;
+                let output = if server_data.result_type == "script" {
+                    script::run(&server_data, &String::from_utf8(content)?, request)
+                }else {
+                    plugin::run(&server_data, &content, request)
+                };
+                match output {
+                    Ok(output) => {
+                        if let Some(code) = output.status {
+                            status = Status::from(code);
+                        }
+                        if output.json {
+                            headers.insert("Content-Type".to_string(), "application/json".to_string());
+                        }
+                        script_headers = output.headers;
+                        output.body
+                    },
+                    Err(e) => return Ok(Response::text(Status::internal_server_error(), &e)),
+                }
+            },
+            "sse" => {
+                let sse = server_data.sse.as_ref().ok_or_else(|| Error::ConfigValidationError(
+                    format!("`sse` is required for the sse route `{}`", server_data.path)
+                ))?;
+                headers.insert("Content-Type".to_string(), "text/event-stream".to_string());
+                headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+                // the stream ends with the connection
+                headers.insert("Connection".to_string(), "close".to_string());
+                events = Some(EventStream::new(sse, request));
+                Vec::new()
+            },
+            "websocket" => {
+                let websocket = server_data.websocket.as_ref().ok_or_else(|| Error::ConfigValidationError(
+                    format!("`websocket` is required for the websocket route `{}`", server_data.path)
+                ))?;
+                let Some(accept) = websocket::accept_key(request) else {
+                    let mut response = Response::text(Status::upgrade_required(), "WebSocket upgrade required");
+                    response.headers.insert("Upgrade".to_string(), "websocket".to_string());
+                    response.headers.insert("Sec-WebSocket-Version".to_string(), "13".to_string());
+                    return Ok(response)
+                };
+                status = Status::switching_protocols();
+                headers.insert("Upgrade".to_string(), "websocket".to_string());
+                headers.insert("Connection".to_string(), "Upgrade".to_string());
+                headers.insert("Sec-WebSocket-Accept".to_string(), accept);
+                if let Some(protocol) = websocket::protocol(websocket, request) {
+                    headers.insert("Sec-WebSocket-Protocol".to_string(), protocol.to_string());
+                }
+                session = Some(Session::new(websocket, request));
+                Vec::new()
+            },
+            "base64" => {
+                headers.insert("Content-Type".to_string(), "application/octet-stream".to_string());
+                decode_base64(&server_data.result).map_err(|e| Error::ConfigValidationError(
+                    format!("the base64 result is invalid: {}", e)
+                ))?
+            },
+            "redirect" => {
+                if server_data.status_code.is_none() {
+                    status = Status::found();
+                }
+                headers.insert("Location".to_string(), server_data.result.to_string());
+                Vec::new()
+            },
             "file" => {
-                let path = PathBuf::from(&server_data.result);
+                let path = PathBuf::from(server_data.result.as_str());
+                if !path.is_file() {
+                    return Err(Error::ConfigFileOpenError(
+                        format!("The given path is invalid or not a file: {}", &server_data.result)
+                    ))
+                }
+
+                let (content, modified) = read_file(&path, server).await?;
+                last_modified = modified;
+                content
+            },
+            "static_dir" => match static_dir::resolve(&server_data, request).await {
+                Some(Target::File(path)) => {
+                    headers.insert("Content-Type".to_string(), static_dir::content_type(&path));
+                    let (content, modified) = read_file(&path, server).await?;
+                    last_modified = modified;
+                    content
+                },
+                Some(Target::Directory { path, root }) => {
+                    let listing = server_data.listing.as_ref().unwrap_or(&Listing::Html);
+                    let (content_type, content) = static_dir::list(&path, root, listing, request).await?;
+                    headers.insert("Content-Type".to_string(), content_type);
+                    content
+                },
+                None => return Ok(Response::text(Status::not_found(), "File not found")),
+            },
+            "template_file" => {
+                let path = PathBuf::from(server_data.result.as_str());
                 if !path.is_file() {
                     return Err(Error::ConfigFileOpenError(
                         format!("The given path is invalid or not a file: {}", &server_data.result)
                     ))
                 }
 
-                tokio::fs::read_to_string(path).await?.into_bytes()
+                if let Some(content_type) = template::content_type(&path) {
+                    headers.insert("Content-Type".to_string(), content_type);
+                }
+                let (content, _) = read_file(&path, server).await?;
+                template::render(&String::from_utf8(content)?, request)?.into_bytes()
             },
             "dl" => {
-                let path = PathBuf::from(&server_data.result);
+                let path = PathBuf::from(server_data.result.as_str());
                 if !path.is_file() {
                     return Err(Error::ConfigFileOpenError(
                         format!("The given path is invalid or not a file: {}", &server_data.result)
@@ -104,48 +366,173 @@ impl Response {
 
                 let file_name = path.file_name().unwrap().to_str().unwrap().to_string();
                 let mut mime_type = String::new();
-                match path.extension() {
-                    Some(ext) => 
-                        mime_type.push_str(
-                            ContentType::get_mime_type(
-                                ext.to_str().unwrap()
-                            ).as_str()
-                        ),
-                    None => {},
+                if let Some(ext) = path.extension() {
+                    mime_type.push_str(
+                        ContentType::get_mime_type(
+                            ext.to_str().unwrap()
+                        ).as_str()
+                    )
                 }
                     
                 headers.insert("Content-Type".to_string(), mime_type);
-                headers.insert("Accept-Ranges".to_string(), "None".to_string());
+                headers.insert("Accept-Ranges".to_string(), "bytes".to_string());
                 headers.insert("Content-Disposition".to_string(), format!("attachment; filename={}", file_name));
-                tokio::fs::read(path).await?
+                let metadata = tokio::fs::metadata(&path).await?;
+                let cached = server.config.file_cache.as_ref().is_some_and(|file_cache| file_cache.fits(metadata.len()));
+                // the faults write a part of the body, so it's read in memory
+                let streamed = metadata.len() >= STREAM_MIN_SIZE && !cached && server_data.fault.is_none();
+                let (content, size) = if streamed {
+                    last_modified = metadata.modified().ok();
+                    (None, metadata.len() as usize)
+                }else {
+                    let (content, modified) = read_file(&path, server).await?;
+                    last_modified = modified;
+                    let size = content.len();
+                    (Some(content), size)
+                };
+                // the part of the file from `start` until `end`, exclusive
+                let (start, end) = match range::parse(request.header("Range"), size) {
+                    ByteRange::Full => (0, size),
+                    ByteRange::Partial(start, end) => {
+                        status = Status::partial_content();
+                        headers.insert("Content-Range".to_string(), format!("bytes {}-{}/{}", start, end, size));
+                        (start, end + 1)
+                    },
+                    ByteRange::Unsatisfiable => {
+                        status = Status::range_not_satisfiable();
+                        headers.insert("Content-Range".to_string(), format!("bytes */{}", size));
+                        (0, 0)
+                    },
+                };
+                match content {
+                    Some(content) if start == 0 && end == content.len() => content,
+                    Some(content) => content[start..end].to_vec(),
+                    None => {
+                        if end > start {
+                            file = Some(FileBody { path, start: start as u64, len: (end - start) as u64 });
+                        }
+                        Vec::new()
+                    },
+                }
             },
             _ => Vec::new()
         };
 
 
         // prepare response headers
-        headers.insert("Content-Length".to_string(), body.len().to_string());
+        let mut cookies = Vec::new();
+        let content_length = file.as_ref().map(|file: &FileBody| file.len as usize).unwrap_or(body.len());
+        // the events are delimited by the end of the connection, and an upgraded connection has no body
+        if events.is_none() && session.is_none() {
+            headers.insert("Content-Length".to_string(), content_length.to_string());
+        }
         if let Some(host) = request.headers.get("Host") {
             headers.insert("Host".to_string(), host.to_string());
         }
         if let Some(data_header) = &server_data.result_headers {
             for header_item in data_header.iter() {
-                let mut item_iter = header_item.split(':');
-                let key = match item_iter.next() {
-                    Some(k) => k.trim(),
-                    None => return Err(Error::ConfigParsingError(header_item.to_string())),
-                };
-                let value = match item_iter.next() {
-                    Some(v) => v.trim(),
+                // split on the first `:` only, values like dates and urls have more of them
+                let (key, value) = match header_item.split_once(':') {
+                    Some((k, v)) => (k.trim(), v.trim()),
                     None => return Err(Error::ConfigParsingError(header_item.to_string())),
                 };
 
-                headers.insert(key.to_string(), value.to_string());
+                if key.eq_ignore_ascii_case("Set-Cookie") {
+                    cookies.push(value.to_string());
+                }else {
+                    headers.insert(key.to_string(), value.to_string());
+                }
             }
         }
+        for (name, value) in script_headers {
+            headers.insert(name, value);
+        }
+        for cookie in server_data.set_cookies.iter().flatten() {
+            cookies.push(cookie.to_header());
+        }
+        if let Some(state) = &rate_limit {
+            add_rate_limit_headers(&mut headers, state);
+        }
+
+        let mut response = Response { status, headers, body, cookies, fault: server_data.fault.clone(), file, throttle_kbps: server_data.throttle_kbps, events, websocket: session };
+        if server.config.etag || server_data.etag.is_some() || last_modified.is_some() {
+            cache::apply(&mut response, request, server_data.etag.as_deref(), server.config.etag, last_modified);
+        }
+
+        let compression = server.config.compression.as_ref();
+        if server_data.compress.unwrap_or(compression.is_some_and(|c| c.enabled)) {
+            let min_size = compression.and_then(|c| c.min_size).unwrap_or(1024);
+            compression::compress(&mut response, request, min_size)?;
+        }
+
+        // simulate a slow backend
+        let mut delay = Duration::from_millis(server_data.delay_ms.unwrap_or(0));
+        if let Some(random_delay) = &server_data.delay {
+            delay += random_delay.sample();
+        }
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        if let Some(callbacks) = &server_data.callbacks {
+            callback::fire(callbacks, request, server.config.log.as_ref()).await;
+        }
+
+        Ok(response)
+    }
+
+    /// a plain text response with the right `Content-Length`.
+    pub fn text(status: Status, body: &str) -> Response {
+        let mut headers = Headers::new();
+        headers.insert("Content-Length".to_string(), body.len().to_string());
+        Response { status, headers, body: body.as_bytes().to_vec(), cookies: Vec::new(), fault: None, file: None, throttle_kbps: None, events: None, websocket: None }
+    }
 
+    /// the bytes of the body, including the streamed file.
+    pub fn body_len(&self) -> usize {
+        self.file.as_ref().map(|file| file.len as usize).unwrap_or(self.body.len())
+    }
 
-        Ok( Response { status, headers, body } )
+    /// a pretty printed json response.
+    pub fn json(status: Status, value: &serde_json::Value) -> Response {
+        let mut response = Response::text(status, &serde_json::to_string_pretty(value).unwrap_or_default());
+        response.headers.insert("Content-Type".to_string(), "application/json".to_string());
+        response
     }
 
-}
\ No newline at end of file
+}
+
+/// the content and the modification time of a file, from the `file_cache` when the server has one.
+async fn read_file(path: &Path, server: &Server) -> Result<(Vec<u8>, Option<SystemTime>), Error> {
+    match &server.config.file_cache {
+        Some(file_cache) => {
+            let (content, modified) = file_cache.read(path).await?;
+            Ok((content.to_vec(), modified))
+        },
+        None => {
+            let modified = tokio::fs::metadata(path).await?.modified().ok();
+            Ok((tokio::fs::read(path).await?, modified))
+        },
+    }
+}
+
+/// the bytes of a `base64` result, the whitespace is skipped so it can be split over lines.
+pub fn decode_base64(result: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    let compact: String = result.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    base64::engine::general_purpose::STANDARD.decode(compact)
+}
+
+fn add_rate_limit_headers(headers: &mut Headers, state: &RateLimitState) {
+    headers.insert("X-RateLimit-Limit".to_string(), state.limit.to_string());
+    headers.insert("X-RateLimit-Remaining".to_string(), state.remaining.to_string());
+    headers.insert("X-RateLimit-Reset".to_string(), state.reset_secs.to_string());
+}
+
+/// the `Allow` header of the methods configured for the path of the request.
+fn allow_header(server: &Server, request: &Request) -> String {
+    let methods: Vec<String> = router::allowed_methods(&server.data, request)
+        .iter()
+        .map(|method| method.to_string())
+        .collect();
+    methods.join(", ")
+}
@@ -0,0 +1,210 @@
+use std::{collections::HashMap, sync::atomic::Ordering};
+use regex::Regex;
+use crate::fake_rest::server_config::ServerDataSchema;
+use super::{request::{Method, Request}, matcher, resource};
+
+pub enum RouteLookup<'a> {
+    Found(&'a ServerDataSchema, PathMatch),
+    /// the path exists but the request didn't satisfy the query or body matchers of any of its routes
+    NotMatched,
+    MethodNotAllowed,
+    NotFound,
+}
+
+/// result of matching a config path against the request uri.
+///
+/// `specificity` decides which route wins when several of them match the same uri.
+/// it compares the number of literal segments first, then path values (`{id}`),
+/// then single segment globs (`*`). subtree globs (`**`) don't count at all, so
+/// `/api/users` beats `/api/{name}`, which beats `/api/*`, which beats `/api/**`.
+/// `path_regex` routes have the lowest specificity. the routes with a `host` go before all of them.
+#[derive(Debug)]
+pub struct PathMatch {
+    pub params: HashMap<String, String>,
+    pub specificity: (usize, usize, usize),
+}
+
+/// the methods of all the routes matching the path of the request, in the order of the config.
+/// `HEAD` is added for `GET` and `OPTIONS` is always allowed.
+pub fn allowed_methods(data: &[ServerDataSchema], request: &Request) -> Vec<Method> {
+    let mut methods: Vec<Method> = Vec::new();
+    for item in data.iter() {
+        let item_methods = match match_item(item, request) {
+            Some(path_match) if item.result_type == "resource" => resource::methods(path_match.params.contains_key(resource::RESOURCE_ID)),
+            Some(_) => vec![item.method.clone()],
+            None => continue,
+        };
+        for method in item_methods {
+            if !methods.contains(&method) {
+                methods.push(method);
+            }
+        }
+    }
+
+    if methods.contains(&Method::GET) && !methods.contains(&Method::HEAD) {
+        methods.push(Method::HEAD);
+    }
+    if !methods.contains(&Method::OPTIONS) {
+        methods.push(Method::OPTIONS);
+    }
+    methods
+}
+
+/// find the route that serves the request.
+///
+/// routes with a matching path are tried from the most specific to the least specific one,
+/// keeping the order of the config file on a tie. the first one with the same method and
+/// satisfied matchers wins. `HEAD` requests are served by the `GET` routes
+/// when the path has no `HEAD` route.
+pub fn find_route<'a>(data: &'a [ServerDataSchema], request: &Request) -> RouteLookup<'a> {
+    match find_route_by_method(data, request, &request.method) {
+        RouteLookup::MethodNotAllowed if request.method == Method::HEAD => find_route_by_method(data, request, &Method::GET),
+        lookup => lookup,
+    }
+}
+
+fn find_route_by_method<'a>(data: &'a [ServerDataSchema], request: &Request, method: &Method) -> RouteLookup<'a> {
+    let mut candidates: Vec<(&ServerDataSchema, PathMatch)> = data
+        .iter()
+        .filter_map(|item| match_item(item, request).map(|path_match| (item, path_match)))
+        .collect();
+    if candidates.is_empty() {
+        return RouteLookup::NotFound;
+    }
+    // the routes of the host of the request go before the ones of any host
+    candidates.sort_by_key(|candidate| std::cmp::Reverse((candidate.0.host.is_some(), candidate.1.specificity)));
+
+    let mut method_found = false;
+    for (item, path_match) in candidates {
+        let allowed = if item.result_type == "resource" {
+            resource::allows_method(method, path_match.params.contains_key(resource::RESOURCE_ID))
+        }else {
+            item.method == *method
+        };
+        if !allowed {
+            continue;
+        }
+        method_found = true;
+        if matcher::matches(item, request) {
+            return RouteLookup::Found(item, path_match);
+        }
+    }
+
+    if method_found {
+        RouteLookup::NotMatched
+    }else {
+        RouteLookup::MethodNotAllowed
+    }
+}
+
+/// match the request against the `host` and the `path_regex` or the `path` of the route, `resource` and `dataset`
+/// routes match both of their collection and item paths, and `static_dir` routes the paths under theirs.
+fn match_item(item: &ServerDataSchema, request: &Request) -> Option<PathMatch> {
+    if item.disabled.load(Ordering::SeqCst) {
+        return None;
+    }
+    if let Some(host) = &item.host {
+        if !request.host().is_some_and(|request_host| match_host(host, request_host)) {
+            return None;
+        }
+    }
+    let uri = &request.uri;
+    match &item.compiled_path_regex {
+        Some(regex) => match_regex(regex, uri),
+        None if matches!(item.result_type.as_str(), "resource" | "dataset") => match_path(&item.path, uri)
+            .or_else(|| match_path(&format!("{}/{{{}}}", item.path, resource::RESOURCE_ID), uri)),
+        None if item.result_type == "static_dir" => match_path(&item.path, uri)
+            .or_else(|| match_path(&format!("{}/**", item.path.trim_end_matches('/')), uri)),
+        None => match_path(&item.path, uri),
+    }
+}
+
+/// match the host name of the request against a host like `api.example.test` or `*.example.test`,
+/// the wildcard matches one or more labels.
+fn match_host(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host.len() > domain.len() + 1
+            && host[host.len() - domain.len()..].eq_ignore_ascii_case(domain)
+            && host.as_bytes()[host.len() - domain.len() - 1] == b'.',
+        None => pattern.eq_ignore_ascii_case(host),
+    }
+}
+
+/// match the request uri against a config path like `/users/{id}`, `/api/*` or `/files/**`.
+pub fn match_path(pattern: &str, uri: &str) -> Option<PathMatch> {
+    let pattern_segments: Vec<&str> = pattern.split('/').collect();
+    let uri_segments: Vec<&str> = uri.split('/').collect();
+
+    let mut params = HashMap::new();
+    if !match_segments(&pattern_segments, &uri_segments, &mut params) {
+        return None;
+    }
+
+    let mut specificity = (0, 0, 0);
+    for segment in pattern_segments.iter() {
+        if get_param_name(segment).is_some() {
+            specificity.1 += 1;
+        }else if *segment == "*" {
+            specificity.2 += 1;
+        }else if *segment != "**" {
+            specificity.0 += 1;
+        }
+    }
+
+    Some(PathMatch { params, specificity })
+}
+
+/// match the request uri against a `path_regex`, named groups become path values.
+pub fn match_regex(regex: &Regex, uri: &str) -> Option<PathMatch> {
+    let captures = regex.captures(uri)?;
+    let mut params = HashMap::new();
+    for name in regex.capture_names().flatten() {
+        if let Some(value) = captures.name(name) {
+            params.insert(name.to_string(), value.as_str().to_string());
+        }
+    }
+
+    Some(PathMatch { params, specificity: (0, 0, 0) })
+}
+
+fn match_segments(pattern: &[&str], uri: &[&str], params: &mut HashMap<String, String>) -> bool {
+    let (pattern_segment, uri_segment) = match (pattern.first(), uri.first()) {
+        (None, None) => return true,
+        (Some(&"**"), _) => {
+            // `**` swallows zero or more segments
+            for skip in 0..=uri.len() {
+                let mut attempt = params.clone();
+                if match_segments(&pattern[1..], &uri[skip..], &mut attempt) {
+                    *params = attempt;
+                    return true;
+                }
+            }
+            return false;
+        },
+        (Some(p), Some(u)) => (*p, *u),
+        _ => return false,
+    };
+
+    if let Some(name) = get_param_name(pattern_segment) {
+        if uri_segment.is_empty() {
+            return false;
+        }
+        params.insert(name.to_string(), uri_segment.to_string());
+    }else if pattern_segment == "*" {
+        if uri_segment.is_empty() {
+            return false;
+        }
+    }else if pattern_segment != uri_segment {
+        return false;
+    }
+
+    match_segments(&pattern[1..], &uri[1..], params)
+}
+
+pub fn get_param_name(segment: &str) -> Option<&str> {
+    if segment.len() > 2 && segment.starts_with('{') && segment.ends_with('}') {
+        Some(&segment[1..segment.len() - 1])
+    }else {
+        None
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::OnceLock;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use crate::fake_rest::server_config::ServerDataSchema;
+use super::{request::Request, template};
+
+/// the operations a script can run for one request, so an endless loop can't hang the connection.
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+/// what a `script` or a `wasm` route answers with.
+pub struct ScriptOutput {
+    pub status: Option<usize>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// the body is a map or an array of the script
+    pub json: bool,
+}
+
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine
+    })
+}
+
+/// compile a script to check it before it's served.
+pub fn compile(source: &str) -> Result<AST, String> {
+    engine().compile(source).map_err(|e| e.to_string())
+}
+
+/// run the rhai script of a `script` route, the `result` is the path of the script.
+///
+/// the script gets the request values of the templates as `request`, like `request.path.id`,
+/// `request.header["x-request-id"]` and `request.body.json`, and `state`, a map that is kept between the requests of the route. it returns
+/// the body as a string, a map or an array (served as json), or a map like
+/// `#{ status: 201, headers: #{ "X-Id": "1" }, body: #{ id: 1 } }`.
+///
+/// the error of a failed script is answered with 500. the requests of a route run its script one at a time.
+pub fn run(item: &ServerDataSchema, source: &str, request: &Request) -> Result<ScriptOutput, String> {
+    let ast = compile(source).map_err(|e| failure(&item.result, e))?;
+    let request = rhai::serde::to_dynamic(template::context(request)).map_err(|e| failure(&item.result, e.to_string()))?;
+
+    let mut state = item.script_state.lock().unwrap();
+    let mut scope = Scope::new();
+    scope.push("request", request);
+    scope.push("state", std::mem::take(&mut *state));
+    let result = engine().eval_ast_with_scope::<Dynamic>(&mut scope, &ast);
+    // the changes of the state are kept even when the script fails after them
+    *state = scope.get_value::<Map>("state").unwrap_or_default();
+    drop(state);
+
+    let result = result.map_err(|e| failure(&item.result, e.to_string()))?;
+    output(result).map_err(|e| failure(&item.result, e))
+}
+
+fn output(result: Dynamic) -> Result<ScriptOutput, String> {
+    if !result.is_map() {
+        return body(result).map(|(body, json)| ScriptOutput { status: None, headers: Vec::new(), body, json });
+    }
+    let mut map = result.cast::<Map>();
+    // a map without any of the fields of a response is the json body itself, the bodies with one of them
+    // are returned as `#{ body: ... }`
+    if !["status", "headers", "body"].iter().any(|field| map.contains_key(*field)) {
+        return body(Dynamic::from_map(map)).map(|(body, json)| ScriptOutput { status: None, headers: Vec::new(), body, json });
+    }
+
+    let status = match map.remove("status") {
+        Some(status) => {
+            let status = status.as_int().map_err(|_| "`status` must be a number".to_string())?;
+            Some(http_status(status)?)
+        },
+        None => None,
+    };
+    let mut headers = Vec::new();
+    if let Some(values) = map.remove("headers") {
+        let values = values.try_cast::<Map>().ok_or("`headers` must be a map")?;
+        for (name, value) in values {
+            headers.push((name.to_string(), value.to_string()));
+        }
+    }
+    let (body, json) = body(map.remove("body").unwrap_or(Dynamic::UNIT))?;
+    Ok(ScriptOutput { status, headers, body, json })
+}
+
+/// the returned status, the codes out of http can't be served.
+pub fn http_status(status: i64) -> Result<usize, String> {
+    if (100..600).contains(&status) {
+        Ok(status as usize)
+    }else {
+        Err(format!("`status` must be an http status between 100 and 599, not {}", status))
+    }
+}
+
+/// the bytes of a returned body and whether it's json.
+fn body(value: Dynamic) -> Result<(Vec<u8>, bool), String> {
+    if value.is_unit() {
+        return Ok((Vec::new(), false));
+    }
+    if value.is_string() {
+        return Ok((value.to_string().into_bytes(), false));
+    }
+    let json: serde_json::Value = rhai::serde::from_dynamic(&value).map_err(|e| e.to_string())?;
+    Ok((json.to_string().into_bytes(), true))
+}
+
+fn failure(path: &str, error: String) -> String {
+    format!("the script `{}` failed: {}", path, error)
+}
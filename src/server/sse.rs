@@ -0,0 +1,86 @@
+use std::time::Duration;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use crate::error::Error;
+use super::{request::Request, template};
+
+/// the milliseconds between the events when `interval_ms` is not set.
+const DEFAULT_INTERVAL: u64 = 1000;
+
+/// the events of an `sse` route, they're sent one by one on an open `text/event-stream` response.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Sse {
+    pub events: Vec<SseEvent>,
+    /// the milliseconds between the events, default is 1000
+    pub interval_ms: Option<u64>,
+    /// how many times the `events` are sent, forever when it's 0, default is 1. the connection is closed at the end
+    pub repeat: Option<u64>,
+    /// the milliseconds the clients wait to reconnect, sent as `retry:` first
+    pub retry_ms: Option<u64>,
+}
+
+/// an event, its `data` and `id` are templates of the request with `{{event.index}}`, the number of the event.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    #[serde(default)]
+    pub data: String,
+    pub id: Option<String>,
+    /// wait this many milliseconds before the event instead of `interval_ms`
+    pub delay_ms: Option<u64>,
+}
+
+/// the events of a response and the request values of their templates.
+pub struct EventStream {
+    sse: Sse,
+    context: Value,
+    index: u64,
+}
+
+impl EventStream {
+
+    pub fn new(sse: &Sse, request: &Request) -> EventStream {
+        EventStream { sse: sse.clone(), context: template::context(request), index: 0 }
+    }
+
+    /// the `retry:` line that goes before the events.
+    pub fn head(&self) -> Option<String> {
+        self.sse.retry_ms.map(|retry| format!("retry: {}\n\n", retry))
+    }
+
+    /// the wait before the next event and the event itself, `None` after the last one.
+    pub fn next_event(&mut self) -> Option<Result<(Duration, String), Error>> {
+        let count = self.sse.events.len() as u64;
+        let repeat = self.sse.repeat.unwrap_or(1);
+        if count == 0 || (repeat != 0 && self.index >= count * repeat) {
+            return None;
+        }
+        let event = &self.sse.events[(self.index % count) as usize];
+        let delay = match event.delay_ms {
+            Some(delay) => delay,
+            None if self.index == 0 => 0,
+            None => self.sse.interval_ms.unwrap_or(DEFAULT_INTERVAL),
+        };
+
+        self.context["event"] = json!({ "index": self.index });
+        self.index += 1;
+        Some(self.frame(event).map(|frame| (Duration::from_millis(delay), frame)))
+    }
+
+    fn frame(&self, event: &SseEvent) -> Result<String, Error> {
+        let mut frame = String::new();
+        if let Some(id) = &event.id {
+            frame.push_str(&format!("id: {}\n", template::render_context(id, &self.context)?));
+        }
+        if let Some(name) = &event.event {
+            frame.push_str(&format!("event: {}\n", name));
+        }
+        // every line of the data is a `data:` line
+        for line in template::render_context(&event.data, &self.context)?.lines() {
+            frame.push_str(&format!("data: {}\n", line));
+        }
+        frame.push('\n');
+        Ok(frame)
+    }
+
+}
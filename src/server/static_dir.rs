@@ -0,0 +1,119 @@
+use std::{fmt::Write, path::{Path, PathBuf}};
+use serde::Deserialize;
+use serde_json::json;
+use crate::{error::Error, fake_rest::server_config::ServerDataSchema};
+use super::{content_type::ContentType, helpers, request::Request};
+
+/// how a directory without an `index.html` is listed.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Listing {
+    Html,
+    Json,
+}
+
+/// what a path under a `static_dir` route is served with.
+pub enum Target {
+    File(PathBuf),
+    /// a directory to list, `root` is the directory of the route itself
+    Directory { path: PathBuf, root: bool },
+}
+
+/// the file of the request under the `result` directory of a `static_dir` route, `None` when it doesn't
+/// exist or it's out of the directory.
+///
+/// `/assets/js/app.js` of the `/assets` route is `{result}/js/app.js`, and a directory is served by its `index.html`,
+/// or it's listed with `listing` when it doesn't have one. the `spa` routes serve the `index.html` of the directory
+/// instead of a missing file.
+pub async fn resolve(item: &ServerDataSchema, request: &Request) -> Option<Target> {
+    let root = tokio::fs::canonicalize(item.result.as_str()).await.ok()?;
+    match find(&root, item, request).await {
+        Some(target) => Some(target),
+        None if item.spa => Some(root.join("index.html")).filter(|index| index.is_file()).map(Target::File),
+        None => None,
+    }
+}
+
+async fn find(root: &Path, item: &ServerDataSchema, request: &Request) -> Option<Target> {
+    let mut path = root.to_path_buf();
+    let prefix_len = item.path.trim_end_matches('/').split('/').count();
+    for segment in request.uri.split('/').skip(prefix_len) {
+        match segment {
+            "" | "." => continue,
+            ".." => return None,
+            // the separators and the drive letters of windows
+            _ if segment.contains(['\\', ':', '\0']) => return None,
+            _ => path.push(segment),
+        }
+    }
+
+    // the symlinks can point out of the directory too
+    let path = tokio::fs::canonicalize(&path).await.ok()?;
+    if !path.starts_with(root) {
+        return None;
+    }
+    if path.is_dir() && !path.join("index.html").is_file() && item.listing.is_some() {
+        let root = path == root;
+        return Some(Target::Directory { path, root });
+    }
+    let path = if path.is_dir() { path.join("index.html") } else { path };
+    path.is_file().then_some(Target::File(path))
+}
+
+/// the listing of a directory and its content type, the directories go first and then the files by name.
+pub async fn list(path: &Path, root: bool, listing: &Listing, request: &Request) -> Result<(String, Vec<u8>), Error> {
+    let mut entries = Vec::new();
+    let mut dir = tokio::fs::read_dir(path).await?;
+    while let Some(entry) = dir.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        entries.push((entry.file_name().to_string_lossy().to_string(), metadata));
+    }
+    entries.sort_by(|(a, a_metadata), (b, b_metadata)| b_metadata.is_dir().cmp(&a_metadata.is_dir()).then(a.cmp(b)));
+
+    let base = format!("{}/", request.uri.trim_end_matches('/'));
+    match listing {
+        Listing::Json => {
+            let items: Vec<serde_json::Value> = entries.iter().map(|(name, metadata)| json!({
+                "name": name,
+                "type": if metadata.is_dir() { "directory" } else { "file" },
+                "size": if metadata.is_dir() { None } else { Some(metadata.len()) },
+                "modified": metadata.modified().ok().map(httpdate::fmt_http_date),
+            })).collect();
+            let body = serde_json::to_string_pretty(&items).unwrap_or_default();
+            Ok(("application/json".to_string(), body.into_bytes()))
+        },
+        Listing::Html => {
+            let title = format!("Index of {}", escape(&base));
+            let mut body = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n<h1>{}</h1>\n<ul>\n", title, title);
+            if !root {
+                // the links are absolute, the uri of the directory may not end with a `/`
+                let parent = base.trim_end_matches('/').rsplit_once('/').map(|(parent, _)| parent).unwrap_or_default();
+                let _ = writeln!(body, "<li><a href=\"{}/\">../</a></li>", escape(parent));
+            }
+            for (name, metadata) in entries.iter() {
+                let slash = if metadata.is_dir() { "/" } else { "" };
+                let _ = writeln!(
+                    body,
+                    "<li><a href=\"{}{}{}\">{}{}</a></li>",
+                    escape(&base),
+                    escape(&helpers::percent_encode(name)),
+                    slash,
+                    escape(name),
+                    slash
+                );
+            }
+            body.push_str("</ul>\n</body>\n</html>\n");
+            Ok(("text/html; charset=utf-8".to_string(), body.into_bytes()))
+        },
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// the mime type of the file by its extension.
+pub fn content_type(path: &Path) -> String {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    ContentType::get_mime_type(&extension.to_ascii_lowercase())
+}
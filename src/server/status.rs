@@ -4,6 +4,10 @@ pub struct Status {
 }
 
 impl Status {
+    pub fn switching_protocols() -> Self {
+        Status { code: 101, message: String::from("Switching Protocols") }
+    }
+
     pub fn ok() -> Self {
         Status { code: 200, message: String::from("OK") }
     }
@@ -12,6 +16,38 @@ impl Status {
         Status { code: 201, message: String::from("Created") }
     }
 
+    pub fn no_content() -> Self {
+        Status { code: 204, message: String::from("No Content") }
+    }
+
+    pub fn partial_content() -> Self {
+        Status { code: 206, message: String::from("Partial Content") }
+    }
+
+    pub fn moved_permanently() -> Self {
+        Status { code: 301, message: String::from("Moved Permanently") }
+    }
+
+    pub fn found() -> Self {
+        Status { code: 302, message: String::from("Found") }
+    }
+
+    pub fn see_other() -> Self {
+        Status { code: 303, message: String::from("See Other") }
+    }
+
+    pub fn not_modified() -> Self {
+        Status { code: 304, message: String::from("Not Modified") }
+    }
+
+    pub fn temporary_redirect() -> Self {
+        Status { code: 307, message: String::from("Temporary Redirect") }
+    }
+
+    pub fn permanent_redirect() -> Self {
+        Status { code: 308, message: String::from("Permanent Redirect") }
+    }
+
     pub fn bad_request() -> Self {
         Status { code: 400, message: String::from("Bad Request") }
     }
@@ -40,18 +76,73 @@ impl Status {
         Status { code: 406, message: String::from("Not Acceptable") }
     }
 
+    pub fn request_timeout() -> Self {
+        Status { code: 408, message: String::from("Request Timeout") }
+    }
+
+    pub fn payload_too_large() -> Self {
+        Status { code: 413, message: String::from("Payload Too Large") }
+    }
+
+    pub fn uri_too_long() -> Self {
+        Status { code: 414, message: String::from("URI Too Long") }
+    }
+
+    pub fn expectation_failed() -> Self {
+        Status { code: 417, message: String::from("Expectation Failed") }
+    }
+
+    pub fn range_not_satisfiable() -> Self {
+        Status { code: 416, message: String::from("Range Not Satisfiable") }
+    }
+
     pub fn un_processable_entity() -> Self {
         Status { code: 422, message: String::from("Unprocessable Entity") }
     }
 
+    pub fn upgrade_required() -> Self {
+        Status { code: 426, message: String::from("Upgrade Required") }
+    }
+
+    pub fn too_many_requests() -> Self {
+        Status { code: 429, message: String::from("Too Many Requests") }
+    }
+
+    pub fn request_header_fields_too_large() -> Self {
+        Status { code: 431, message: String::from("Request Header Fields Too Large") }
+    }
+
     pub fn internal_server_error() -> Self {
         Status { code: 500, message: String::from("Internal Server Error") }
     }
 
+    pub fn bad_gateway() -> Self {
+        Status { code: 502, message: String::from("Bad Gateway") }
+    }
+
+    pub fn service_unavailable() -> Self {
+        Status { code: 503, message: String::from("Service Unavailable") }
+    }
+
+    pub fn gateway_timeout() -> Self {
+        Status { code: 504, message: String::from("Gateway Timeout") }
+    }
+
+    /// the status of the code, the codes without a constructor keep their code with their standard
+    /// reason phrase, or the one of their class like `Client Error`.
     pub fn from(status: usize) -> Self {
         match status {
+            101 => Status::switching_protocols(),
             200 => Status::ok(),
             201 => Status::created(),
+            204 => Status::no_content(),
+            206 => Status::partial_content(),
+            301 => Status::moved_permanently(),
+            302 => Status::found(),
+            303 => Status::see_other(),
+            304 => Status::not_modified(),
+            307 => Status::temporary_redirect(),
+            308 => Status::permanent_redirect(),
             400 => Status::bad_request(),
             401 => Status::un_athorized(),
             402 => Status::payment_required(),
@@ -59,9 +150,68 @@ impl Status {
             404 => Status::not_found(),
             405 => Status::method_not_allowed(),
             406 => Status::not_acceptable(),
+            408 => Status::request_timeout(),
+            413 => Status::payload_too_large(),
+            414 => Status::uri_too_long(),
+            416 => Status::range_not_satisfiable(),
+            417 => Status::expectation_failed(),
             422 => Status::un_processable_entity(),
+            426 => Status::upgrade_required(),
+            429 => Status::too_many_requests(),
+            431 => Status::request_header_fields_too_large(),
             500 => Status::internal_server_error(),
-            _ => Status::ok()
+            502 => Status::bad_gateway(),
+            503 => Status::service_unavailable(),
+            504 => Status::gateway_timeout(),
+            _ => Status { code: status, message: String::from(reason(status).unwrap_or_else(|| class(status))) }
         }
     }
+}
+
+fn reason(status: usize) -> Option<&'static str> {
+    let reason = match status {
+        100 => "Continue",
+        102 => "Processing",
+        103 => "Early Hints",
+        202 => "Accepted",
+        203 => "Non-Authoritative Information",
+        205 => "Reset Content",
+        207 => "Multi-Status",
+        208 => "Already Reported",
+        226 => "IM Used",
+        300 => "Multiple Choices",
+        305 => "Use Proxy",
+        407 => "Proxy Authentication Required",
+        409 => "Conflict",
+        410 => "Gone",
+        411 => "Length Required",
+        412 => "Precondition Failed",
+        415 => "Unsupported Media Type",
+        418 => "I'm a teapot",
+        421 => "Misdirected Request",
+        423 => "Locked",
+        424 => "Failed Dependency",
+        425 => "Too Early",
+        428 => "Precondition Required",
+        451 => "Unavailable For Legal Reasons",
+        501 => "Not Implemented",
+        505 => "HTTP Version Not Supported",
+        506 => "Variant Also Negotiates",
+        507 => "Insufficient Storage",
+        508 => "Loop Detected",
+        510 => "Not Extended",
+        511 => "Network Authentication Required",
+        _ => return None,
+    };
+    Some(reason)
+}
+
+fn class(status: usize) -> &'static str {
+    match status {
+        100..=199 => "Informational",
+        200..=299 => "Success",
+        300..=399 => "Redirection",
+        400..=499 => "Client Error",
+        _ => "Server Error",
+    }
 }
\ No newline at end of file
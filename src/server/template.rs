@@ -0,0 +1,134 @@
+use std::{collections::HashMap, path::Path, sync::OnceLock};
+use fake::{Fake, faker::{
+    address::en::{CityName, CountryName, StreetName},
+    company::en::CompanyName,
+    internet::en::{SafeEmail, Username},
+    lorem::en::{Paragraph, Sentence, Word},
+    name::en::{FirstName, LastName, Name},
+    phone_number::en::PhoneNumber,
+}};
+use handlebars::{handlebars_helper, Handlebars};
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use crate::error::Error;
+use super::{request::Request, multipart::FilePart, content_type::ContentType};
+
+fn handlebars() -> &'static Handlebars<'static> {
+    static HANDLEBARS: OnceLock<Handlebars> = OnceLock::new();
+    HANDLEBARS.get_or_init(|| {
+        let mut handlebars = Handlebars::new();
+        // responses are mostly json, so don't escape them like html
+        handlebars.register_escape_fn(handlebars::no_escape);
+        register_fake_helpers(&mut handlebars);
+        handlebars
+    })
+}
+
+handlebars_helper!(fake_name: | | Name().fake::<String>());
+handlebars_helper!(fake_first_name: | | FirstName().fake::<String>());
+handlebars_helper!(fake_last_name: | | LastName().fake::<String>());
+handlebars_helper!(fake_username: | | Username().fake::<String>());
+handlebars_helper!(fake_email: | | SafeEmail().fake::<String>());
+handlebars_helper!(fake_phone: | | PhoneNumber().fake::<String>());
+handlebars_helper!(fake_company: | | CompanyName().fake::<String>());
+handlebars_helper!(fake_city: | | CityName().fake::<String>());
+handlebars_helper!(fake_country: | | CountryName().fake::<String>());
+handlebars_helper!(fake_street: | | StreetName().fake::<String>());
+handlebars_helper!(fake_word: | | Word().fake::<String>());
+handlebars_helper!(fake_sentence: | | Sentence(4..10).fake::<String>());
+handlebars_helper!(fake_paragraph: | | Paragraph(3..6).fake::<String>());
+handlebars_helper!(fake_uuid: | | uuid::Uuid::new_v4().to_string());
+handlebars_helper!(fake_bool: | | (0..2).fake::<u8>() == 1);
+handlebars_helper!(fake_int: |min: i64, max: i64| (min..=max.max(min)).fake::<i64>());
+handlebars_helper!(fake_float: |min: f64, max: f64| {
+    if max > min { (min..max).fake::<f64>() } else { min }
+});
+
+fn register_fake_helpers(handlebars: &mut Handlebars) {
+    handlebars.register_helper("fake_name", Box::new(fake_name));
+    handlebars.register_helper("fake_first_name", Box::new(fake_first_name));
+    handlebars.register_helper("fake_last_name", Box::new(fake_last_name));
+    handlebars.register_helper("fake_username", Box::new(fake_username));
+    handlebars.register_helper("fake_email", Box::new(fake_email));
+    handlebars.register_helper("fake_phone", Box::new(fake_phone));
+    handlebars.register_helper("fake_company", Box::new(fake_company));
+    handlebars.register_helper("fake_city", Box::new(fake_city));
+    handlebars.register_helper("fake_country", Box::new(fake_country));
+    handlebars.register_helper("fake_street", Box::new(fake_street));
+    handlebars.register_helper("fake_word", Box::new(fake_word));
+    handlebars.register_helper("fake_sentence", Box::new(fake_sentence));
+    handlebars.register_helper("fake_paragraph", Box::new(fake_paragraph));
+    handlebars.register_helper("fake_uuid", Box::new(fake_uuid));
+    handlebars.register_helper("fake_bool", Box::new(fake_bool));
+    handlebars.register_helper("fake_int", Box::new(fake_int));
+    handlebars.register_helper("fake_float", Box::new(fake_float));
+}
+
+/// handlebars doesn't accept arguments after a dotted name like `{{fake.int 1 100}}`,
+/// so the `fake.` prefix is turned into the `fake_` helpers before rendering.
+fn rewrite_fake_helpers(template: &str) -> std::borrow::Cow<'_, str> {
+    static FAKE_PREFIX: OnceLock<Regex> = OnceLock::new();
+    FAKE_PREFIX
+        .get_or_init(|| Regex::new(r"\{\{(~?\s*)fake\.").unwrap())
+        .replace_all(template, "{{${1}fake_")
+}
+
+/// render the template with the request values like `{{path.id}}`, `{{query.name}}`,
+/// `{{header.X-Request-Id}}`, `{{cookie.session}}`, `{{body.json.email}}`, `{{body.form.username}}`,
+/// `{{body.files.avatar.filename}}`, `{{body.text}}` and `{{tenant}}`,
+/// plus fake data like `{{fake.name}}`, `{{fake.email}}`, `{{fake.uuid}}` and `{{fake.int 1 100}}`.
+pub fn render(template: &str, request: &Request) -> Result<String, Error> {
+    render_context(template, &context(request))
+}
+
+/// render the template with the request values of `context`, the ones of an event stream are reused for its events.
+pub fn render_context(template: &str, context: &Value) -> Result<String, Error> {
+    let template = rewrite_fake_helpers(template);
+    Ok(handlebars().render_template(&template, context)?)
+}
+
+/// the content type of a `template_file` by the extension before its `.hbs`, like `users.json.hbs`,
+/// `None` when it's not known.
+pub fn content_type(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let name = name.strip_suffix(".hbs").or_else(|| name.strip_suffix(".handlebars")).unwrap_or(name);
+    let extension = Path::new(name).extension()?.to_str()?.to_ascii_lowercase();
+    match ContentType::from(extension.as_str()) {
+        ContentType::OTHER => None,
+        content_type => Some(content_type.into()),
+    }
+}
+
+/// the request values of the templates, the `command` results get them on their stdin too.
+pub fn context(request: &Request) -> Value {
+    let files: HashMap<&str, &FilePart> = request.multipart()
+        .map(|multipart| multipart.files.iter().map(|file| (file.name.as_str(), file)).collect())
+        .unwrap_or_default();
+    json!({
+        "method": request.method.to_string(),
+        "uri": request.uri,
+        "path": request.path_params,
+        "query": request.query_strings,
+        "header": headers(request),
+        "cookie": request.cookies,
+        "client_cert": { "subject": request.client_cert_subject },
+        "tenant": request.tenant,
+        "body": {
+            "text": String::from_utf8_lossy(&request.body),
+            "json": request.json(),
+            "form": request.form(),
+            "files": files,
+        },
+    })
+}
+
+/// the headers by the names they're sent with and by their lowercase names, `{{header.x-request-id}}`
+/// finds the header in any case the client sends it.
+fn headers(request: &Request) -> Map<String, Value> {
+    let mut headers = Map::new();
+    for (name, value) in request.headers.iter() {
+        headers.insert(name.to_ascii_lowercase(), Value::from(value.as_str()));
+        headers.insert(name.clone(), Value::from(value.as_str()));
+    }
+    headers
+}
@@ -0,0 +1,30 @@
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// the pace of a body that is written at `throttle_kbps`, the bytes are written in slices of a tenth of a second.
+pub struct Throttle {
+    started: Instant,
+    bytes_per_second: u64,
+    sent: u64,
+}
+
+impl Throttle {
+
+    /// `kbps` is in kilobits per second, `800` is 100 KB/s.
+    pub fn new(kbps: u64) -> Throttle {
+        Throttle { started: Instant::now(), bytes_per_second: (kbps * 1000 / 8).max(1), sent: 0 }
+    }
+
+    /// the bytes of a slice.
+    pub fn chunk_size(&self) -> usize {
+        (self.bytes_per_second / 10).max(1) as usize
+    }
+
+    /// wait until the next `bytes` are due, the whole body takes its size divided by the rate.
+    pub async fn wait(&mut self, bytes: usize) {
+        self.sent += bytes as u64;
+        let due = Duration::from_secs_f64(self.sent as f64 / self.bytes_per_second as f64);
+        tokio::time::sleep_until(self.started + due).await;
+    }
+
+}
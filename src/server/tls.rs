@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use rustls_pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+use tokio_rustls::{rustls::{server::WebPkiClientVerifier, RootCertStore, ServerConfig}, TlsAcceptor};
+use crate::{error::Error, fake_rest::server_config::{ClientAuth, TlsConfig}};
+
+/// build the tls acceptor from the pem `cert` and `key` files of the config.
+pub fn load_acceptor(config: &TlsConfig) -> Result<TlsAcceptor, Error> {
+    let certs = CertificateDer::pem_file_iter(&config.cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| Error::TlsError(format!("can't read the certificates of `{}`: {}", config.cert, e)))?;
+    let key = PrivateKeyDer::from_pem_file(&config.key)
+        .map_err(|e| Error::TlsError(format!("can't read the private key of `{}`: {}", config.key, e)))?;
+
+    let builder = ServerConfig::builder();
+    let builder = match &config.client_ca {
+        Some(client_ca) => {
+            let mut roots = RootCertStore::empty();
+            let ca_certs = CertificateDer::pem_file_iter(client_ca)
+                .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| Error::TlsError(format!("can't read the client ca of `{}`: {}", client_ca, e)))?;
+            for ca_cert in ca_certs {
+                roots.add(ca_cert).map_err(|e| Error::TlsError(e.to_string()))?;
+            }
+
+            let mut verifier = WebPkiClientVerifier::builder(Arc::new(roots));
+            if config.client_auth == ClientAuth::Optional {
+                verifier = verifier.allow_unauthenticated();
+            }
+            let verifier = verifier.build().map_err(|e| Error::TlsError(e.to_string()))?;
+            builder.with_client_cert_verifier(verifier)
+        },
+        None => builder.with_no_client_auth(),
+    };
+
+    let server_config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| Error::TlsError(e.to_string()))?;
+
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+/// the subject of the client certificate like `CN=alice, O=Example`.
+pub fn get_peer_subject(certificate: &CertificateDer) -> Option<String> {
+    let (_, certificate) = x509_parser::parse_x509_certificate(certificate.as_ref()).ok()?;
+    Some(certificate.subject().to_string())
+}
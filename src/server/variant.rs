@@ -0,0 +1,81 @@
+use std::sync::atomic::Ordering;
+use rand::Rng;
+use crate::fake_rest::server_config::{ResponseVariant, Sequence, ServerDataSchema};
+use super::{request::Request, matcher};
+
+/// pick the response of the route and return the route with the picked response applied.
+///
+/// responses with a `when` block are checked in order and the first matching one wins,
+/// otherwise one of the responses without `when` is picked based on the `sequence`.
+pub fn select(item: &ServerDataSchema, request: &Request) -> ServerDataSchema {
+    let mut selected = item.clone();
+    let responses = match &item.responses {
+        Some(responses) if !responses.is_empty() => responses,
+        _ => return selected,
+    };
+
+    let conditional = responses
+        .iter()
+        .filter_map(|response| response.when.as_ref().map(|when| (response, when)))
+        .find(|(_, when)| matcher::matches_when(when, request));
+    if let Some((response, _)) = conditional {
+        apply(&mut selected, response);
+        return selected;
+    }
+
+    let responses: Vec<ResponseVariant> = responses
+        .iter()
+        .filter(|response| response.when.is_none())
+        .cloned()
+        .collect();
+    if responses.is_empty() {
+        return selected;
+    }
+
+    let call = item.calls.fetch_add(1, Ordering::SeqCst);
+    let index = match item.sequence {
+        Sequence::Stick => call.min(responses.len() - 1),
+        Sequence::Repeat => call % responses.len(),
+        Sequence::Random => pick_weighted(&responses),
+    };
+
+    apply(&mut selected, &responses[index]);
+    selected
+}
+
+fn pick_weighted(responses: &[ResponseVariant]) -> usize {
+    let total: u64 = responses.iter().map(|response| response.weight.unwrap_or(1) as u64).sum();
+    let mut point = rand::rng().random_range(0..total.max(1));
+    for (index, response) in responses.iter().enumerate() {
+        let weight = response.weight.unwrap_or(1) as u64;
+        if point < weight {
+            return index;
+        }
+        point -= weight;
+    }
+    responses.len() - 1
+}
+
+fn apply(item: &mut ServerDataSchema, variant: &ResponseVariant) {
+    if let Some(result_type) = &variant.result_type {
+        item.result_type = result_type.clone();
+    }
+    if let Some(result) = &variant.result {
+        item.result = result.clone();
+    }
+    if variant.result_headers.is_some() {
+        item.result_headers = variant.result_headers.clone();
+    }
+    if variant.set_cookies.is_some() {
+        item.set_cookies = variant.set_cookies.clone();
+    }
+    if variant.status_code.is_some() {
+        item.status_code = variant.status_code;
+    }
+    if variant.delay_ms.is_some() {
+        item.delay_ms = variant.delay_ms;
+    }
+    if variant.fault.is_some() {
+        item.fault = variant.fault.clone();
+    }
+}
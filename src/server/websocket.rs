@@ -0,0 +1,314 @@
+use std::time::Duration;
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::{io::AsyncReadExt, time::Instant};
+use crate::error::Error;
+use super::{connection::Connection, request::{Method, Request}, response, template};
+
+/// the magic of the `Sec-WebSocket-Accept` hash.
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// the largest message of a client, the larger ones are closed with 1009.
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+/// the time the client has to answer a `close` of the server.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xa;
+
+/// what a `websocket` route does after the handshake.
+///
+/// the `messages` are sent in order, each one after its `delay_ms`, and then the connection is closed with
+/// `close`. the messages of the client are sent back with `echo`, and the connection stays open until the
+/// client closes it when there's no `close`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebSocket {
+    #[serde(default)]
+    pub echo: bool,
+    #[serde(default)]
+    pub messages: Vec<WebSocketMessage>,
+    pub close: Option<WebSocketClose>,
+    /// the subprotocol that is accepted when the client asks for it, like `graphql-ws`
+    pub protocol: Option<String>,
+}
+
+/// a message of the server, `text` is a template of the request with `{{message.index}}` and `binary` is base64.
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebSocketMessage {
+    pub text: Option<String>,
+    pub binary: Option<String>,
+    /// the milliseconds after the previous message, or the handshake for the first one
+    pub delay_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebSocketClose {
+    /// the close code, default is 1000
+    pub code: Option<u16>,
+    #[serde(default)]
+    pub reason: String,
+    /// the milliseconds after the last message
+    pub delay_ms: Option<u64>,
+}
+
+/// the `Sec-WebSocket-Accept` of a websocket handshake, `None` when the request is not one.
+pub fn accept_key(request: &Request) -> Option<String> {
+    let upgrade = request.header("Upgrade").is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"));
+    let version = request.header("Sec-WebSocket-Version").is_some_and(|version| version.trim() == "13");
+    if request.method != Method::GET || !upgrade || !version {
+        return None;
+    }
+    let key = request.header("Sec-WebSocket-Key")?.trim();
+    let hash = ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, format!("{}{}", key, GUID).as_bytes());
+    Some(base64::engine::general_purpose::STANDARD.encode(hash.as_ref()))
+}
+
+/// the subprotocol of the route if the client asks for it.
+pub fn protocol<'a>(websocket: &'a WebSocket, request: &Request) -> Option<&'a str> {
+    let protocol = websocket.protocol.as_deref()?;
+    let offered = request.header("Sec-WebSocket-Protocol")?;
+    offered.split(',').any(|offered| offered.trim() == protocol).then_some(protocol)
+}
+
+/// a websocket connection after the handshake and the request values of its templates.
+pub struct Session {
+    websocket: WebSocket,
+    context: Value,
+}
+
+/// a frame of the client, the payload is unmasked.
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+/// the next thing the server sends on its own.
+#[derive(Clone, Copy)]
+enum Step {
+    Message(usize),
+    Close,
+    Done,
+}
+
+impl Session {
+
+    pub fn new(websocket: &WebSocket, request: &Request) -> Session {
+        Session { websocket: websocket.clone(), context: template::context(request) }
+    }
+
+    /// talk to the client until one of the sides closes the connection.
+    pub async fn run(mut self, connection: &mut Connection) -> Result<(), Error> {
+        let mut buffer = Vec::new();
+        // the fragments of a message until its last frame
+        let mut message: Option<(u8, Vec<u8>)> = None;
+        let mut step = self.step(0);
+        let mut due = Instant::now() + self.delay(&step);
+        // the server has sent its `close` and waits for the client's until the timeout, it's started once
+        // so the frames the client sends meanwhile don't push it back
+        let mut closing = false;
+        let close_timeout = tokio::time::sleep(CLOSE_TIMEOUT);
+        tokio::pin!(close_timeout);
+
+        loop {
+            tokio::select! {
+                read = connection.socket.read_buf(&mut buffer) => {
+                    if read? == 0 {
+                        return Ok(());
+                    }
+                    while let Some(parsed) = parse_frame(&mut buffer) {
+                        let Frame { fin, opcode, payload } = match parsed {
+                            Ok(frame) => frame,
+                            Err(code) => return close(connection, code, "").await,
+                        };
+                        match opcode {
+                            CLOSE => {
+                                if !closing {
+                                    // the code of the client is sent back
+                                    let code = payload.get(..2).map(|code| u16::from_be_bytes([code[0], code[1]])).unwrap_or(1000);
+                                    connection.write(&frame(CLOSE, &code.to_be_bytes())).await?;
+                                }
+                                return Ok(());
+                            },
+                            PING => connection.write(&frame(PONG, &payload)).await?,
+                            PONG => {},
+                            _ if closing => {},
+                            TEXT | BINARY if message.is_none() => message = Some((opcode, payload)),
+                            CONTINUATION if message.is_some() => {
+                                if let Some((_, data)) = &mut message {
+                                    data.extend_from_slice(&payload);
+                                }
+                            },
+                            _ => return close(connection, 1002, "unexpected frame").await,
+                        }
+                        if message.as_ref().is_some_and(|(_, data)| data.len() > MAX_MESSAGE_SIZE) {
+                            return close(connection, 1009, "message too big").await;
+                        }
+                        if !fin || !matches!(opcode, TEXT | BINARY | CONTINUATION) {
+                            continue;
+                        }
+                        if let Some((opcode, data)) = message.take() {
+                            if opcode == TEXT && std::str::from_utf8(&data).is_err() {
+                                return close(connection, 1007, "invalid utf-8").await;
+                            }
+                            if self.websocket.echo {
+                                connection.write(&frame(opcode, &data)).await?;
+                            }
+                        }
+                    }
+                },
+                _ = tokio::time::sleep_until(due), if !matches!(step, Step::Done) => {
+                    match step {
+                        Step::Message(index) => {
+                            let (opcode, payload) = self.message(index)?;
+                            connection.write(&frame(opcode, &payload)).await?;
+                            step = self.step(index + 1);
+                            due = Instant::now() + self.delay(&step);
+                        },
+                        Step::Close => {
+                            let close = self.websocket.close.as_ref();
+                            let code = close.and_then(|close| close.code).unwrap_or(1000);
+                            let reason = close.map(|close| close.reason.as_str()).unwrap_or_default();
+                            connection.write(&close_frame(code, reason)).await?;
+                            closing = true;
+                            close_timeout.as_mut().reset(Instant::now() + CLOSE_TIMEOUT);
+                            step = Step::Done;
+                        },
+                        Step::Done => {},
+                    }
+                },
+                _ = &mut close_timeout, if closing => return Ok(()),
+            }
+        }
+    }
+
+    fn step(&self, index: usize) -> Step {
+        if index < self.websocket.messages.len() {
+            Step::Message(index)
+        }else if self.websocket.close.is_some() {
+            Step::Close
+        }else {
+            Step::Done
+        }
+    }
+
+    fn delay(&self, step: &Step) -> Duration {
+        let delay = match step {
+            Step::Message(index) => self.websocket.messages[*index].delay_ms,
+            Step::Close => self.websocket.close.as_ref().and_then(|close| close.delay_ms),
+            Step::Done => None,
+        };
+        Duration::from_millis(delay.unwrap_or(0))
+    }
+
+    /// the opcode and the payload of a message of the route.
+    fn message(&mut self, index: usize) -> Result<(u8, Vec<u8>), Error> {
+        let message = &self.websocket.messages[index];
+        if let Some(binary) = &message.binary {
+            let payload = response::decode_base64(binary).map_err(|e| Error::ConfigValidationError(
+                format!("the binary websocket message is invalid: {}", e)
+            ))?;
+            return Ok((BINARY, payload));
+        }
+        self.context["message"] = json!({ "index": index });
+        let text = template::render_context(message.text.as_deref().unwrap_or_default(), &self.context)?;
+        Ok((TEXT, text.into_bytes()))
+    }
+
+}
+
+/// send a `close` with the code and end the connection.
+async fn close(connection: &mut Connection, code: u16, reason: &str) -> Result<(), Error> {
+    connection.write(&close_frame(code, reason)).await
+}
+
+/// the next frame of the client in the buffer, `None` until all of it is read, or the close code of
+/// a frame that breaks the protocol.
+fn parse_frame(buffer: &mut Vec<u8>) -> Option<Result<Frame, u16>> {
+    if buffer.len() < 2 {
+        return None;
+    }
+    let fin = buffer[0] & 0x80 != 0;
+    let opcode = buffer[0] & 0x0f;
+    let masked = buffer[1] & 0x80 != 0;
+    let (len, mut offset) = match buffer[1] & 0x7f {
+        126 if buffer.len() >= 4 => (u16::from_be_bytes([buffer[2], buffer[3]]) as u64, 4),
+        127 if buffer.len() >= 10 => (u64::from_be_bytes(buffer[2..10].try_into().ok()?), 10),
+        126 | 127 => return None,
+        len => (len as u64, 2),
+    };
+    // the frames of the clients are masked, and the control frames are small and not fragmented
+    if !masked || buffer[0] & 0x70 != 0 || (opcode >= CLOSE && (len > 125 || !fin)) {
+        return Some(Err(1002));
+    }
+    if len > MAX_MESSAGE_SIZE as u64 {
+        return Some(Err(1009));
+    }
+    let len = len as usize;
+    if buffer.len() < offset + 4 + len {
+        return None;
+    }
+    let mask = [buffer[offset], buffer[offset + 1], buffer[offset + 2], buffer[offset + 3]];
+    offset += 4;
+    let payload = buffer[offset..offset + len].iter().enumerate().map(|(i, byte)| byte ^ mask[i % 4]).collect();
+    buffer.drain(..offset + len);
+    Some(Ok(Frame { fin, opcode, payload }))
+}
+
+/// an unmasked frame of the server.
+fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        },
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        },
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn close_frame(code: u16, reason: &str) -> Vec<u8> {
+    let mut payload = code.to_be_bytes().to_vec();
+    // the reason can't make the control frame longer than 125 bytes
+    let mut end = reason.len().min(123);
+    while !reason.is_char_boundary(end) {
+        end -= 1;
+    }
+    payload.extend_from_slice(&reason.as_bytes()[..end]);
+    frame(CLOSE, &payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::request::{Request, RequestLimits};
+    use super::accept_key;
+
+    async fn handshake(method: &str) -> Request {
+        let raw = format!(
+            "{} /ws HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+            method
+        );
+        Request::new(&mut raw.as_bytes(), RequestLimits::default()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn accepts_the_get_handshake() {
+        assert_eq!(accept_key(&handshake("GET").await).as_deref(), Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+    }
+
+    #[tokio::test]
+    async fn does_not_upgrade_head() {
+        assert_eq!(accept_key(&handshake("HEAD").await), None);
+    }
+}
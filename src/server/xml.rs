@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use sxd_xpath::{Context, Factory, XPath};
+use super::request::Request;
+
+/// the envelope of the `soap` routes.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub enum Soap {
+    #[serde(rename = "1.1")]
+    V1_1,
+    #[serde(rename = "1.2")]
+    V1_2,
+}
+
+impl Soap {
+
+    fn namespace(&self) -> &'static str {
+        match self {
+            Soap::V1_1 => "http://schemas.xmlsoap.org/soap/envelope/",
+            Soap::V1_2 => "http://www.w3.org/2003/05/soap-envelope",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Soap::V1_1 => "text/xml; charset=utf-8",
+            Soap::V1_2 => "application/soap+xml; charset=utf-8",
+        }
+    }
+
+    /// put the xml in the `Body` of an envelope, without its `<?xml ...?>` declaration.
+    pub fn wrap(&self, body: &str) -> String {
+        let body = body.trim();
+        let body = match body.strip_prefix("<?xml").and_then(|rest| rest.split_once("?>")) {
+            Some((_, rest)) => rest.trim_start(),
+            None => body,
+        };
+        format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<soap:Envelope xmlns:soap=\"{}\">\n<soap:Body>\n{}\n</soap:Body>\n</soap:Envelope>\n",
+            self.namespace(),
+            body
+        )
+    }
+
+}
+
+/// the action of a soap request, the `SOAPAction` header of soap 1.1 or the `action` of the
+/// `Content-Type` of soap 1.2, without the quotes.
+pub fn soap_action(request: &Request) -> Option<String> {
+    if let Some(action) = request.header("SOAPAction") {
+        return Some(action.trim().trim_matches('"').to_string());
+    }
+    request.header("Content-Type")?
+        .split(';')
+        .filter_map(|parameter| parameter.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("action"))
+        .map(|(_, action)| action.trim().trim_matches('"').to_string())
+}
+
+/// parse an xpath of `body_xpath` to check it before it's used.
+pub fn compile_xpath(expression: &str) -> Result<XPath, String> {
+    match Factory::new().build(expression) {
+        Ok(Some(xpath)) => Ok(xpath),
+        Ok(None) => Err("the xpath is empty".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// whether all of the xpaths are true for the xml body, a body that is not xml doesn't match.
+///
+/// the node sets are true when they're not empty, like `//GetUser/id`, and the comparisons like
+/// `//GetUser/id = '42'` when they hold. the `namespaces` are the prefixes of the xpaths, the elements
+/// of a namespace can be matched without them with `//*[local-name() = 'GetUser']`.
+pub fn matches_xpaths(body: &[u8], xpaths: &[String], namespaces: Option<&HashMap<String, String>>) -> bool {
+    let Some(package) = std::str::from_utf8(body).ok().and_then(|body| sxd_document::parser::parse(body).ok()) else {
+        return false;
+    };
+    let document = package.as_document();
+    let mut context = Context::new();
+    for (prefix, uri) in namespaces.into_iter().flatten() {
+        context.set_namespace(prefix, uri);
+    }
+    xpaths.iter().all(|expression| {
+        compile_xpath(expression)
+            .ok()
+            .and_then(|xpath| xpath.evaluate(&context, document.root()).ok())
+            .is_some_and(|value| value.boolean())
+    })
+}